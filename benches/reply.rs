@@ -0,0 +1,85 @@
+//! Measures how long [Context::reply] takes to walk large values, with the
+//! reply functions of Redis replaced by functions that do nothing, so only the
+//! work done by the crate is measured. Run with `cargo bench --bench reply`.
+
+use std::hint::black_box;
+use std::os::raw::{c_char, c_int, c_long, c_longlong};
+use std::time::{Duration, Instant};
+
+use redis_module::{raw, Context, RedisValue};
+
+const LEN: usize = 1_000_000;
+const RUNS: u32 = 20;
+
+extern "C" fn reply_with_array(_ctx: *mut raw::RedisModuleCtx, len: c_long) -> c_int {
+    black_box(len);
+    raw::REDISMODULE_OK as c_int
+}
+
+extern "C" fn reply_with_string_buffer(
+    _ctx: *mut raw::RedisModuleCtx,
+    buf: *const c_char,
+    len: usize,
+) -> c_int {
+    black_box((buf, len));
+    raw::REDISMODULE_OK as c_int
+}
+
+extern "C" fn reply_with_simple_string(
+    _ctx: *mut raw::RedisModuleCtx,
+    msg: *const c_char,
+) -> c_int {
+    black_box(msg);
+    raw::REDISMODULE_OK as c_int
+}
+
+extern "C" fn reply_with_long_long(_ctx: *mut raw::RedisModuleCtx, ll: c_longlong) -> c_int {
+    black_box(ll);
+    raw::REDISMODULE_OK as c_int
+}
+
+fn bench(name: &str, value: impl Fn() -> RedisValue) {
+    let ctx = Context::dummy();
+    let mut total = Duration::ZERO;
+    for _ in 0..RUNS {
+        let value = value();
+        let start = Instant::now();
+        ctx.reply(Ok(value));
+        total += start.elapsed();
+    }
+    println!("{name:<16} {:?} per reply", total / RUNS);
+}
+
+fn main() {
+    unsafe {
+        raw::RedisModule_ReplyWithArray = Some(reply_with_array);
+        raw::RedisModule_ReplyWithStringBuffer = Some(reply_with_string_buffer);
+        raw::RedisModule_ReplyWithSimpleString = Some(reply_with_simple_string);
+        raw::RedisModule_ReplyWithLongLong = Some(reply_with_long_long);
+    }
+
+    bench("bulk strings", || {
+        RedisValue::Array(
+            (0..LEN)
+                .map(|i| RedisValue::BulkString(i.to_string()))
+                .collect(),
+        )
+    });
+    bench("simple strings", || {
+        RedisValue::Array(
+            (0..LEN)
+                .map(|i| RedisValue::SimpleString(i.to_string()))
+                .collect(),
+        )
+    });
+    bench("integers", || {
+        RedisValue::Array((0..LEN as i64).map(RedisValue::Integer).collect())
+    });
+    bench("nested arrays", || {
+        RedisValue::Array(
+            (0..LEN / 10)
+                .map(|i| RedisValue::Array(vec![RedisValue::Integer(i as i64); 10]))
+                .collect(),
+        )
+    });
+}
@@ -18,6 +18,26 @@ fn get_current_user(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
     Ok(RedisValue::BulkRedisString(ctx.get_current_user()))
 }
 
+fn get_client_cert(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let client_id = args.next_u64()?;
+    Ok(ctx.get_client_cert(client_id).into())
+}
+
+fn get_client_name(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let client_id = args.next_u64()?;
+    Ok(ctx.get_client_name(client_id).into())
+}
+
+fn set_client_name(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let client_id = args.next_u64()?;
+    let name = args.next_arg()?;
+    ctx.set_client_name(client_id, &name)?;
+    Ok(RedisValue::SimpleStringStatic("OK"))
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -28,5 +48,8 @@ redis_module! {
     commands: [
         ["verify_key_access_for_user", verify_key_access_for_user, "", 0, 0, 0],
         ["get_current_user", get_current_user, "", 0, 0, 0],
+        ["get_client_cert", get_client_cert, "", 0, 0, 0],
+        ["get_client_name", get_client_name, "", 0, 0, 0],
+        ["set_client_name", set_client_name, "", 0, 0, 0],
     ],
 }
@@ -0,0 +1,28 @@
+use redis_module::{redis_module, Context, RedisResult, RedisString, RedisValue, Status};
+
+fn get(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
+    Ok(RedisValue::BulkRedisString(ctx.get_current_user()))
+}
+
+fn init(ctx: &Context, _args: &[RedisString]) -> Status {
+    match ctx.set_command_acl_categories("acl_categories.get", &["read", "fast"]) {
+        Ok(()) => Status::Ok,
+        Err(e) => {
+            ctx.log_warning(&e.to_string());
+            Status::Err
+        }
+    }
+}
+
+//////////////////////////////////////////////////////
+
+redis_module! {
+    name: "acl_categories",
+    version: 1,
+    allocator: (redis_module::alloc::RedisAlloc, redis_module::alloc::RedisAlloc),
+    data_types: [],
+    init: init,
+    commands: [
+        ["acl_categories.get", get, "", 0, 0, 0],
+    ],
+}
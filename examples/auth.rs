@@ -0,0 +1,43 @@
+use redis_module::{redis_module, AuthResult, Context, RedisString, Status};
+use std::thread;
+
+fn auth(ctx: &Context, username: &RedisString, password: &RedisString) -> AuthResult {
+    match password.as_slice() {
+        b"module_secret" => AuthResult::Authenticated(username.to_string_lossy()),
+        b"module_deny" => AuthResult::Denied(Some("DENIED by the auth module".to_owned())),
+        b"module_async_secret" | b"module_async_deny" => {
+            let Ok(blocked_client) = ctx.block_client_on_auth() else {
+                return AuthResult::Denied(None);
+            };
+            let username = username.to_string_lossy();
+            let accept = password.as_slice() == b"module_async_secret";
+            thread::spawn(move || {
+                // A slow verification, for example a call to an identity provider.
+                let result = if accept {
+                    AuthResult::Authenticated(username)
+                } else {
+                    AuthResult::Denied(None)
+                };
+                blocked_client.finish(result);
+            });
+            AuthResult::Blocked
+        }
+        _ => AuthResult::Next,
+    }
+}
+
+fn init(ctx: &Context, _args: &[RedisString]) -> Status {
+    ctx.register_auth_callback(auth);
+    Status::Ok
+}
+
+//////////////////////////////////////////////////////
+
+redis_module! {
+    name: "auth",
+    version: 1,
+    allocator: (redis_module::alloc::RedisAlloc, redis_module::alloc::RedisAlloc),
+    data_types: [],
+    init: init,
+    commands: [],
+}
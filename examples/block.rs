@@ -5,6 +5,11 @@ use std::thread;
 use std::time::Duration;
 
 fn block(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
+    // Like BLPOP, reply immediately when the client can not be blocked.
+    if ctx.in_transaction() {
+        return Ok(RedisValue::Null);
+    }
+
     let blocked_client = ctx.block_client();
 
     thread::spawn(move || {
@@ -17,6 +22,10 @@ fn block(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
     Ok(RedisValue::NoReply)
 }
 
+fn block_count(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
+    Ok((ctx.blocked_client_count() as i64).into())
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -26,5 +35,6 @@ redis_module! {
     data_types: [],
     commands: [
         ["block", block, "", 0, 0, 0],
+        ["block.count", block_count, "", 0, 0, 0],
     ],
 }
@@ -0,0 +1,56 @@
+use lazy_static::lazy_static;
+use redis_module::{
+    redis_module, Context, NextArg, RedisError, RedisResult, RedisString, RedisValue,
+};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+lazy_static! {
+    static ref RUNTIME: Runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_time()
+        .build()
+        .unwrap();
+}
+
+fn sleep_add(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let millis = args.next_u64()?;
+    let a = args.next_i64()?;
+    let b = args.next_i64()?;
+    args.done()?;
+
+    let task = async move {
+        tokio::time::sleep(Duration::from_millis(millis)).await;
+        a.checked_add(b)
+    };
+    ctx.block_client_on_task(RUNTIME.handle(), task, |_ctx, sum| {
+        sum.map(RedisValue::Integer)
+            .ok_or(RedisError::Str("ERR integer overflow"))
+    });
+
+    // We will reply once the task completes
+    Ok(RedisValue::NoReply)
+}
+
+fn panic_task(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
+    ctx.block_client_on_task(
+        RUNTIME.handle(),
+        async { panic!("the task failed") },
+        |_ctx, ()| Ok(RedisValue::Null),
+    );
+    Ok(RedisValue::NoReply)
+}
+
+//////////////////////////////////////////////////////
+
+redis_module! {
+    name: "block_async",
+    version: 1,
+    allocator: (redis_module::alloc::RedisAlloc, redis_module::alloc::RedisAlloc),
+    data_types: [],
+    commands: [
+        ["block_async.sleep_add", sleep_add, "", 0, 0, 0],
+        ["block_async.panic", panic_task, "", 0, 0, 0],
+    ],
+}
@@ -4,7 +4,15 @@ use redis_module::{
     ThreadSafeContext,
 };
 
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
+use std::time::Duration;
+
+static HOOKED_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_calls(_command: &str, _duration: Duration, _is_error: bool) {
+    HOOKED_CALLS.fetch_add(1, Ordering::Relaxed);
+}
 
 fn call_test(ctx: &Context, _: Vec<RedisString>) -> RedisResult {
     let res: String = ctx.call("ECHO", &["TEST"])?.try_into()?;
@@ -110,6 +118,33 @@ fn call_test(ctx: &Context, _: Vec<RedisString>) -> RedisResult {
         ));
     }
 
+    // test the read only preset on call_ext
+    let call_options = CallOptionsBuilder::read_only_safe().build();
+    let res: CallResult = ctx.call_ext("SET", &call_options, &["x", "1"]);
+    if let Err(err) = res {
+        let error_msg = err.to_utf8_string().unwrap();
+        if !error_msg.contains("not allowed") {
+            return Err(RedisError::String(format!(
+                "Failed to verify error messages, expected error message to contain 'not allowed', error message: '{error_msg}'",
+            )));
+        }
+    } else {
+        return Err(RedisError::Str("Failed to set no writes on call_ext"));
+    }
+
+    if CallOptionsBuilder::from_flags("!x").is_ok() {
+        return Err(RedisError::Str("Unknown call option flag was accepted"));
+    }
+
+    // test the call hook
+    ctx.set_call_hook(Some(count_calls));
+    ctx.call("ECHO", &["TEST"])?;
+    ctx.set_call_hook(None);
+    ctx.call("ECHO", &["TEST"])?;
+    if HOOKED_CALLS.load(Ordering::Relaxed) != 1 {
+        return Err(RedisError::Str("Call hook was not invoked exactly once"));
+    }
+
     Ok("pass".into())
 }
 
@@ -156,6 +191,16 @@ fn call_blocking_from_detach_ctx(ctx: &Context, _: Vec<RedisString>) -> RedisRes
     Ok(RedisValue::NoReply)
 }
 
+fn call_proxy(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() < 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let command = args[1].try_as_str()?;
+    let command_args: Vec<&RedisString> = args[2..].iter().collect();
+    ctx.call(command, command_args.as_slice())
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -167,5 +212,6 @@ redis_module! {
         ["call.test", call_test, "", 0, 0, 0],
         ["call.blocking", call_blocking, "", 0, 0, 0],
         ["call.blocking_from_detached_ctx", call_blocking_from_detach_ctx, "", 0, 0, 0],
+        ["call.proxy", call_proxy, "", 0, 0, 0],
     ],
 }
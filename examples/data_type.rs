@@ -78,6 +78,15 @@ fn alloc_get(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     Ok(value)
 }
 
+/// Replies like `TYPE key`.
+fn alloc_type(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_arg()?;
+    args.done()?;
+
+    Ok(ctx.open_key(&key).type_name().into())
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -90,5 +99,6 @@ redis_module! {
     commands: [
         ["alloc.set", alloc_set, "write", 1, 1, 1],
         ["alloc.get", alloc_get, "readonly", 1, 1, 1],
+        ["alloc.type", alloc_type, "readonly fast", 1, 1, 1],
     ],
 }
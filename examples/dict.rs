@@ -0,0 +1,135 @@
+use lazy_static::lazy_static;
+use redis_module::{
+    redis_module, Context, Dict, NextArg, RedisError, RedisGILGuard, RedisResult, RedisString,
+    RedisValue, SeekOp, Status,
+};
+
+lazy_static! {
+    static ref INDEX: RedisGILGuard<Option<Dict<Vec<u8>>>> = RedisGILGuard::default();
+}
+
+fn with_index<R>(ctx: &Context, f: impl FnOnce(&mut Dict<Vec<u8>>) -> R) -> R {
+    let mut index = INDEX.lock(ctx);
+    f(index.as_mut().expect("the index is created on module load"))
+}
+
+fn dict_set(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 3 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_arg()?;
+    let value = args.next_arg()?;
+
+    let old = with_index(ctx, |index| index.set(&key, value.to_vec()));
+    Ok(RedisValue::Integer(old.is_none().into()))
+}
+
+fn dict_get(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let value = with_index(ctx, |index| index.get(&args[1]).cloned());
+    Ok(value.map_or(RedisValue::Null, RedisValue::StringBuffer))
+}
+
+fn dict_del(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let deleted = with_index(ctx, |index| index.del(&args[1]));
+    Ok(RedisValue::Integer(deleted.into()))
+}
+
+fn dict_len(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 1 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let len = with_index(ctx, |index| index.len());
+    Ok(RedisValue::Integer(len as i64))
+}
+
+fn dict_range(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 3 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let entries = with_index(ctx, |index| {
+        index
+            .range(&args[1], &args[2])
+            .flat_map(|(key, value)| {
+                [
+                    RedisValue::StringBuffer(key),
+                    RedisValue::StringBuffer(value.clone()),
+                ]
+            })
+            .collect::<Vec<_>>()
+    });
+    Ok(RedisValue::Array(entries))
+}
+
+fn dict_from(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 5 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let op = match args.next_str()? {
+        "^" => SeekOp::First,
+        "$" => SeekOp::Last,
+        ">" => SeekOp::Greater,
+        ">=" => SeekOp::GreaterOrEqual,
+        "<" => SeekOp::Less,
+        "<=" => SeekOp::LessOrEqual,
+        "==" => SeekOp::Equal,
+        _ => return Err(RedisError::Str("ERR invalid seek operator")),
+    };
+    let key = args.next_arg()?;
+    let count = args.next_u64()? as usize;
+    let backwards = match args.next_str()? {
+        "next" => false,
+        "prev" => true,
+        _ => return Err(RedisError::Str("ERR direction must be next or prev")),
+    };
+
+    let keys = with_index(ctx, |index| {
+        let mut iter = index.iter_from(op, &key);
+        let mut keys = Vec::new();
+        while keys.len() < count {
+            let entry = if backwards { iter.prev() } else { iter.next() };
+            match entry {
+                Some((key, _)) => keys.push(RedisValue::StringBuffer(key)),
+                None => break,
+            }
+        }
+        keys
+    });
+    Ok(RedisValue::Array(keys))
+}
+
+fn init(ctx: &Context, _args: &[RedisString]) -> Status {
+    *INDEX.lock(ctx) = Some(ctx.create_dict());
+    Status::Ok
+}
+
+//////////////////////////////////////////////////////
+
+redis_module! {
+    name: "dict",
+    version: 1,
+    allocator: (redis_module::alloc::RedisAlloc, redis_module::alloc::RedisAlloc),
+    data_types: [],
+    init: init,
+    commands: [
+        ["dict.set", dict_set, "write", 0, 0, 0],
+        ["dict.get", dict_get, "readonly", 0, 0, 0],
+        ["dict.del", dict_del, "write", 0, 0, 0],
+        ["dict.len", dict_len, "readonly", 0, 0, 0],
+        ["dict.range", dict_range, "readonly", 0, 0, 0],
+        ["dict.from", dict_from, "readonly", 0, 0, 0],
+    ],
+}
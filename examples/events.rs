@@ -1,8 +1,10 @@
 use redis_module::{
-    redis_module, Context, NotifyEvent, RedisError, RedisResult, RedisString, RedisValue, Status,
+    key::KeyFlags, redis_module, Context, NotifyEvent, RedisError, RedisResult, RedisString,
+    RedisValue, Status,
 };
 use std::ptr::NonNull;
 use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
 
 static NUM_KEY_MISSES: AtomicI64 = AtomicI64::new(0);
 static NUM_KEYS: AtomicI64 = AtomicI64::new(0);
@@ -45,6 +47,32 @@ fn event_send(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     }
 }
 
+fn event_send_module(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    match ctx.notify_keyspace_event(NotifyEvent::MODULE, "myevent", &args[1]) {
+        Status::Ok => Ok("Event sent".into()),
+        Status::Err => Err(RedisError::Str("Generic error")),
+    }
+}
+
+fn event_set_with_ttl(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 4 {
+        return Err(RedisError::WrongArity);
+    }
+
+    // Both writes are published as a single `events.set_with_ttl` event.
+    let ttl = Duration::from_millis(args[3].parse_unsigned_integer()?);
+    let key = ctx.open_key_writable_with_flags(&args[1], KeyFlags::NONOTIFY);
+    key.write(args[2].try_as_str()?)?;
+    key.set_expire(ttl)?;
+    ctx.notify_keyspace_event(NotifyEvent::MODULE, "events.set_with_ttl", &args[1]);
+
+    Ok(RedisValue::SimpleStringStatic("OK"))
+}
+
 fn on_key_miss(_ctx: &Context, _event_type: NotifyEvent, _event: &str, _key: &[u8]) {
     NUM_KEY_MISSES.fetch_add(1, Ordering::SeqCst);
 }
@@ -69,6 +97,8 @@ redis_module! {
     data_types: [],
     commands: [
         ["events.send", event_send, "", 0, 0, 0],
+        ["events.send_module", event_send_module, "", 1, 1, 1],
+        ["events.set_with_ttl", event_set_with_ttl, "write", 1, 1, 1],
         ["events.num_key_miss", num_key_miss, "", 0, 0, 0],
         ["events.num_keys", num_keys, "", 0, 0, 0],
     ],
@@ -1,4 +1,7 @@
-use redis_module::{redis_module, Context, NextArg, RedisError, RedisResult, RedisString};
+use redis_module::{
+    key::{EvictionMetric, KeyFlags},
+    redis_module, Context, NextArg, RedisError, RedisResult, RedisString, RedisValue,
+};
 use std::time::Duration;
 
 fn expire_cmd(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
@@ -17,6 +20,64 @@ fn expire_cmd(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     }
 }
 
+fn idle_cmd(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let key = ctx.open_key_with_flags(&args[1], KeyFlags::NOTOUCH);
+    Ok(RedisValue::Integer(key.idle_time()?.as_secs() as i64))
+}
+
+fn freq_cmd(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let key = ctx.open_key_with_flags(&args[1], KeyFlags::NOTOUCH);
+    Ok(RedisValue::Integer(key.freq()?.into()))
+}
+
+fn eviction_cmd(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let key = ctx.open_key_with_flags(&args[1], KeyFlags::NOTOUCH);
+    let metric = match key.eviction_metric()? {
+        EvictionMetric::NoEviction => "noeviction",
+        EvictionMetric::Lru(_) => "lru",
+        EvictionMetric::Lfu(_) => "lfu",
+    };
+    Ok(metric.into())
+}
+
+fn exists_cmd(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let exists = ctx.open_key(&args[1]).exists();
+    let exists_writable = ctx.open_key_writable(&args[1]).exists();
+    Ok(vec![exists as i64, exists_writable as i64].into())
+}
+
+/// `expire.set key value <ttl_ms | PERSIST | KEEPTTL>`
+fn set_cmd(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key_name = args.next_arg()?;
+    let value = args.next_arg()?;
+    let ttl = args.next_string()?;
+    args.done()?;
+
+    let key = ctx.open_key_writable_with_flags(&key_name, KeyFlags::NONOTIFY);
+    match ttl.to_uppercase().as_str() {
+        "PERSIST" => key.string_set_with_expire(&value, None),
+        "KEEPTTL" => key.string_set_keep_ttl(&value),
+        ttl => key.string_set_with_expire(&value, Some(Duration::from_millis(ttl.parse()?))),
+    }
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -26,5 +87,10 @@ redis_module! {
     data_types: [],
     commands: [
         ["expire.cmd", expire_cmd, "write fast deny-oom", 1, 1, 1],
+        ["expire.idle", idle_cmd, "readonly fast", 1, 1, 1],
+        ["expire.freq", freq_cmd, "readonly fast", 1, 1, 1],
+        ["expire.eviction", eviction_cmd, "readonly", 1, 1, 1],
+        ["expire.exists", exists_cmd, "write fast", 1, 1, 1],
+        ["expire.set", set_cmd, "write fast deny-oom", 1, 1, 1],
     ],
 }
@@ -1,6 +1,11 @@
 use redis_module::{
-    redis_module, Context, NextArg, RedisError, RedisResult, RedisString, RedisValue,
+    redis_module, Context, NextArg, RedisError, RedisResult, RedisString, RedisValue, Status,
 };
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+static ACTIVE_SESSIONS: OnceLock<Arc<AtomicI64>> = OnceLock::new();
 
 fn info_cmd(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     if args.len() < 3 {
@@ -18,6 +23,22 @@ fn info_cmd(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
         .map_or(RedisValue::Null, RedisValue::BulkRedisString))
 }
 
+fn session_open(_ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
+    let sessions = ACTIVE_SESSIONS.get().unwrap();
+    Ok(RedisValue::Integer(
+        sessions.fetch_add(1, Ordering::Relaxed) + 1,
+    ))
+}
+
+fn init(ctx: &Context, _args: &[RedisString]) -> Status {
+    let sessions = Arc::clone(ACTIVE_SESSIONS.get_or_init(Default::default));
+    ctx.register_info_gauge("sessions", "active_sessions", move || {
+        sessions.load(Ordering::Relaxed)
+    });
+    ctx.register_info_gauge("sessions", "max_sessions", || 100);
+    Status::Ok
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -25,7 +46,9 @@ redis_module! {
     version: 1,
     allocator: (redis_module::alloc::RedisAlloc, redis_module::alloc::RedisAlloc),
     data_types: [],
+    init: init,
     commands: [
         ["infoex", info_cmd, "", 0, 0, 0],
+        ["info.session_open", session_open, "", 0, 0, 0],
     ],
 }
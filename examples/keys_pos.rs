@@ -1,4 +1,6 @@
-use redis_module::{redis_module, Context, RedisError, RedisResult, RedisString, RedisValue};
+use redis_module::{
+    commands::KeySpecFlags, redis_module, Context, RedisError, RedisResult, RedisString, RedisValue,
+};
 
 fn keys_pos(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     // Number of args (excluding command name) must be even
@@ -20,6 +22,35 @@ fn keys_pos(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     Ok(reply.into())
 }
 
+fn keys_of(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() < 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let command: Vec<&[u8]> = args[1..].iter().map(|arg| arg.as_slice()).collect();
+    let positions = ctx.get_command_keys(&command)?;
+    Ok(positions
+        .into_iter()
+        .map(|pos| RedisValue::Integer(pos as i64))
+        .collect::<Vec<_>>()
+        .into())
+}
+
+fn read_only_keys_of(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() < 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let command: Vec<&[u8]> = args[1..].iter().map(|arg| arg.as_slice()).collect();
+    let keys = ctx.get_command_keys_with_flags(&command)?;
+    Ok(keys
+        .into_iter()
+        .filter(|(_, flags)| flags.contains(KeySpecFlags::READ_ONLY))
+        .map(|(pos, _)| RedisValue::Integer(pos as i64))
+        .collect::<Vec<_>>()
+        .into())
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -29,5 +60,7 @@ redis_module! {
     data_types: [],
     commands: [
         ["keys_pos", keys_pos, "getkeys-api", 1, 1, 1],
+        ["keys_pos.of", keys_of, "readonly", 0, 0, 0],
+        ["keys_pos.read_only_of", read_only_keys_of, "readonly", 0, 0, 0],
     ],
 }
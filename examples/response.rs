@@ -59,6 +59,257 @@ fn map_unique(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     Ok(res)
 }
 
+fn map_entries(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() < 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key_name = args.next_arg()?;
+
+    let fields: Vec<RedisString> = args.collect();
+
+    let key = ctx.open_key(&key_name);
+    let values = key.hash_get_multi(&fields)?;
+    let res = match values {
+        None => RedisValue::Null,
+        Some(values) => RedisValue::MapEntries(
+            values
+                .into_iter()
+                .map(|(field, value)| {
+                    (
+                        RedisValueKey::BulkRedisString(field),
+                        RedisValue::BulkRedisString(value),
+                    )
+                })
+                .collect(),
+        ),
+    };
+
+    Ok(res)
+}
+
+fn empty_or_null(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let kind = args.next_str()?;
+    args.done()?;
+
+    match kind {
+        "empty" => {
+            ctx.reply_empty_string();
+            Ok(RedisValue::NoReply)
+        }
+        "bulk" => Ok(RedisValue::BulkString(String::new())),
+        "buffer" => Ok(RedisValue::StringBuffer(Vec::new())),
+        "null" => Ok(RedisValue::Null),
+        _ => Err(RedisError::Str("unknown reply kind")),
+    }
+}
+
+fn reply_range(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let len = args.next_i64()?;
+    args.done()?;
+
+    let values: Vec<i64> = (0..len).collect();
+    ctx.reply_i64_slice(&values);
+    Ok(RedisValue::NoReply)
+}
+
+/// An iterator that claims `len` values but may yield fewer.
+struct ClaimedLen {
+    len: usize,
+    values: std::ops::Range<i64>,
+}
+
+impl Iterator for ClaimedLen {
+    type Item = RedisValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.values.next().map(RedisValue::Integer)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl ExactSizeIterator for ClaimedLen {}
+
+/// Replies `0..len` from an exact size iterator, which only yields `yielded`
+/// values when given.
+fn reply_exact_iter(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let len = args.next_u64()?;
+    let yielded = match args.next() {
+        Some(arg) => arg.parse_unsigned_integer()?,
+        None => len,
+    };
+    args.done()?;
+
+    ctx.reply_from_exact_iter(ClaimedLen {
+        len: len as usize,
+        values: 0..yielded.min(len) as i64,
+    });
+    Ok(RedisValue::NoReply)
+}
+
+fn reply_double(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let value = args.next_f64()?;
+    let precision = args.next_u64()?;
+    args.done()?;
+
+    ctx.reply_double_fmt(value, precision as usize);
+    Ok(RedisValue::NoReply)
+}
+
+fn reply_null_array(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 1 {
+        return Err(RedisError::WrongArity);
+    }
+
+    ctx.reply_null_array();
+    Ok(RedisValue::NoReply)
+}
+
+fn reply_pairs(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let pairs = args[1..].chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(RedisError::WrongArity);
+    }
+
+    let entries = pairs
+        .map(|pair| (pair[0].clone().into(), pair[1].clone().into()))
+        .collect();
+    ctx.reply_with_map(entries);
+    Ok(RedisValue::NoReply)
+}
+
+fn reply_flat_pairs(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let args = &args[1..];
+    let mut pairs = args.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(RedisError::WrongArity);
+    }
+
+    ctx.reply_flat_pairs(pairs.map(|pair| (&pair[0], &pair[1])));
+    Ok(RedisValue::NoReply)
+}
+
+fn reply_raw(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let resp = args.next_arg()?;
+    args.done()?;
+
+    ctx.reply_raw_protocol(resp.as_slice())?;
+    Ok(RedisValue::NoReply)
+}
+
+/// An error type of the module, replied with [Context::reply_with].
+enum ParseError {
+    Empty,
+    NotANumber(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "EMPTY no value given"),
+            Self::NotANumber(s) => write!(f, "'{s}' is not a number"),
+        }
+    }
+}
+
+fn parse_number(s: &str) -> Result<RedisValue, ParseError> {
+    if s.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    s.parse::<i64>()
+        .map(RedisValue::Integer)
+        .map_err(|_| ParseError::NotANumber(s.to_owned()))
+}
+
+fn reply_with(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let value = args.next_arg()?;
+    args.done()?;
+
+    ctx.reply_with(parse_number(&value.to_string_lossy()));
+    Ok(RedisValue::NoReply)
+}
+
+/// Fails with an error carrying the limit as a detail for RESP3 clients.
+fn reply_error_details(_ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let limit = args.next_string()?;
+    args.done()?;
+
+    Err(RedisError::with_details(
+        "INDEX_FULL",
+        "the index is full",
+        [("limit", limit)],
+    ))
+}
+
+/// Replies with an integer nested in `depth` arrays, optionally setting the
+/// maximal reply depth first.
+fn reply_nested(_ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let depth = args.next_u64()?;
+    let max_depth = args
+        .next()
+        .map(|arg| arg.parse_unsigned_integer())
+        .transpose()?;
+    args.done()?;
+
+    if let Some(max_depth) = max_depth {
+        Context::set_max_reply_depth(max_depth as usize);
+    }
+    let mut value = RedisValue::Integer(1);
+    for _ in 0..depth {
+        value = RedisValue::Array(vec![value]);
+    }
+    Ok(value)
+}
+
+fn reply_deferred(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let kind = args.next_string()?;
+    let len = args.next_i64()?;
+    args.done()?;
+
+    let mut reply = match kind.as_str() {
+        "array" => ctx.reply_array_deferred(),
+        "map" => ctx.reply_map_deferred(),
+        "set" => ctx.reply_set_deferred(),
+        _ => return Err(RedisError::Str("unknown reply kind")),
+    };
+    for i in 0..len {
+        if kind == "map" {
+            reply.reply_entry(RedisValue::Integer(i), Ok(RedisValue::Integer(i * i)));
+        } else {
+            reply.reply(Ok(RedisValue::Integer(i)));
+        }
+    }
+    reply.finish();
+    Ok(RedisValue::NoReply)
+}
+
+fn reply_bool(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let as_int = args.next_str()? == "int";
+    let value = args.next_i64()? != 0;
+    args.done()?;
+
+    if as_int {
+        ctx.reply_bool_as_int(value);
+        Ok(RedisValue::NoReply)
+    } else {
+        Ok(RedisValue::Bool(value))
+    }
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -69,5 +320,19 @@ redis_module! {
     commands: [
         ["map.mget", map_mget, "readonly", 1, 1, 1],
         ["map.unique", map_unique, "readonly", 1, 1, 1],
+        ["map.entries", map_entries, "readonly", 1, 1, 1],
+        ["reply.empty_or_null", empty_or_null, "", 0, 0, 0],
+        ["reply.range", reply_range, "", 0, 0, 0],
+        ["reply.exact_iter", reply_exact_iter, "", 0, 0, 0],
+        ["reply.pairs", reply_pairs, "", 0, 0, 0],
+        ["reply.flat_pairs", reply_flat_pairs, "", 0, 0, 0],
+        ["reply.double", reply_double, "", 0, 0, 0],
+        ["reply.bool", reply_bool, "", 0, 0, 0],
+        ["reply.null_array", reply_null_array, "", 0, 0, 0],
+        ["reply.deferred", reply_deferred, "", 0, 0, 0],
+        ["reply.raw", reply_raw, "", 0, 0, 0],
+        ["reply.with", reply_with, "", 0, 0, 0],
+        ["reply.error_details", reply_error_details, "", 0, 0, 0],
+        ["reply.nested", reply_nested, "", 0, 0, 0],
     ],
 }
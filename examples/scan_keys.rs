@@ -1,21 +1,54 @@
 use redis_module::{
-    key::RedisKey, redis_module, Context, KeysCursor, RedisResult, RedisString, RedisValue,
+    key::RedisKey, redis_module, Context, KeysCursor, NextArg, RedisResult, RedisString, RedisValue,
 };
+use std::cell::RefCell;
+
+thread_local! {
+    // A cursor kept between commands, commands are always called on the main thread.
+    static STEP_CURSOR: RefCell<Option<KeysCursor>> = const { RefCell::new(None) };
+}
 
 fn scan_keys(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
     let cursor = KeysCursor::new();
     let mut res = Vec::new();
 
-    let scan_callback = |_ctx: &Context, key_name: RedisString, _key: Option<&RedisKey>| {
+    let mut scan_callback = |_ctx: &Context, key_name: RedisString, _key: Option<&RedisKey>| {
         res.push(RedisValue::BulkRedisString(key_name));
     };
 
-    while cursor.scan(ctx, &scan_callback) {
+    while cursor.scan(ctx, &mut scan_callback) {
         // do nothing
     }
     Ok(RedisValue::Array(res))
 }
 
+/// Scan a few batches of keys with a cursor that is kept between calls.
+/// Reply with whether the scan is done followed by the keys.
+fn scan_keys_step(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let batches = args.next_u64()?;
+
+    let mut res = vec![RedisValue::Integer(0)];
+    let mut scan_callback = |_ctx: &Context, key_name: RedisString, _key: Option<&RedisKey>| {
+        res.push(RedisValue::BulkRedisString(key_name));
+    };
+
+    let done = STEP_CURSOR.with(|cursor| {
+        let mut cursor = cursor.borrow_mut();
+        let keys_cursor = cursor.get_or_insert_with(KeysCursor::new);
+        for _ in 0..batches {
+            if !keys_cursor.scan(ctx, &mut scan_callback) {
+                *cursor = None;
+                return true;
+            }
+        }
+        false
+    });
+
+    res[0] = RedisValue::Integer(done.into());
+    Ok(RedisValue::Array(res))
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -25,5 +58,6 @@ redis_module! {
     data_types: [],
     commands: [
         ["scan_keys", scan_keys, "readonly", 0, 0, 0],
+        ["scan_keys.step", scan_keys_step, "readonly", 0, 0, 0],
     ],
 }
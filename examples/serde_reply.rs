@@ -0,0 +1,54 @@
+use redis_module::{
+    redis_module, Context, NextArg, RedisError, RedisResult, RedisString, RedisValue,
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+enum Role {
+    Admin,
+    Guest { expires: u64 },
+}
+
+#[derive(Serialize)]
+struct User {
+    name: String,
+    age: u32,
+    tags: Vec<&'static str>,
+    nickname: Option<String>,
+    role: Role,
+}
+
+fn serde_user(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let name = args.next_string()?;
+
+    let user = User {
+        role: if name == "admin" {
+            Role::Admin
+        } else {
+            Role::Guest { expires: 60 }
+        },
+        name,
+        age: 42,
+        tags: vec!["a", "b"],
+        nickname: None,
+    };
+    ctx.reply_serialized(&user);
+    Ok(RedisValue::NoReply)
+}
+
+//////////////////////////////////////////////////////
+
+redis_module! {
+    name: "serde_reply",
+    version: 1,
+    allocator: (redis_module::alloc::RedisAlloc, redis_module::alloc::RedisAlloc),
+    data_types: [],
+    commands: [
+        ["serde.user", serde_user, "readonly", 0, 0, 0],
+    ],
+}
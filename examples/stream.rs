@@ -1,4 +1,5 @@
 use redis_module::raw::{KeyType, RedisModuleStreamID};
+use redis_module::stream::{StreamAddId, StreamAddOptions, StreamId};
 use redis_module::{
     redis_module, Context, NextArg, RedisError, RedisResult, RedisString, RedisValue,
 };
@@ -26,13 +27,108 @@ fn stream_read_from(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     );
 
     let stream = ctx.open_key_writable(&stream_key);
-    stream.trim_stream_by_id(id_to_keep, false)?;
+    stream.stream_trim_by_id(id_to_keep.into(), false)?;
     Ok(match element {
         Some(e) => RedisValue::BulkString(format!("{}-{}", e.id.ms, e.id.seq)),
         None => RedisValue::Null,
     })
 }
 
+/// Replies like `XRANGE key start end`.
+fn stream_range(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let stream_key = args.next_arg()?;
+    let start: StreamId = args.next_str()?.parse()?;
+    let end: StreamId = args.next_str()?.parse()?;
+    args.done()?;
+
+    let stream = ctx.open_key(&stream_key);
+    let entries = stream
+        .stream_iter(start, end)?
+        .map(|(id, fields)| {
+            let fields = fields
+                .into_iter()
+                .flat_map(|(field, value)| [RedisValue::from(field), RedisValue::from(value)])
+                .collect();
+            RedisValue::Array(vec![id.to_string().into(), RedisValue::Array(fields)])
+        })
+        .collect();
+    Ok(RedisValue::Array(entries))
+}
+
+/// Replies like `XADD key [NOMKSTREAM] [MAXLEN [~] count] <* | id> field value ...`.
+fn stream_add(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1).peekable();
+    let stream_key = args.next_arg()?;
+
+    let mut options = StreamAddOptions::default();
+    let id = loop {
+        let arg = args.next_arg()?;
+        match arg.to_string_lossy().to_uppercase().as_str() {
+            "NOMKSTREAM" => options = options.no_mkstream(),
+            "MAXLEN" => {
+                let approx = args.peek().is_some_and(|arg| arg.as_slice() == b"~");
+                if approx {
+                    args.next();
+                }
+                options = options.max_len(args.next_u64()? as usize, approx);
+            }
+            "*" => break StreamAddId::Auto,
+            id => break StreamAddId::Explicit(id.parse()?),
+        }
+    };
+    let args: Vec<RedisString> = args.collect();
+    if args.is_empty() || args.len() % 2 != 0 {
+        return Err(RedisError::WrongArity);
+    }
+    let fields: Vec<(&RedisString, &RedisString)> = args
+        .chunks_exact(2)
+        .map(|pair| (&pair[0], &pair[1]))
+        .collect();
+
+    let stream = ctx.open_key_writable(&stream_key);
+    Ok(stream
+        .stream_add_with_options(id, &fields, options)?
+        .map_or(RedisValue::Null, |id| id.to_string().into()))
+}
+
+/// Replies like `XTRIM key <MAXLEN | MINID> [~] threshold`.
+fn stream_trim(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let mut args = args.into_iter().skip(1).peekable();
+    let stream_key = args.next_arg()?;
+    let strategy = args.next_string()?.to_uppercase();
+    let approx = args.peek().is_some_and(|arg| arg.as_slice() == b"~");
+    if approx {
+        args.next();
+    }
+    let threshold = args.next_str()?;
+    args.done()?;
+
+    let stream = ctx.open_key_writable(&stream_key);
+    let removed = match strategy.as_str() {
+        "MAXLEN" => stream.stream_trim_by_length(threshold.parse()?, approx)?,
+        "MINID" => stream.stream_trim_by_id(threshold.parse()?, approx)?,
+        _ => return Err(RedisError::Str("ERR syntax error")),
+    };
+    Ok(RedisValue::Integer(removed as i64))
+}
+
+/// Replies like `XDEL key id ...`.
+fn stream_del(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() < 3 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let stream = ctx.open_key_writable(&args[1]);
+    let mut deleted = 0;
+    for id in &args[2..] {
+        if stream.stream_delete(id.try_as_str()?.parse()?)? {
+            deleted += 1;
+        }
+    }
+    Ok(RedisValue::Integer(deleted))
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -42,5 +138,9 @@ redis_module! {
     data_types: [],
     commands: [
         ["STREAM_POP", stream_read_from, "write", 1, 1, 1],
+        ["STREAM_RANGE", stream_range, "readonly", 1, 1, 1],
+        ["STREAM_ADD", stream_add, "write deny-oom", 1, 1, 1],
+        ["STREAM_TRIM", stream_trim, "write", 1, 1, 1],
+        ["STREAM_DEL", stream_del, "write fast", 1, 1, 1],
     ],
 }
@@ -1,6 +1,8 @@
+use redis_module::key::RedisKey;
 use redis_module::{
     redis_module, Context, NextArg, RedisError, RedisResult, RedisString, RedisValue,
 };
+use std::rc::Rc;
 
 fn string_set(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     if args.len() < 3 {
@@ -32,6 +34,144 @@ fn string_get(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     Ok(res)
 }
 
+fn string_getrange(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 4 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key_name = args.next_arg()?;
+    let start = args.next_i64()?;
+    let end = args.next_i64()?;
+
+    let key = ctx.open_key(&key_name);
+    Ok(RedisValue::StringBuffer(key.string_range(start, end)?))
+}
+
+fn string_setrange(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 4 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key_name = args.next_arg()?;
+    let offset = args.next_u64()?;
+    let value = args.next_arg()?;
+
+    let key = ctx.open_key_writable(&key_name);
+    let len = key.set_range(offset as usize, value.as_slice())?;
+    Ok(RedisValue::Integer(len as i64))
+}
+
+fn string_getbit(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 3 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key_name = args.next_arg()?;
+    let offset = args.next_u64()?;
+
+    let key = ctx.open_key(&key_name);
+    Ok(RedisValue::Integer(key.get_bit(offset)?.into()))
+}
+
+fn string_setbit(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 4 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key_name = args.next_arg()?;
+    let offset = args.next_u64()?;
+    let value = match args.next_i64()? {
+        0 => false,
+        1 => true,
+        _ => return Err(RedisError::Str("ERR bit is not an integer or out of range")),
+    };
+
+    let key = ctx.open_key_writable(&key_name);
+    Ok(RedisValue::Integer(key.set_bit(offset, value)?.into()))
+}
+
+fn string_bitcount(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    let with_range = match args.len() {
+        2 => false,
+        4 => true,
+        _ => return Err(RedisError::WrongArity),
+    };
+
+    let mut args = args.into_iter().skip(1);
+    let key_name = args.next_arg()?;
+    let (start, end) = if with_range {
+        (args.next_i64()?, args.next_i64()?)
+    } else {
+        (0, -1)
+    };
+
+    let key = ctx.open_key(&key_name);
+    Ok(RedisValue::Integer(key.bit_count(start, end)? as i64))
+}
+
+/// Reads the key twice through the cached handle, then overwrites it with `CALL`
+/// and reads it again. Replies with the value before the write, whether the
+/// handle was reused, and the value after the write.
+fn string_cached(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 3 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key_name = args.next_arg()?;
+    let value = args.next_arg()?;
+
+    let read = |key: &RedisKey| -> RedisResult {
+        Ok(key
+            .read()?
+            .map_or(RedisValue::Null, |v| RedisValue::StringBuffer(Vec::from(v))))
+    };
+
+    let key = ctx.open_key_cached(&key_name);
+    let before = read(&key)?;
+    let reused = Rc::ptr_eq(&key, &ctx.open_key_cached(&key_name));
+    drop(key);
+
+    ctx.call("SET", &[&key_name, &value])?;
+    let after = read(&ctx.open_key_cached(&key_name))?;
+    Ok(vec![before, RedisValue::Bool(reused), after].into())
+}
+
+/// Opens the key for writing, reads it through the cached handle, writes it and
+/// reads it again. Then closes the writable handle and reads it twice. Replies
+/// with the values read while the key was open for writing, whether the handle
+/// was reused once it was closed, and the value read then.
+fn string_cached_write(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 3 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key_name = args.next_arg()?;
+    let value = args.next_arg()?;
+
+    let read = |key: &RedisKey| -> RedisResult {
+        Ok(key
+            .read()?
+            .map_or(RedisValue::Null, |v| RedisValue::StringBuffer(Vec::from(v))))
+    };
+
+    let writable = ctx.open_key_writable(&key_name);
+    let before = read(&ctx.open_key_cached(&key_name))?;
+    writable.as_string_dma()?.write(value.as_slice())?;
+    let written = read(&ctx.open_key_cached(&key_name))?;
+    drop(writable);
+
+    let key = ctx.open_key_cached(&key_name);
+    let reused = Rc::ptr_eq(&key, &ctx.open_key_cached(&key_name));
+    let after = read(&key)?;
+    Ok(vec![before, written, RedisValue::Bool(reused), after].into())
+}
+
 //////////////////////////////////////////////////////
 
 redis_module! {
@@ -42,5 +182,12 @@ redis_module! {
     commands: [
         ["string.set", string_set, "write fast deny-oom", 1, 1, 1],
         ["string.get", string_get, "readonly", 1, 1, 1],
+        ["string.getrange", string_getrange, "readonly", 1, 1, 1],
+        ["string.setrange", string_setrange, "write deny-oom", 1, 1, 1],
+        ["string.getbit", string_getbit, "readonly fast", 1, 1, 1],
+        ["string.setbit", string_setbit, "write deny-oom", 1, 1, 1],
+        ["string.bitcount", string_bitcount, "readonly", 1, 1, 1],
+        ["string.cached", string_cached, "write deny-oom", 1, 1, 1],
+        ["string.cached_write", string_cached_write, "write deny-oom", 1, 1, 1],
     ],
 }
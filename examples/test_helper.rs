@@ -1,5 +1,8 @@
-use redis_module::{redis_module, Context, RedisError, RedisResult, RedisString};
+use redis_module::{redis_module, Context, RedisError, RedisResult, RedisString, RedisValue};
 use redis_module::{InfoContext, Status};
+use std::cell::Cell;
+use std::thread;
+use std::time::Duration;
 
 fn test_helper_version(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
     let ver = ctx.get_redis_version()?;
@@ -15,6 +18,13 @@ fn test_helper_version_rm_call(ctx: &Context, _args: Vec<RedisString>) -> RedisR
     Ok(response.into())
 }
 
+fn test_helper_version_cached(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
+    let ver = ctx.get_redis_version_cached()?;
+    let response: Vec<i64> = vec![ver.major.into(), ver.minor.into(), ver.patch.into()];
+
+    Ok(response.into())
+}
+
 fn test_helper_command_name(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
     Ok(ctx.current_command_name()?.into())
 }
@@ -30,6 +40,79 @@ fn test_helper_err(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
     Ok(().into())
 }
 
+fn test_helper_err_code(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 3 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let code = args[1].try_as_str()?;
+    let msg = args[2].try_as_str()?;
+
+    ctx.reply_error_with_code(code, msg);
+    Ok(RedisValue::NoReply)
+}
+
+fn test_helper_numbers(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
+    Ok(vec![
+        ctx.create_string_from_i64(-42),
+        ctx.create_string_from_f64(1.5),
+        ctx.create_string_from_f64(f64::INFINITY),
+    ]
+    .into())
+}
+
+/// Replies `chunks` chunks of `chunk_len` bytes, declaring `total_len` bytes,
+/// `chunks * chunk_len` by default. With `FAIL`, returns an error afterwards.
+fn test_helper_bulk_chunks(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if !(3..=5).contains(&args.len()) {
+        return Err(RedisError::WrongArity);
+    }
+
+    let chunks = args[1].parse_integer()?;
+    let chunk_len = args[2].parse_integer()?;
+    let total_len = match args.get(3) {
+        Some(arg) => arg.parse_integer()?,
+        None => chunks * chunk_len,
+    };
+    if chunks < 0 || chunk_len < 0 || total_len < 0 {
+        return Err(RedisError::Str("ERR invalid length"));
+    }
+
+    let chunk: Vec<u8> = (0..chunk_len).map(|i| b'a' + (i % 26) as u8).collect();
+    let mut bulk = ctx.reply_bulk_begin(total_len as usize);
+    for _ in 0..chunks {
+        bulk.chunk(&chunk);
+    }
+    if args.get(4).is_some_and(|arg| arg.as_slice() == b"FAIL") {
+        return Err(RedisError::Str("ERR failed after the bulk reply began"));
+    }
+    Ok(RedisValue::NoReply)
+}
+
+fn test_helper_latency(ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+    if args.len() != 2 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let millis = args[1].parse_unsigned_integer()?;
+    let elapsed = Cell::new(Duration::ZERO);
+    let start = ctx.microseconds();
+    {
+        let _timer = ctx.latency_timer(|d| elapsed.set(d));
+        thread::sleep(Duration::from_millis(millis));
+    }
+    let micros = ctx.microseconds() - start;
+    Ok(vec![elapsed.get().as_micros() as i64, micros as i64].into())
+}
+
+fn test_helper_module_identity(ctx: &Context, _args: Vec<RedisString>) -> RedisResult {
+    Ok(vec![
+        RedisValue::BulkString(ctx.module_name().to_owned()),
+        RedisValue::Integer(ctx.module_version().into()),
+    ]
+    .into())
+}
+
 fn add_info(ctx: &InfoContext, _for_crash_report: bool) {
     if ctx.add_info_section(Some("test_helper")) == Status::Ok {
         ctx.add_info_field_str("field", "value");
@@ -47,7 +130,13 @@ redis_module! {
     commands: [
         ["test_helper.version", test_helper_version, "", 0, 0, 0],
         ["test_helper._version_rm_call", test_helper_version_rm_call, "", 0, 0, 0],
+        ["test_helper._version_cached", test_helper_version_cached, "", 0, 0, 0],
         ["test_helper.name", test_helper_command_name, "", 0, 0, 0],
         ["test_helper.err", test_helper_err, "", 0, 0, 0],
+        ["test_helper.err_code", test_helper_err_code, "", 0, 0, 0],
+        ["test_helper.numbers", test_helper_numbers, "", 0, 0, 0],
+        ["test_helper.bulk_chunks", test_helper_bulk_chunks, "", 0, 0, 0],
+        ["test_helper.latency", test_helper_latency, "", 0, 0, 0],
+        ["test_helper.module_identity", test_helper_module_identity, "", 0, 0, 0],
     ],
 }
@@ -21,6 +21,8 @@ lazy_static::lazy_static! {
         ("RedisModule_ACLAddLogEntryByUserName".to_string(), 70200),
         ("RedisModule_GetCommand".to_string(), 70000),
         ("RedisModule_SetCommandInfo".to_string(), 70000),
+        ("RedisModule_GetClientNameById".to_string(), 70000),
+        ("RedisModule_SetClientNameById".to_string(), 70000),
 
     ]);
 
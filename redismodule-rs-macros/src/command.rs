@@ -370,7 +370,7 @@ pub(crate) fn redis_command(attr: TokenStream, item: TokenStream) -> TokenStream
 
             let args = redis_module::decode_args(ctx, argv, argc);
             let response = #original_function_name(&context, args);
-            context.reply(response.map(|v| v.into())) as i32
+            context.reply_command_result(response.map(|v| v.into())) as i32
         }
 
         #[linkme::distributed_slice(redis_module::commands::COMMANDS_LIST)]
@@ -81,6 +81,10 @@ mod redis_value;
 /// ```
 ///
 /// **Notice**, by default Redis does not validate the command spec. User should validate the command keys on the module command code. The command spec is used for validation on cluster so Redis can raise a cross slot error when needed.
+///
+/// **Notice**, reply schemas can not be specified. `RedisModule_SetCommandInfo` has no field for them,
+/// Redis only has reply schemas for its own commands, so `COMMAND DOCS` never shows a reply schema for
+/// module commands.
 #[proc_macro_attribute]
 pub fn command(attr: TokenStream, item: TokenStream) -> TokenStream {
     command::redis_command(attr, item)
@@ -0,0 +1,182 @@
+use std::os::raw::{c_int, c_void};
+use std::ptr::{self, NonNull};
+use std::sync::Mutex;
+
+use redis_module_macros_internals::api;
+
+use crate::{raw, Context, RedisError, RedisString};
+
+/// The result of an [AuthCallback].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthResult {
+    /// Authenticate the client as the given ACL user.
+    Authenticated(String),
+    /// Fail the authentication. Without a message the client gets the standard
+    /// `WRONGPASS` error.
+    Denied(Option<String>),
+    /// The callback does not handle this username, let the next registered
+    /// callback, or eventually Redis' own password authentication, decide.
+    Next,
+    /// The client was blocked with [Context::block_client_on_auth], the result
+    /// is given later with [AuthBlockedClient::finish].
+    Blocked,
+}
+
+/// A callback invoked on `AUTH` and `HELLO AUTH` with the given username and password.
+pub type AuthCallback =
+    fn(ctx: &Context, username: &RedisString, password: &RedisString) -> AuthResult;
+
+/// All the callbacks registered by the module, tried in registration order.
+static AUTH_CALLBACKS: Mutex<Vec<AuthCallback>> = Mutex::new(Vec::new());
+
+fn set_auth_error(err: *mut *mut raw::RedisModuleString, msg: &str) {
+    // Redis takes ownership of the error string.
+    unsafe { *err = RedisString::create_from_slice(ptr::null_mut(), msg.as_bytes()).take() };
+}
+
+extern "C" fn auth_callback(
+    ctx: *mut raw::RedisModuleCtx,
+    username: *mut raw::RedisModuleString,
+    password: *mut raw::RedisModuleString,
+    err: *mut *mut raw::RedisModuleString,
+) -> c_int {
+    let context = Context::new(ctx);
+    let username = RedisString::new(NonNull::new(ctx), username);
+    let password = RedisString::new(NonNull::new(ctx), password);
+
+    // Copy the callbacks so a callback can register more callbacks.
+    let callbacks = AUTH_CALLBACKS.lock().unwrap().clone();
+    for callback in callbacks {
+        match callback(&context, &username, &password) {
+            AuthResult::Next => continue,
+            result => return apply_auth_result(ctx, result, err),
+        }
+    }
+    raw::REDISMODULE_AUTH_NOT_HANDLED as c_int
+}
+
+fn apply_auth_result(
+    ctx: *mut raw::RedisModuleCtx,
+    result: AuthResult,
+    err: *mut *mut raw::RedisModuleString,
+) -> c_int {
+    match result {
+        AuthResult::Next => return raw::REDISMODULE_AUTH_NOT_HANDLED as c_int,
+        AuthResult::Authenticated(user) => {
+            let status: raw::Status = unsafe {
+                raw::RedisModule_AuthenticateClientWithACLUser.unwrap()(
+                    ctx,
+                    user.as_ptr().cast(),
+                    user.len(),
+                    None,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                )
+            }
+            .into();
+            if status == raw::Status::Err {
+                set_auth_error(
+                    err,
+                    &format!("ERR user '{user}' does not exist or is disabled"),
+                );
+            }
+        }
+        AuthResult::Denied(Some(msg)) => set_auth_error(err, &msg),
+        AuthResult::Denied(None) | AuthResult::Blocked => (),
+    }
+    raw::REDISMODULE_AUTH_HANDLED as c_int
+}
+
+/// A client blocked during `AUTH` by [Context::block_client_on_auth]. Its
+/// commands are not processed until the result is given with [Self::finish],
+/// which can be done from any thread. Dropping it denies the authentication.
+pub struct AuthBlockedClient {
+    inner: *mut raw::RedisModuleBlockedClient,
+}
+
+// The blocked client is meant to be finished from another thread.
+unsafe impl Send for AuthBlockedClient {}
+
+impl AuthBlockedClient {
+    /// Unblock the client with the result of the authentication. The result
+    /// is applied as if it was returned by the [AuthCallback], except that
+    /// [AuthResult::Next] passes the attempt on to Redis' own password
+    /// authentication, and [AuthResult::Blocked] denies it.
+    pub fn finish(mut self, result: AuthResult) {
+        self.unblock(result);
+    }
+
+    fn unblock(&mut self, result: AuthResult) {
+        let inner = std::mem::replace(&mut self.inner, ptr::null_mut());
+        if inner.is_null() {
+            return;
+        }
+        let result = Box::into_raw(Box::new(result));
+        unsafe { raw::RedisModule_UnblockClient.unwrap()(inner, result.cast()) };
+    }
+}
+
+impl Drop for AuthBlockedClient {
+    fn drop(&mut self) {
+        self.unblock(AuthResult::Denied(None));
+    }
+}
+
+extern "C" fn auth_reply_callback(
+    ctx: *mut raw::RedisModuleCtx,
+    _username: *mut raw::RedisModuleString,
+    _password: *mut raw::RedisModuleString,
+    err: *mut *mut raw::RedisModuleString,
+) -> c_int {
+    let result = unsafe { raw::RedisModule_GetBlockedClientPrivateData.unwrap()(ctx) };
+    let result = unsafe { &*result.cast::<AuthResult>() };
+    apply_auth_result(ctx, result.clone(), err)
+}
+
+extern "C" fn auth_free_result(_ctx: *mut raw::RedisModuleCtx, result: *mut c_void) {
+    drop(unsafe { Box::from_raw(result.cast::<AuthResult>()) });
+}
+
+impl Context {
+    api!(
+        [RedisModule_RegisterAuthCallback],
+        /// Register a callback that handles `AUTH` and `HELLO AUTH`, for example to
+        /// validate a token instead of a password.
+        ///
+        /// The module's callbacks are tried in registration order. A callback returning
+        /// [AuthResult::Next] passes the attempt on, and if no callback handles it Redis
+        /// falls back to the callbacks of other modules and then to password
+        /// authentication.
+        pub fn register_auth_callback(&self, callback: AuthCallback) {
+            let mut callbacks = AUTH_CALLBACKS.lock().unwrap();
+            if callbacks.is_empty() {
+                unsafe { RedisModule_RegisterAuthCallback(self.ctx, Some(auth_callback)) };
+            }
+            callbacks.push(callback);
+        }
+    );
+
+    api!(
+        [RedisModule_BlockClientOnAuth],
+        /// Block the client being authenticated, to verify its credentials without
+        /// blocking Redis, for example with a network call to an identity provider.
+        /// Only valid inside an [AuthCallback], which must then return
+        /// [AuthResult::Blocked]. Pass the username and password to the thread
+        /// doing the verification and give the result with [AuthBlockedClient::finish].
+        pub fn block_client_on_auth(&self) -> Result<AuthBlockedClient, RedisError> {
+            let inner = unsafe {
+                RedisModule_BlockClientOnAuth(
+                    self.ctx,
+                    Some(auth_reply_callback),
+                    Some(auth_free_result),
+                )
+            };
+            if inner.is_null() {
+                return Err(RedisError::Str(
+                    "ERR the client can only be blocked during authentication",
+                ));
+            }
+            Ok(AuthBlockedClient { inner })
+        }
+    );
+}
@@ -1,8 +1,12 @@
 use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::raw;
 use crate::Context;
 
+/// The number of clients blocked by the module which were not unblocked yet.
+static BLOCKED_CLIENTS: AtomicUsize = AtomicUsize::new(0);
+
 pub struct BlockedClient {
     pub(crate) inner: *mut raw::RedisModuleBlockedClient,
 }
@@ -12,6 +16,8 @@ unsafe impl Send for BlockedClient {}
 
 impl Drop for BlockedClient {
     fn drop(&mut self) {
+        // Count the client out first, it may be served as soon as it is unblocked.
+        BLOCKED_CLIENTS.fetch_sub(1, Ordering::Relaxed);
         unsafe { raw::RedisModule_UnblockClient.unwrap()(self.inner, ptr::null_mut()) };
     }
 }
@@ -28,8 +34,19 @@ impl Context {
             )
         };
 
+        self.note_blocked();
+        BLOCKED_CLIENTS.fetch_add(1, Ordering::Relaxed);
         BlockedClient {
             inner: blocked_client,
         }
     }
+
+    /// The number of clients blocked with [Self::block_client] whose [BlockedClient]
+    /// was not dropped yet, from any thread. A count that keeps growing means
+    /// blocked clients are leaked. Redis does not unload a module while it has
+    /// blocked clients, so the count is always 0 on unload.
+    #[must_use]
+    pub fn blocked_client_count(&self) -> usize {
+        BLOCKED_CLIENTS.load(Ordering::Relaxed)
+    }
 }
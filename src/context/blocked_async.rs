@@ -0,0 +1,89 @@
+//! Runs the work of a blocked client as a Tokio task, enabled by the `tokio`
+//! feature.
+//!
+//! The task itself never touches Redis. Once it completes, its output is handed
+//! to Redis with `RedisModule_UnblockClient`, which is thread safe, and the reply
+//! is built from it on the Redis main thread, with a [Context] of the client.
+
+use std::future::Future;
+use std::os::raw::{c_int, c_void};
+
+use tokio::runtime::Handle;
+
+use crate::context::Context;
+use crate::raw;
+use crate::{RedisError, RedisResult, RedisValue};
+
+type ReplyFn = Box<dyn FnOnce(&Context) -> RedisResult + Send>;
+
+/// A client blocked by [Context::block_client_on_task], unblocked when dropped.
+/// It is moved into the task, so the client is unblocked even if the task panics
+/// or is cancelled, with an error reply.
+struct PendingClient {
+    inner: *mut raw::RedisModuleBlockedClient,
+    reply: Option<ReplyFn>,
+}
+
+// The blocked client is unblocked from the thread running the task.
+unsafe impl Send for PendingClient {}
+
+impl Drop for PendingClient {
+    fn drop(&mut self) {
+        let reply = self.reply.take().unwrap_or_else(|| {
+            Box::new(|_: &Context| Err(RedisError::Str("ERR the task did not complete")))
+        });
+        let reply = Box::into_raw(Box::new(reply));
+        unsafe { raw::RedisModule_UnblockClient.unwrap()(self.inner, reply.cast()) };
+    }
+}
+
+extern "C" fn task_reply_callback(
+    ctx: *mut raw::RedisModuleCtx,
+    _argv: *mut *mut raw::RedisModuleString,
+    _argc: c_int,
+) -> c_int {
+    let reply = unsafe { raw::RedisModule_GetBlockedClientPrivateData.unwrap()(ctx) };
+    let reply = unsafe { &mut *reply.cast::<ReplyFn>() };
+    // The callback is called at most once, leave a no-op for `task_free_reply`.
+    let reply = std::mem::replace(reply, Box::new(|_: &Context| Ok(RedisValue::NoReply)));
+    let context = Context::new(ctx);
+    context.reply(reply(&context));
+    raw::REDISMODULE_OK as c_int
+}
+
+extern "C" fn task_free_reply(_ctx: *mut raw::RedisModuleCtx, reply: *mut c_void) {
+    drop(unsafe { Box::from_raw(reply.cast::<ReplyFn>()) });
+}
+
+impl Context {
+    /// Block the client and run `future` on the Tokio runtime of `handle`. When
+    /// the future completes, `reply` is called on the Redis main thread with its
+    /// output, and the client is unblocked with the returned reply. The command
+    /// should then return [RedisValue::NoReply].
+    ///
+    /// The future must not use Redis APIs, a [crate::ThreadSafeContext] is needed
+    /// for that. If the task panics or is cancelled, for example because the
+    /// runtime shuts down, the client gets an error.
+    pub fn block_client_on_task<F, R>(&self, handle: &Handle, future: F, reply: R)
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+        R: FnOnce(&Context, F::Output) -> RedisResult + Send + 'static,
+    {
+        let inner = unsafe {
+            raw::RedisModule_BlockClient.unwrap()(
+                self.ctx,
+                Some(task_reply_callback),
+                None,
+                Some(task_free_reply),
+                0,
+            )
+        };
+        self.note_blocked();
+        let mut client = PendingClient { inner, reply: None };
+        handle.spawn(async move {
+            let output = future.await;
+            client.reply = Some(Box::new(move |ctx: &Context| reply(ctx, output)));
+        });
+    }
+}
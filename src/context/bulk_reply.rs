@@ -0,0 +1,107 @@
+use std::os::raw::c_char;
+use std::ptr;
+
+use crate::context::Context;
+use crate::{raw, RedisString};
+
+/// A bulk string replied in chunks, returned by [Context::reply_bulk_begin].
+///
+/// The string is replied once the chunks add up to the length given to
+/// [Context::reply_bulk_begin]. If a chunk does not fit in that length, can not
+/// be appended, or the handle is dropped before the string is complete, the
+/// bulk string is aborted and nothing is replied. If the command then returns
+/// [crate::RedisValue::NoReply], without replying anything else, the client
+/// gets `ERR bulk reply aborted`. An error returned by the command, for example
+/// with `?`, is replied instead.
+#[must_use]
+pub struct BulkReply<'ctx> {
+    ctx: &'ctx Context,
+    /// The bytes given so far, `None` once replied or aborted.
+    buffer: Option<RedisString>,
+    remaining: usize,
+}
+
+impl<'ctx> BulkReply<'ctx> {
+    /// Append a chunk to the bulk string, and reply with it once the declared
+    /// length is reached. A chunk that does not fit in the declared length
+    /// aborts the bulk string.
+    #[allow(clippy::must_use_candidate)]
+    pub fn chunk(&mut self, chunk: &[u8]) -> raw::Status {
+        let Some(buffer) = &self.buffer else {
+            self.ctx
+                .log_warning("BulkReply::chunk called after the bulk reply was sent or aborted");
+            return raw::Status::Err;
+        };
+        if chunk.len() > self.remaining {
+            return self.abort("BulkReply::chunk exceeded the length given to reply_bulk_begin");
+        }
+        let status: raw::Status = unsafe {
+            raw::RedisModule_StringAppendBuffer.unwrap()(
+                ptr::null_mut(),
+                buffer.inner,
+                chunk.as_ptr().cast::<c_char>(),
+                chunk.len(),
+            )
+        }
+        .into();
+        if status == raw::Status::Err {
+            return self.abort("BulkReply::chunk failed to append the chunk");
+        }
+        self.remaining -= chunk.len();
+        if self.remaining > 0 {
+            return raw::Status::Ok;
+        }
+        let buffer = self.buffer.take().unwrap();
+        self.ctx.note_reply();
+        raw::reply_with_string(self.ctx.ctx, buffer.inner)
+    }
+
+    /// The number of bytes still expected.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Drop the bytes given so far, see [BulkReply] for what the client gets.
+    /// Always returns [raw::Status::Err].
+    fn abort(&mut self, reason: &str) -> raw::Status {
+        self.buffer = None;
+        self.ctx.log_warning(reason);
+        self.ctx.bulk_aborted.set(true);
+        raw::Status::Err
+    }
+}
+
+impl Drop for BulkReply<'_> {
+    fn drop(&mut self) {
+        if self.buffer.is_some() {
+            self.abort("the bulk reply was dropped before it was complete");
+        }
+    }
+}
+
+impl Context {
+    /// Start replying with a bulk string of `total_len` bytes, whose content is
+    /// given by calls to [BulkReply::chunk] on the returned handle. The lengths
+    /// of the chunks must sum up to exactly `total_len`, see [BulkReply] for
+    /// what happens otherwise.
+    ///
+    /// Redis has no API to send a bulk string in parts, so the chunks are
+    /// appended to a Redis string which is replied once it is complete.
+    /// This avoids building the value in an intermediate Rust buffer.
+    pub fn reply_bulk_begin(&self, total_len: usize) -> BulkReply {
+        if total_len == 0 {
+            self.reply_empty_string();
+            return BulkReply {
+                ctx: self,
+                buffer: None,
+                remaining: 0,
+            };
+        }
+        BulkReply {
+            ctx: self,
+            buffer: Some(RedisString::create_from_slice(ptr::null_mut(), &[])),
+            remaining: total_len,
+        }
+    }
+}
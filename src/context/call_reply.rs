@@ -204,6 +204,15 @@ impl<'root> ArrayCallReply<'root> {
     pub fn len(&self) -> usize {
         call_reply_length(self.reply.as_ptr())
     }
+
+    /// Return all the elements. They are owned by the root reply, so unlike
+    /// [Self::get] they do not borrow `self`.
+    pub(crate) fn elements(&self) -> Vec<CallResult<'root>> {
+        (0..self.len())
+            .map_while(|idx| NonNull::new(call_reply_array_element(self.reply.as_ptr(), idx)))
+            .map(create_call_reply)
+            .collect()
+    }
 }
 
 pub struct ArrayCallReplyIterator<'root, 'curr> {
@@ -303,6 +312,20 @@ impl<'root> MapCallReply<'root> {
     pub fn len(&self) -> usize {
         call_reply_length(self.reply.as_ptr())
     }
+
+    /// Return all the entries. They are owned by the root reply, so unlike
+    /// [Self::get] they do not borrow `self`.
+    pub(crate) fn entries(&self) -> Vec<(CallResult<'root>, CallResult<'root>)> {
+        (0..self.len())
+            .map_while(|idx| {
+                let (key, val) = call_reply_map_element(self.reply.as_ptr(), idx);
+                Some((
+                    create_call_reply(NonNull::new(key)?),
+                    create_call_reply(NonNull::new(val)?),
+                ))
+            })
+            .collect()
+    }
 }
 
 pub struct MapCallReplyIterator<'root, 'curr> {
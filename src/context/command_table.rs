@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use crate::{Context, RedisError, RedisResult, RedisString, RedisValue};
+
+/// A command handler, with the same signature as the commands given to
+/// [crate::redis_module].
+pub type CommandHandler = fn(&Context, Vec<RedisString>) -> RedisResult;
+
+struct TableEntry {
+    arity: i64,
+    flags: String,
+    handler: CommandHandler,
+}
+
+/// Routes commands to their handlers by name, checking their arity first.
+///
+/// Useful when a single Redis command callback serves many commands, instead of
+/// matching on the command name by hand. Names are case insensitive and the
+/// arity follows the Redis convention: it counts the command name, a positive
+/// arity is an exact number of arguments and a negative one a minimum.
+#[derive(Default)]
+pub struct CommandTable {
+    commands: HashMap<String, TableEntry>,
+}
+
+impl CommandTable {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a command, replacing any command with the same name. The flags are not
+    /// used for dispatching, they are kept for registering the command with Redis,
+    /// see [Self::commands].
+    pub fn add(
+        &mut self,
+        name: &str,
+        arity: i64,
+        flags: &str,
+        handler: CommandHandler,
+    ) -> &mut Self {
+        let entry = TableEntry {
+            arity,
+            flags: flags.to_owned(),
+            handler,
+        };
+        self.commands.insert(name.to_ascii_lowercase(), entry);
+        self
+    }
+
+    /// The name, arity and flags of every command in the table, in no particular order.
+    pub fn commands(&self) -> impl Iterator<Item = (&str, i64, &str)> {
+        self.commands
+            .iter()
+            .map(|(name, entry)| (name.as_str(), entry.arity, entry.flags.as_str()))
+    }
+
+    /// Call the handler of the command named by `args[0]`. A wrong number of
+    /// arguments is a [RedisError::WrongArity] and an unknown command an error,
+    /// both returned without calling a handler.
+    ///
+    /// On a keys position request with a wrong number of arguments the command has
+    /// no keys to report, so nothing is returned rather than an error.
+    pub fn dispatch(&self, ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+        let Some(name) = args.first() else {
+            return Err(RedisError::WrongArity);
+        };
+        let name = String::from_utf8_lossy(name.as_slice()).to_ascii_lowercase();
+        let Some(entry) = self.commands.get(&name) else {
+            return Err(RedisError::String(format!("ERR unknown command '{name}'")));
+        };
+
+        let argc = args.len() as i64;
+        let arity_ok = if entry.arity >= 0 {
+            argc == entry.arity
+        } else {
+            argc >= -entry.arity
+        };
+        if !arity_ok {
+            if ctx.is_keys_position_request() {
+                return Ok(RedisValue::NoReply);
+            }
+            return Err(RedisError::WrongArity);
+        }
+
+        (entry.handler)(ctx, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CommandTable;
+    use crate::{Context, RedisError, RedisResult, RedisString, RedisValue};
+
+    fn args(args: &[&str]) -> Vec<RedisString> {
+        args.iter()
+            .map(|a| RedisString::from_bytes_owned(a.as_bytes()))
+            .collect()
+    }
+
+    fn count(_ctx: &Context, args: Vec<RedisString>) -> RedisResult {
+        Ok((args.len() as i64).into())
+    }
+
+    #[test]
+    fn dispatch_checks_arity() {
+        let ctx = Context::dummy();
+        let mut table = CommandTable::new();
+        table
+            .add("mod.get", 2, "readonly", count)
+            .add("mod.mset", -3, "write", count);
+
+        assert_eq!(
+            table.dispatch(&ctx, args(&["MOD.GET", "k"])).unwrap(),
+            RedisValue::Integer(2)
+        );
+        assert_eq!(
+            table
+                .dispatch(&ctx, args(&["mod.mset", "k", "v", "k2", "v2"]))
+                .unwrap(),
+            RedisValue::Integer(5)
+        );
+        assert!(matches!(
+            table.dispatch(&ctx, args(&["mod.get"])),
+            Err(RedisError::WrongArity)
+        ));
+        assert!(matches!(
+            table.dispatch(&ctx, args(&["mod.mset", "k"])),
+            Err(RedisError::WrongArity)
+        ));
+        assert!(table.dispatch(&ctx, args(&["mod.del", "k"])).is_err());
+        assert_eq!(table.commands().count(), 2);
+    }
+}
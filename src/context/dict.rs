@@ -0,0 +1,261 @@
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+use crate::context::Context;
+use crate::raw;
+
+/// A sorted dictionary managed by Redis, wrapping the `RedisModule_Dict*` API.
+///
+/// The keys are binary safe and kept in lexicographic order, which makes the
+/// dictionary suitable for ordered auxiliary indexes. A [crate::RedisString] can
+/// be used as a key directly, since it dereferences to `[u8]`. The values are owned by
+/// the dictionary and dropped when they are replaced, deleted, or when the
+/// dictionary itself is dropped.
+///
+/// The dictionary is not tied to the context that created it, so it can be
+/// kept across commands, for example in a [crate::RedisGILGuard]. It must only
+/// be accessed while the Redis GIL is held.
+pub struct Dict<T> {
+    inner: *mut raw::RedisModuleDict,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Dict<T> {
+    pub(crate) fn new() -> Self {
+        // Passing a null context means the dict is never freed by automatic
+        // memory management, its lifetime is controlled by `Drop`.
+        let inner = unsafe { raw::RedisModule_CreateDict.unwrap()(ptr::null_mut()) };
+        Self {
+            inner,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of entries in the dictionary.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        unsafe { raw::RedisModule_DictSize.unwrap()(self.inner) as usize }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sets the value of `key`, returning the previous value if there was one.
+    pub fn set(&mut self, key: &[u8], value: T) -> Option<T> {
+        let old = self.remove(key);
+        let value = Box::into_raw(Box::new(value));
+        unsafe {
+            raw::RedisModule_DictSetC.unwrap()(
+                self.inner,
+                key.as_ptr() as *mut c_void,
+                key.len(),
+                value.cast::<c_void>(),
+            )
+        };
+        old
+    }
+
+    fn value_ptr(&self, key: &[u8]) -> Option<*mut T> {
+        let mut nokey: c_int = 0;
+        let value = unsafe {
+            raw::RedisModule_DictGetC.unwrap()(
+                self.inner,
+                key.as_ptr() as *mut c_void,
+                key.len(),
+                &mut nokey,
+            )
+        };
+        if nokey != 0 || value.is_null() {
+            None
+        } else {
+            Some(value.cast::<T>())
+        }
+    }
+
+    /// Returns the value of `key`, or `None` if the key does not exist.
+    #[must_use]
+    pub fn get(&self, key: &[u8]) -> Option<&T> {
+        self.value_ptr(key).map(|value| unsafe { &*value })
+    }
+
+    /// Returns a mutable reference to the value of `key`, or `None` if the key
+    /// does not exist.
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut T> {
+        self.value_ptr(key).map(|value| unsafe { &mut *value })
+    }
+
+    /// Removes `key` from the dictionary, returning its value if it existed.
+    pub fn remove(&mut self, key: &[u8]) -> Option<T> {
+        let mut old: *mut c_void = ptr::null_mut();
+        let res = unsafe {
+            raw::RedisModule_DictDelC.unwrap()(
+                self.inner,
+                key.as_ptr() as *mut c_void,
+                key.len(),
+                (&mut old as *mut *mut c_void).cast::<c_void>(),
+            )
+        };
+        if raw::Status::Ok == res.into() && !old.is_null() {
+            Some(*unsafe { Box::from_raw(old.cast::<T>()) })
+        } else {
+            None
+        }
+    }
+
+    /// Removes `key` from the dictionary, returning whether it existed.
+    pub fn del(&mut self, key: &[u8]) -> bool {
+        self.remove(key).is_some()
+    }
+
+    /// Returns an iterator over all the entries, in lexicographic order of the keys.
+    #[must_use]
+    pub fn iter(&self) -> DictIter<T> {
+        self.iter_from(SeekOp::First, &[])
+    }
+
+    /// Returns an iterator over the entries whose key is `>= start` and `<= end`,
+    /// in lexicographic order of the keys.
+    #[must_use]
+    pub fn range(&self, start: &[u8], end: &[u8]) -> DictIter<T> {
+        let mut iter = self.iter_from(SeekOp::GreaterOrEqual, start);
+        iter.end = Some(end.to_vec());
+        iter
+    }
+
+    /// Returns an iterator positioned at the first key that satisfies `op` when
+    /// compared to `key`. Use [DictIter::next] to move towards greater keys and
+    /// [DictIter::prev] to move towards smaller keys. `key` is ignored for
+    /// [SeekOp::First] and [SeekOp::Last].
+    #[must_use]
+    pub fn iter_from(&self, op: SeekOp, key: &[u8]) -> DictIter<T> {
+        let inner = unsafe {
+            raw::RedisModule_DictIteratorStartC.unwrap()(
+                self.inner,
+                op.as_cstr().as_ptr(),
+                key.as_ptr() as *mut c_void,
+                key.len(),
+            )
+        };
+        DictIter {
+            inner,
+            end: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for Dict<T> {
+    fn drop(&mut self) {
+        let values: Vec<*mut T> = self
+            .iter()
+            .map(|(_, value)| (value as *const T) as *mut T)
+            .collect();
+        values
+            .into_iter()
+            .for_each(|value| drop(unsafe { Box::from_raw(value) }));
+        unsafe { raw::RedisModule_FreeDict.unwrap()(ptr::null_mut(), self.inner) };
+    }
+}
+
+/// The operator used to position a [DictIter], see [Dict::iter_from].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekOp {
+    /// The first (smallest) key.
+    First,
+    /// The last (greatest) key.
+    Last,
+    /// The first key greater than the given key.
+    Greater,
+    /// The first key greater than or equal to the given key.
+    GreaterOrEqual,
+    /// The first key smaller than the given key.
+    Less,
+    /// The first key smaller than or equal to the given key.
+    LessOrEqual,
+    /// The given key, if it exists.
+    Equal,
+}
+
+impl SeekOp {
+    fn as_cstr(self) -> &'static CStr {
+        let op: &[u8] = match self {
+            Self::First => b"^\0",
+            Self::Last => b"$\0",
+            Self::Greater => b">\0",
+            Self::GreaterOrEqual => b">=\0",
+            Self::Less => b"<\0",
+            Self::LessOrEqual => b"<=\0",
+            Self::Equal => b"==\0",
+        };
+        CStr::from_bytes_with_nul(op).unwrap()
+    }
+}
+
+/// An iterator over the entries of a [Dict], yielding `(key, value)` pairs.
+///
+/// The key is copied, because Redis only keeps it valid until the iterator
+/// moves. The iterator borrows the dictionary, so it can not outlive it and the
+/// dictionary can not be modified while iterating. The iterator state is
+/// released when the iterator is dropped.
+pub struct DictIter<'dict, T> {
+    inner: *mut raw::RedisModuleDictIter,
+    end: Option<Vec<u8>>,
+    phantom: PhantomData<&'dict Dict<T>>,
+}
+
+impl<'dict, T> DictIter<'dict, T> {
+    /// Returns the entry at the current position and moves towards smaller keys.
+    /// The upper bound of [Dict::range] does not apply when moving backwards.
+    pub fn prev(&mut self) -> Option<(Vec<u8>, &'dict T)> {
+        self.step(raw::RedisModule_DictPrevC.unwrap())
+    }
+
+    fn step(
+        &mut self,
+        step: unsafe extern "C" fn(
+            *mut raw::RedisModuleDictIter,
+            *mut usize,
+            *mut *mut c_void,
+        ) -> *mut c_void,
+    ) -> Option<(Vec<u8>, &'dict T)> {
+        let mut key_len: usize = 0;
+        let mut value: *mut c_void = ptr::null_mut();
+        let key = unsafe { step(self.inner, &mut key_len, &mut value) };
+        if key.is_null() {
+            return None;
+        }
+        let key = unsafe { std::slice::from_raw_parts(key.cast::<u8>(), key_len) };
+        Some((key.to_vec(), unsafe { &*value.cast::<T>() }))
+    }
+}
+
+impl<'dict, T: 'dict> Iterator for DictIter<'dict, T> {
+    type Item = (Vec<u8>, &'dict T);
+
+    /// Returns the entry at the current position and moves towards greater keys.
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.step(raw::RedisModule_DictNextC.unwrap())?;
+        if self.end.as_deref().is_some_and(|end| key.as_slice() > end) {
+            return None;
+        }
+        Some((key, value))
+    }
+}
+
+impl<'dict, T> Drop for DictIter<'dict, T> {
+    fn drop(&mut self) {
+        unsafe { raw::RedisModule_DictIteratorStop.unwrap()(self.inner) };
+    }
+}
+
+impl Context {
+    /// Creates an empty [Dict]. See [Dict] for how long it lives.
+    #[must_use]
+    pub fn create_dict<T>(&self) -> Dict<T> {
+        Dict::new()
+    }
+}
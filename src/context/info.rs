@@ -1,8 +1,11 @@
 use std::ffi::CString;
 use std::ptr::NonNull;
+use std::sync::Mutex;
 
-use crate::Context;
 use crate::{raw, RedisString};
+use crate::{
+    Context, InfoContext, InfoContextBuilderFieldTopLevelValue, OneInfoSectionData, RedisResult,
+};
 
 pub struct ServerInfo {
     ctx: *mut raw::RedisModuleCtx,
@@ -29,7 +32,65 @@ impl ServerInfo {
     }
 }
 
+struct InfoGauge {
+    section: String,
+    name: String,
+    value: Box<dyn Fn() -> i64 + Send + Sync>,
+}
+
+/// The gauges registered with [Context::register_info_gauge], in registration order.
+static INFO_GAUGES: Mutex<Vec<InfoGauge>> = Mutex::new(Vec::new());
+
+/// Add the registered gauges to the module's `INFO`, grouped by section.
+pub(crate) fn add_info_gauges(ctx: &InfoContext) -> RedisResult<()> {
+    let gauges = INFO_GAUGES.lock().unwrap();
+    if gauges.is_empty() {
+        return Ok(());
+    }
+    let mut sections: Vec<OneInfoSectionData> = Vec::new();
+    for gauge in gauges.iter() {
+        let field = (
+            gauge.name.clone(),
+            InfoContextBuilderFieldTopLevelValue::from((gauge.value)()),
+        );
+        match sections
+            .iter_mut()
+            .find(|(section, _)| *section == gauge.section)
+        {
+            Some((_, fields)) => fields.push(field),
+            None => sections.push((gauge.section.clone(), vec![field])),
+        }
+    }
+    sections
+        .into_iter()
+        .fold(ctx.builder(), |builder, section| {
+            builder.add_section_unchecked(section)
+        })
+        .build_info()?;
+    Ok(())
+}
+
 impl Context {
+    /// Register a gauge reported by `INFO` as the field `name` of the module's
+    /// section `section`, which Redis prefixes with the module name. The gauge
+    /// is called each time the section is requested, so it can read state
+    /// shared with the module through an `Arc` or an atomic.
+    ///
+    /// The gauges are not called for crash reports. A gauge must not register
+    /// other gauges.
+    pub fn register_info_gauge<F: Fn() -> i64 + Send + Sync + 'static>(
+        &self,
+        section: &str,
+        name: &str,
+        gauge: F,
+    ) {
+        INFO_GAUGES.lock().unwrap().push(InfoGauge {
+            section: section.to_owned(),
+            name: name.to_owned(),
+            value: Box::new(gauge),
+        });
+    }
+
     #[must_use]
     pub fn server_info(&self, section: &str) -> ServerInfo {
         let section = CString::new(section).unwrap();
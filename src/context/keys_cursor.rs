@@ -5,6 +5,14 @@ use crate::redismodule::RedisString;
 use std::ffi::c_void;
 use std::ptr::NonNull;
 
+/// A cursor over the keys of the current database, wrapping `RedisModule_Scan`.
+///
+/// The cursor keeps its position between calls to [KeysCursor::scan], also across
+/// commands, and is only reset by [KeysCursor::restart]. It follows the
+/// guarantees of the `SCAN` command: a key that exists for the whole duration
+/// of the scan is returned at least once, even if the keyspace is resized or
+/// rehashed in between calls, but it may be returned more than once. Keys that
+/// are added or deleted during the scan may or may not be returned.
 pub struct KeysCursor {
     inner_cursor: *mut raw::RedisModuleScanCursor,
 }
@@ -35,17 +43,19 @@ impl KeysCursor {
         Self { inner_cursor }
     }
 
+    /// Scan the next batch of keys, calling `callback` for each of them.
+    /// Return `false` once the scan is done.
     pub fn scan<F: FnMut(&Context, RedisString, Option<&RedisKey>)>(
         &self,
         ctx: &Context,
-        callback: &F,
+        callback: &mut F,
     ) -> bool {
         let res = unsafe {
             raw::RedisModule_Scan.unwrap()(
                 ctx.ctx,
                 self.inner_cursor,
                 Some(scan_callback::<F>),
-                callback as *const F as *mut c_void,
+                (callback as *mut F).cast::<c_void>(),
             )
         };
         res != 0
@@ -1,15 +1,22 @@
 use bitflags::bitflags;
 use redis_module_macros_internals::api;
+use std::cell::{Cell, RefCell};
 use std::collections::{BTreeMap, HashMap};
 use std::ffi::CString;
 use std::os::raw::c_void;
 use std::os::raw::{c_char, c_int, c_long, c_longlong};
 use std::ptr::{self, NonNull};
-use std::sync::atomic::{AtomicPtr, Ordering};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
-use crate::key::{KeyFlags, RedisKey, RedisKeyWritable};
+use crate::key::{
+    key_write_epoch, note_key_write, verify_key_type, KeyFlags, RedisKey, RedisKeyWritable,
+};
 use crate::logging::RedisLogLevel;
 use crate::raw::{ModuleOptions, Version};
+use crate::rediserror::ErrorDetails;
 use crate::redisvalue::RedisValueKey;
 use crate::{
     add_info_begin_dict_field, add_info_end_dict_field, add_info_field_double,
@@ -22,19 +29,41 @@ use std::ops::Deref;
 
 use std::ffi::CStr;
 
-use self::call_reply::{create_promise_call_reply, CallResult, PromiseCallReply};
+use self::call_reply::{create_promise_call_reply, CallResult, ErrorReply, PromiseCallReply};
 use self::thread_safe::RedisLockIndicator;
 
 mod timer;
 
+pub mod auth;
 pub mod blocked;
+#[cfg(feature = "tokio")]
+pub mod blocked_async;
+pub mod bulk_reply;
 pub mod call_reply;
+pub mod command_table;
 pub mod commands;
+pub mod dict;
 pub mod info;
 pub mod keys_cursor;
 pub mod server_events;
 pub mod thread_safe;
 
+/// Builds the flags passed to `RM_Call` by [Context::call_ext] and [Context::call_blocking].
+///
+/// `RM_Call` has no timeout flag, in any Redis version, so a call runs until
+/// the command completes. A script that runs longer than `busy-reply-threshold`
+/// keeps running until it ends or is stopped with `SCRIPT KILL`. To bound the
+/// time spent waiting on a blocking command, pass the command's own timeout
+/// argument (for example `BLPOP key 1`), or invoke it with `call_blocking` and
+/// abort the returned future with [call_reply::FutureHandler::abort_and_dispose].
+///
+/// There is no flag to hide a call from `MONITOR` or `SLOWLOG` either, in any
+/// Redis version, so the builder has no such option. Whether the commands run
+/// with `RM_Call` are shown to `MONITOR` clients or logged separately in the
+/// slow log is decided by Redis and differs between versions, while the time
+/// they take always counts towards the module command that runs them. To keep
+/// bookkeeping out of the operators' view, prefer the key and data type APIs of
+/// [Context] over calling commands.
 pub struct CallOptionsBuilder {
     options: String,
 }
@@ -47,6 +76,38 @@ impl Default for CallOptionsBuilder {
     }
 }
 
+/// A hook that is invoked after every [Context::call], [Context::call_ext] and
+/// [Context::call_blocking] with the command name, the time spent inside `RM_Call`
+/// and whether the reply was an error.
+pub type CallHook = fn(command: &str, duration: Duration, is_error: bool);
+
+static CALL_HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// The default of [Context::set_max_reply_depth].
+pub const DEFAULT_MAX_REPLY_DEPTH: usize = 1000;
+
+static MAX_REPLY_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_REPLY_DEPTH);
+
+/// The server version, determined once by [Context::get_redis_version_cached].
+static REDIS_VERSION: OnceLock<Version> = OnceLock::new();
+
+/// The name and version the module was registered with, see [set_module_identity].
+static MODULE_IDENTITY: OnceLock<(String, i32)> = OnceLock::new();
+
+/// Remember the name and version the module was registered with, returned by
+/// [Context::module_name] and [Context::module_version]. Called once by
+/// [crate::redis_module] when the module is loaded, later calls are ignored.
+#[doc(hidden)]
+pub fn set_module_identity(name: &str, version: i32) {
+    let _ = MODULE_IDENTITY.set((name.to_owned(), version));
+}
+
+fn module_identity() -> &'static (String, i32) {
+    MODULE_IDENTITY
+        .get()
+        .expect("the module name and version are only known once the module is registered")
+}
+
 #[derive(Clone)]
 pub struct CallOptions {
     options: CString,
@@ -70,6 +131,40 @@ impl CallOptionsBuilder {
         Self::default()
     }
 
+    /// Preset for calls that are allowed to write and must be replicated.
+    /// Equivalent to `new().replicate().verify_acl().verify_oom().errors_as_replies()`,
+    /// that is, the `!`, `C`, `M` and `E` flags. The reply protocol is left as
+    /// RESP2 and can be changed with [Self::resp].
+    pub fn replicated_safe() -> CallOptionsBuilder {
+        Self::new()
+            .replicate()
+            .verify_acl()
+            .verify_oom()
+            .errors_as_replies()
+    }
+
+    /// Preset for calls that must not write.
+    /// Equivalent to `new().no_writes().verify_acl().errors_as_replies()`,
+    /// that is, the `W`, `C` and `E` flags. The reply protocol is left as
+    /// RESP2 and can be changed with [Self::resp].
+    pub fn read_only_safe() -> CallOptionsBuilder {
+        Self::new().no_writes().verify_acl().errors_as_replies()
+    }
+
+    /// Create a builder from raw `RM_Call` flags, for example `"!CE"`.
+    /// Only the flags that have a dedicated method on this builder are accepted
+    /// (`W`, `S`, `C`, `M`, `E`, `!`, `0` and `3`), any other character is an error.
+    pub fn from_flags(flags: &str) -> Result<CallOptionsBuilder, RedisError> {
+        if let Some(c) = flags.chars().find(|c| !"WSCME!03".contains(*c)) {
+            return Err(RedisError::String(format!(
+                "Unsupported call option flag '{c}'"
+            )));
+        }
+        let mut builder = Self::new();
+        builder.add_flag(flags);
+        Ok(builder)
+    }
+
     fn add_flag(&mut self, flag: &str) {
         self.options.push_str(flag);
     }
@@ -179,6 +274,8 @@ unsafe impl RedisLockIndicator for DetachedContextGuard {}
 
 impl Drop for DetachedContextGuard {
     fn drop(&mut self) {
+        // Close the cached keys while the lock is still held.
+        self.ctx.invalidate_open_keys();
         unsafe {
             raw::RedisModule_ThreadSafeContextUnlock.unwrap()(self.ctx.ctx);
         };
@@ -245,6 +342,29 @@ unsafe impl Sync for DetachedContext {}
 #[derive(Debug)]
 pub struct Context {
     pub ctx: *mut raw::RedisModuleCtx,
+    replication: Cell<Replication>,
+    /// Whether a [bulk_reply::BulkReply] was aborted, and nothing was replied since, see
+    /// [Context::reply_command_result].
+    bulk_aborted: Cell<bool>,
+    /// The keys opened by [Context::open_key_cached], by name, and the
+    /// [key_write_epoch] they were opened at.
+    open_keys: RefCell<Vec<(Vec<u8>, Rc<RedisKey>)>>,
+    open_keys_epoch: Cell<u64>,
+    /// Whether a reply was sent and whether the client was blocked, checked by
+    /// [Context::reply_command_result].
+    #[cfg(feature = "test")]
+    replied: Cell<bool>,
+    #[cfg(feature = "test")]
+    blocked: Cell<bool>,
+}
+
+/// How the current command asked to be replicated, used to catch
+/// [Context::replicate_verbatim] and [Context::replicate] being mixed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Replication {
+    None,
+    Verbatim,
+    Commands,
 }
 
 /// A guerd that protected a user that has
@@ -326,15 +446,185 @@ impl<'a> StrCallArgs<'a> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeferredReplyKind {
+    Array,
+    Map,
+    Set,
+}
+
+/// An array, map or set reply whose length is only known after its elements
+/// were replied, returned by [Context::reply_array_deferred],
+/// [Context::reply_map_deferred] and [Context::reply_set_deferred].
+///
+/// The elements are replied with [Self::reply] (and [Self::reply_entry] for
+/// maps), which count them, and the length is set when the handle is dropped or
+/// [Self::finish]ed. Elements can also be replied directly with the [Context],
+/// for example nested collections, in which case the length has to be given
+/// to [Self::set_len]. For maps the length is the number of entries; RESP2
+/// clients get the map as a flat array, twice as long, which Redis takes into
+/// account when the length is set.
+pub struct DeferredReply<'ctx> {
+    ctx: &'ctx Context,
+    kind: DeferredReplyKind,
+    len: usize,
+    done: bool,
+}
+
+impl<'ctx> DeferredReply<'ctx> {
+    fn new(ctx: &'ctx Context, kind: DeferredReplyKind) -> Self {
+        ctx.note_reply();
+        let len = raw::REDISMODULE_POSTPONED_LEN as c_long;
+        match kind {
+            DeferredReplyKind::Array => raw::reply_with_array(ctx.ctx, len),
+            DeferredReplyKind::Map => raw::reply_with_map(ctx.ctx, len),
+            DeferredReplyKind::Set => raw::reply_with_set(ctx.ctx, len),
+        };
+        Self {
+            ctx,
+            kind,
+            len: 0,
+            done: false,
+        }
+    }
+
+    /// Reply with an element of an array or a set.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if called on a map reply, use [Self::reply_entry] instead.
+    pub fn reply(&mut self, value: RedisResult) -> raw::Status {
+        assert_ne!(
+            self.kind,
+            DeferredReplyKind::Map,
+            "map replies take entries, use reply_entry"
+        );
+        self.len += 1;
+        self.ctx.reply(value)
+    }
+
+    /// Reply with an entry of a map.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if called on an array or a set reply, use [Self::reply] instead.
+    pub fn reply_entry(&mut self, key: RedisValue, value: RedisResult) -> raw::Status {
+        assert_eq!(
+            self.kind,
+            DeferredReplyKind::Map,
+            "only map replies take entries, use reply"
+        );
+        self.len += 1;
+        self.ctx.reply(Ok(key));
+        self.ctx.reply(value)
+    }
+
+    /// The number of elements, or map entries, replied so far with this handle.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Set the length of the reply, overriding the number of elements replied
+    /// with this handle.
+    pub fn set_len(mut self, len: usize) {
+        self.len = len;
+        self.finish();
+    }
+
+    /// Set the length of the reply to the number of elements replied with this
+    /// handle. Same as dropping the handle.
+    pub fn finish(mut self) {
+        self.patch_len();
+    }
+
+    fn patch_len(&mut self) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        let len = self.len as c_long;
+        match self.kind {
+            DeferredReplyKind::Array => raw::reply_set_array_length(self.ctx.ctx, len),
+            DeferredReplyKind::Map => raw::reply_set_map_length(self.ctx.ctx, len),
+            DeferredReplyKind::Set => raw::reply_set_set_length(self.ctx.ctx, len),
+        }
+    }
+}
+
+impl<'ctx> Drop for DeferredReply<'ctx> {
+    fn drop(&mut self) {
+        self.patch_len();
+    }
+}
+
+/// Measures the time until it is dropped with the monotonic clock of Redis, and
+/// passes it to a callback, for example to log the latency of a command or to
+/// add it to a statistic shown by the module's `INFO` section. Returned by
+/// [Context::latency_timer].
+pub struct LatencyTimer<F: FnOnce(Duration)> {
+    start: u64,
+    on_drop: Option<F>,
+}
+
+impl<F: FnOnce(Duration)> LatencyTimer<F> {
+    /// The time elapsed since the timer was started.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_micros(monotonic_microseconds().saturating_sub(self.start))
+    }
+}
+
+impl<F: FnOnce(Duration)> Drop for LatencyTimer<F> {
+    fn drop(&mut self) {
+        if let Some(on_drop) = self.on_drop.take() {
+            on_drop(self.elapsed());
+        }
+    }
+}
+
+/// The monotonic clock of Redis, or before Redis 7.0, which does not export
+/// it, the time since the clock was first read.
+fn monotonic_microseconds() -> u64 {
+    if let Some(monotonic) = unsafe { raw::RedisModule_MonotonicMicroseconds } {
+        return unsafe { monotonic() };
+    }
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_micros() as u64
+}
+
 impl Context {
     pub const fn new(ctx: *mut raw::RedisModuleCtx) -> Self {
-        Self { ctx }
+        Self {
+            ctx,
+            replication: Cell::new(Replication::None),
+            bulk_aborted: Cell::new(false),
+            open_keys: RefCell::new(Vec::new()),
+            open_keys_epoch: Cell::new(0),
+            #[cfg(feature = "test")]
+            replied: Cell::new(false),
+            #[cfg(feature = "test")]
+            blocked: Cell::new(false),
+        }
     }
 
     #[must_use]
     pub const fn dummy() -> Self {
         Self {
             ctx: ptr::null_mut(),
+            replication: Cell::new(Replication::None),
+            bulk_aborted: Cell::new(false),
+            open_keys: RefCell::new(Vec::new()),
+            open_keys_epoch: Cell::new(0),
+            #[cfg(feature = "test")]
+            replied: Cell::new(false),
+            #[cfg(feature = "test")]
+            blocked: Cell::new(false),
         }
     }
 
@@ -358,6 +648,20 @@ impl Context {
         self.log(RedisLogLevel::Warning, message);
     }
 
+    /// Enable automatic memory management for this context: the strings, keys and
+    /// call replies created with it which are still alive when the context is
+    /// released are freed by Redis. A command context is released when the command
+    /// returns, but a [crate::ThreadSafeContext] or [DetachedContext] only when it is
+    /// freed, so enabling it there keeps track of every object created during the
+    /// context's whole lifetime.
+    ///
+    /// Redis has no API to disable automatic memory management once enabled, so it
+    /// can not be scoped. It is rarely needed, as [RedisString], [RedisKey] and
+    /// [crate::CallReply] free their objects when dropped, which also removes them from
+    /// the automatic memory pool. Do not enable it on thread safe or detached
+    /// contexts, and do not keep objects created with an automatic memory context
+    /// after the command returns, as Redis frees them at that point.
+    ///
     /// # Panics
     ///
     /// Will panic if `RedisModule_AutoMemory` is missing in redismodule.h
@@ -402,10 +706,23 @@ impl Context {
         fmt: *const c_char,
         args: T,
     ) -> R {
+        // The command name may come from a client, reject it rather than panic.
+        let cmd = match CString::new(command) {
+            Ok(cmd) if !command.is_empty() => cmd,
+            _ => {
+                let err = ErrorReply::Message(format!("ERR invalid command name {command:?}"));
+                return R::from(PromiseCallReply::Resolved(Err(err)));
+            }
+        };
+        // The command may write to keys opened by `open_key_cached`, with this
+        // context or any other.
+        self.invalidate_open_keys();
+        note_key_write();
         let mut call_args: StrCallArgs = args.into();
         let final_args = call_args.args_mut();
 
-        let cmd = CString::new(command).unwrap();
+        let hook = CALL_HOOK.load(Ordering::Relaxed);
+        let start = (!hook.is_null()).then(Instant::now);
         let reply: *mut raw::RedisModuleCallReply = unsafe {
             let p_call = raw::RedisModule_Call.unwrap();
             p_call(
@@ -416,15 +733,102 @@ impl Context {
                 final_args.len(),
             )
         };
+        if let Some(start) = start {
+            let duration = start.elapsed();
+            let is_error = reply.is_null()
+                || unsafe { raw::RedisModule_CallReplyType.unwrap()(reply) }
+                    == raw::REDISMODULE_REPLY_ERROR as c_int;
+            // Safety: only values of type `CallHook` are ever stored in `CALL_HOOK`.
+            let hook: CallHook = unsafe { std::mem::transmute(hook) };
+            hook(command, duration, is_error);
+        }
         let promise = create_promise_call_reply(self, NonNull::new(reply));
         R::from(promise)
     }
 
+    /// Set how deeply containers may be nested in a value given to [Self::reply],
+    /// [DEFAULT_MAX_REPLY_DEPTH] by default. A deeper value is replied as an error
+    /// instead of overflowing the stack, for example when forwarding a
+    /// pathologically nested reply of another command. The depth is set for the
+    /// whole module, all contexts share it.
+    pub fn set_max_reply_depth(depth: usize) {
+        MAX_REPLY_DEPTH.store(depth, Ordering::Relaxed);
+    }
+
+    /// Register a [CallHook] that will be invoked after each call made by the module,
+    /// or remove the current one by passing `None`. When no hook is registered the
+    /// calls are not timed at all.
+    pub fn set_call_hook(&self, hook: Option<CallHook>) {
+        let hook = hook.map_or(ptr::null_mut(), |hook| hook as *mut ());
+        CALL_HOOK.store(hook, Ordering::Relaxed);
+    }
+
+    /// Invoke a command on Redis and return the result. An empty command name, or
+    /// one containing a NUL byte, is an error.
+    ///
+    /// There is no need for `WATCH`, `MULTI` and `EXEC` to read and then write
+    /// keys consistently: a command, like the calls it makes, runs atomically,
+    /// as does code holding the lock of a [crate::ThreadSafeContext]. Nor would they
+    /// work, each call runs on a client whose transaction state is not kept between
+    /// calls. To make a decision on a background thread and apply it later,
+    /// check again that the keys hold the values the decision was based on
+    /// once the lock is reacquired.
     pub fn call<'a, T: Into<StrCallArgs<'a>>>(&self, command: &str, args: T) -> RedisResult {
         self.call_internal::<_, CallResult>(command, raw::FMT, args)
             .map_or_else(|e| Err(e.into()), |v| Ok((&v).into()))
     }
 
+    /// Run a Lua script with `EVAL`, passing the number of `keys` before them.
+    /// The keys and arguments are passed as is, so they can hold any bytes.
+    /// An error raised by the script, or by a command it called such as
+    /// `WRONGTYPE`, is returned as an error.
+    pub fn eval(&self, script: &str, keys: &[&RedisString], args: &[&RedisString]) -> RedisResult {
+        self.call_script("EVAL", script, keys, args)
+    }
+
+    /// Same as [Self::eval], for a script loaded with `SCRIPT LOAD`, given by its
+    /// SHA1 digest. A `NOSCRIPT` error is returned if the script is not loaded.
+    pub fn eval_sha(
+        &self,
+        sha1: &str,
+        keys: &[&RedisString],
+        args: &[&RedisString],
+    ) -> RedisResult {
+        self.call_script("EVALSHA", sha1, keys, args)
+    }
+
+    fn call_script(
+        &self,
+        command: &str,
+        script: &str,
+        keys: &[&RedisString],
+        args: &[&RedisString],
+    ) -> RedisResult {
+        let script = RedisString::create_from_slice(ptr::null_mut(), script.as_bytes());
+        let numkeys =
+            RedisString::create_from_slice(ptr::null_mut(), keys.len().to_string().as_bytes());
+        let call_args: Vec<&RedisString> = [&script, &numkeys]
+            .into_iter()
+            .chain(keys.iter().copied())
+            .chain(args.iter().copied())
+            .collect();
+        self.call(command, call_args.as_slice())
+    }
+
+    /// Same as [Self::call], for commands whose reply is not needed. Redis
+    /// always builds the reply, but it is freed without being converted into a
+    /// [RedisValue], so a successful call does not allocate. Errors are still
+    /// returned.
+    pub fn call_fire_and_forget<'a, T: Into<StrCallArgs<'a>>>(
+        &self,
+        command: &str,
+        args: T,
+    ) -> Result<(), RedisError> {
+        self.call_internal::<_, CallResult>(command, raw::FMT, args)
+            .map(|_| ())
+            .map_err(RedisError::from)
+    }
+
     /// Invoke a command on Redis and return the result
     /// Unlike 'call' this API also allow to pass a CallOption to control different aspects
     /// of the command invocation.
@@ -452,16 +856,21 @@ impl Context {
         options: &BlockingCallOptions,
         args: T,
     ) -> R {
+        // The client is blocked if the command blocks.
+        self.note_blocked();
         self.call_internal(command, options.options.as_ptr() as *const c_char, args)
     }
 
+    /// Copy `s` into a string that can be replied as a simple string or an
+    /// error, replacing the `\r`, `\n` and `\0` bytes with spaces. Other bytes,
+    /// including multi-byte UTF-8 characters, are kept as is.
     #[must_use]
     pub fn str_as_legal_resp_string(s: &str) -> CString {
         CString::new(
-            s.chars()
-                .map(|c| match c {
-                    '\r' | '\n' | '\0' => b' ',
-                    _ => c as u8,
+            s.bytes()
+                .map(|b| match b {
+                    b'\r' | b'\n' | b'\0' => b' ',
+                    _ => b,
                 })
                 .collect::<Vec<_>>(),
         )
@@ -470,17 +879,284 @@ impl Context {
 
     #[allow(clippy::must_use_candidate)]
     pub fn reply_simple_string(&self, s: &str) -> raw::Status {
+        self.note_reply();
         let msg = Self::str_as_legal_resp_string(s);
         raw::reply_with_simple_string(self.ctx, msg.as_ptr())
     }
 
+    /// Reply with the integer `1` or `0`, whatever the protocol of the client, for
+    /// commands whose contract is an integer used as a boolean, like `EXISTS` or
+    /// `SISMEMBER`. A [RedisValue::Bool] replies with a RESP3 boolean (`#t` or
+    /// `#f`) instead, which Redis only turns into `:1` or `:0` for RESP2 clients;
+    /// use it for commands that return a boolean in RESP3.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_bool_as_int(&self, b: bool) -> raw::Status {
+        self.note_reply();
+        raw::reply_with_long_long(self.ctx, b.into())
+    }
+
+    /// Reply with an empty bulk string (`$0\r\n\r\n`), as opposed to
+    /// [RedisValue::Null] which replies with a null (`$-1\r\n` in RESP2).
+    /// Replying with an empty [RedisValue::BulkString] or [RedisValue::StringBuffer]
+    /// is equivalent.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_empty_string(&self) -> raw::Status {
+        self.note_reply();
+        raw::reply_with_empty_string(self.ctx)
+    }
+
+    /// Reply with a null array (`*-1\r\n` in RESP2), as `BLPOP` does on timeout,
+    /// as opposed to [RedisValue::Null] which replies with a null bulk string
+    /// (`$-1\r\n`). Both are the same null (`_\r\n`) in RESP3.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_null_array(&self) -> raw::Status {
+        self.note_reply();
+        raw::reply_with_null_array(self.ctx)
+    }
+
+    /// Reply with a human readable, JSON like, rendering of `value` as a single
+    /// bulk string. This is meant for debugging commands, use [Self::reply] to
+    /// send `value` as a proper RESP reply.
+    ///
+    /// Binary strings are hex escaped (`\x00`) and containers nested more than
+    /// 16 levels deep are rendered as `...`.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_debug(&self, value: &RedisValue) -> raw::Status {
+        self.note_reply();
+        let s = crate::redisvalue::debug_string(value);
+        raw::reply_with_string_buffer(self.ctx, s.as_ptr().cast::<c_char>(), s.len())
+    }
+
+    /// Reply with an error. Redis counts errors in `INFO errorstats` by their
+    /// first word, so a message such as `ERR invalid value` is counted under
+    /// `ERR`. Use [Self::reply_error_with_code] to choose the prefix explicitly.
     #[allow(clippy::must_use_candidate)]
     pub fn reply_error_string(&self, s: &str) -> raw::Status {
+        self.note_reply();
         let msg = Self::str_as_legal_resp_string(s);
         unsafe { raw::RedisModule_ReplyWithError.unwrap()(self.ctx, msg.as_ptr()).into() }
     }
 
+    /// Reply with the result of a handler that uses its own error type. The
+    /// value is replied as by [Self::reply], and the error with the text of its
+    /// [std::fmt::Display] implementation, made legal with
+    /// [Self::str_as_legal_resp_string]. An error text that does not start with
+    /// an error code, see [RedisError::code], is prefixed with `ERR`, as for
+    /// the errors converted into a [RedisError].
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_with<E: std::fmt::Display>(&self, result: Result<RedisValue, E>) -> raw::Status {
+        match result {
+            Ok(value) => self.reply(Ok(value)),
+            Err(e) => {
+                let err = RedisError::String(e.to_string());
+                match err.code() {
+                    Some(_) => self.reply_error_string(&err.to_string()),
+                    None => self.reply_error_string(&format!("ERR {err}")),
+                }
+            }
+        }
+    }
+
+    /// Same as [Self::reply_error_string], for a message that was already made
+    /// legal, for example a constant, so it is not copied again.
+    ///
+    /// The message is written to the client as is: a `\r` or `\n` in it ends
+    /// the error early and the rest is read by the client as further replies,
+    /// which breaks the protocol or lets a client controlled message inject
+    /// replies. Pass only messages built with [Self::str_as_legal_resp_string]
+    /// or known not to contain these characters.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_error_raw(&self, msg: &CStr) -> raw::Status {
+        self.note_reply();
+        unsafe { raw::RedisModule_ReplyWithError.unwrap()(self.ctx, msg.as_ptr()).into() }
+    }
+
+    /// Reply with the error `<code> <message>`, counted under `code` in
+    /// `INFO errorstats`. Whitespace in `code` is replaced with `_` so the code
+    /// stays a single word, and an empty code is replaced with `ERR`.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_error_with_code(&self, code: &str, message: &str) -> raw::Status {
+        let code: String = code
+            .chars()
+            .map(|c| if c.is_whitespace() { '_' } else { c })
+            .collect();
+        let code = if code.is_empty() { "ERR" } else { &code };
+        self.reply_error_string(&format!("{code} {message}"))
+    }
+
+    /// Same as [Self::reply_error_with_code], with `details` about the error for
+    /// RESP3 clients. The error frame is the same in both protocols, so the
+    /// details are sent as an attribute map preceding the error, which RESP3
+    /// clients can read and otherwise ignore. RESP2 has no attributes, so RESP2
+    /// clients, and all clients of Redis versions before 7.0, only get the error. A handler can return the same error as a
+    /// [RedisError::with_details].
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_error_with_details(
+        &self,
+        code: &str,
+        message: &str,
+        details: Vec<(RedisValue, RedisValue)>,
+    ) -> raw::Status {
+        self.note_reply();
+        // Attributes were added in Redis 7.0, RESP3 clients of older servers
+        // only get the error too.
+        let attributes = unsafe { raw::RedisModule_ReplyWithAttribute }.is_some();
+        if !details.is_empty() && attributes && self.get_flags().contains(ContextFlags::FLAGS_RESP3)
+        {
+            raw::reply_with_attribute(self.ctx, details.len() as c_long);
+            for (key, value) in details {
+                self.reply(Ok(key));
+                self.reply(Ok(value));
+            }
+        }
+        self.reply_error_with_code(code, message)
+    }
+
+    /// Reply with `value` rounded to `precision` decimal places, ties rounding to
+    /// even. RESP3 clients get a double, which Redis writes in its shortest form
+    /// so trailing zeros are dropped. RESP2 clients get a bulk string with
+    /// exactly `precision` decimals, for example `"1.50"` for a precision of 2.
+    /// Infinite and NaN values are replied as [Self::reply] does for a [RedisValue::Float].
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_double_fmt(&self, value: f64, precision: usize) -> raw::Status {
+        self.note_reply();
+        if !value.is_finite() {
+            return raw::reply_with_double(self.ctx, value);
+        }
+        let formatted = format!("{value:.precision$}");
+        if self.get_flags().contains(ContextFlags::FLAGS_RESP3) {
+            // The formatted string is a valid float, parsing it can not fail.
+            raw::reply_with_double(self.ctx, formatted.parse().unwrap_or(value))
+        } else {
+            raw::reply_with_string_buffer(
+                self.ctx,
+                formatted.as_ptr().cast::<c_char>(),
+                formatted.len(),
+            )
+        }
+    }
+
+    /// Reply with an array of integers, without building a [RedisValue] for
+    /// each element as replying with a [RedisValue::Array] would.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_i64_slice(&self, values: &[i64]) -> raw::Status {
+        self.note_reply();
+        raw::reply_with_array(self.ctx, values.len() as c_long);
+        for &v in values {
+            raw::reply_with_long_long(self.ctx, v);
+        }
+        raw::Status::Ok
+    }
+
+    /// Reply with an array of the values yielded by `iter`, without collecting
+    /// them into a [RedisValue::Array] first. The length of the array is taken
+    /// from [ExactSizeIterator::len], so an iterator yielding fewer values is
+    /// padded with nulls, and the values beyond its length are ignored, to keep
+    /// the reply well formed.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_from_exact_iter(
+        &self,
+        iter: impl ExactSizeIterator<Item = RedisValue>,
+    ) -> raw::Status {
+        self.note_reply();
+        let len = iter.len();
+        raw::reply_with_array(self.ctx, len as c_long);
+        let mut replied = 0;
+        for value in iter.take(len) {
+            self.reply(Ok(value));
+            replied += 1;
+        }
+        if replied < len {
+            self.log_warning("reply_from_exact_iter got an iterator shorter than its length");
+            for _ in replied..len {
+                raw::reply_with_null(self.ctx);
+            }
+        }
+        raw::Status::Ok
+    }
+
+    /// Reply with a map holding the given entries, in order and without removing
+    /// duplicate keys, for example to forward a reply received from another server.
+    /// Unlike [RedisValue::MapEntries], the keys can be any [RedisValue].
+    /// RESP2 clients get the entries as a flat array of keys and values.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_with_map(&self, entries: Vec<(RedisValue, RedisValue)>) -> raw::Status {
+        self.note_reply();
+        raw::reply_with_map(self.ctx, entries.len() as c_long);
+        for (key, value) in entries {
+            self.reply(Ok(key));
+            self.reply(Ok(value));
+        }
+        raw::Status::Ok
+    }
+
+    /// Reply with a flat array of keys and values, `[k1, v1, k2, v2, ...]`, in
+    /// the order yielded by `pairs`, for both RESP2 and RESP3 clients. Unlike
+    /// [Self::reply_with_map], a RESP3 client does not get a map, so the reply
+    /// is the same whatever the protocol of the client, like `HGETALL` replies
+    /// to RESP2 clients. Sort the pairs first when they come from a
+    /// [std::collections::HashMap] and the order matters.
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_flat_pairs<K, V>(&self, pairs: impl IntoIterator<Item = (K, V)>) -> raw::Status
+    where
+        K: Into<RedisValue>,
+        V: Into<RedisValue>,
+    {
+        self.note_reply();
+        let pairs: Vec<(K, V)> = pairs.into_iter().collect();
+        raw::reply_with_array(self.ctx, (pairs.len() * 2) as c_long);
+        for (key, value) in pairs {
+            self.reply(Ok(key.into()));
+            self.reply(Ok(value.into()));
+        }
+        raw::Status::Ok
+    }
+
+    /// Reply with a value given in the RESP2 or RESP3 wire format, for example
+    /// a reply received from another server, when none of the typed reply
+    /// methods fits.
+    ///
+    /// This is an advanced escape hatch. The module API has no way to write
+    /// raw protocol to a client, so the bytes are parsed with the rules of
+    /// [RedisValue::from_resp_bytes] and the parsed value is replied with the
+    /// typed reply functions. Writing the bytes as they are would break the
+    /// connection of the client if they were malformed, or if they were in
+    /// RESP3 while the client uses RESP2. Instead, malformed bytes are an
+    /// error and nothing is replied, and RESP3 types are downgraded for RESP2
+    /// clients as usual. The reply may also differ from the given bytes:
+    /// attributes are dropped, a null array (`*-1`) becomes a null bulk string
+    /// (`$-1`), map keys are bulk strings and the elements of a set may be
+    /// reordered.
+    pub fn reply_raw_protocol(&self, bytes: &[u8]) -> Result<raw::Status, RedisError> {
+        let value = crate::redisvalue_resp::parse_resp_reply(bytes)?;
+        Ok(self.reply(Ok(value)))
+    }
+
+    /// Start an array reply whose length is set once all the elements were
+    /// replied, for example when streaming the results of a scan.
+    /// See [DeferredReply].
+    #[must_use]
+    pub fn reply_array_deferred(&self) -> DeferredReply {
+        DeferredReply::new(self, DeferredReplyKind::Array)
+    }
+
+    /// Start a map reply whose number of entries is set once all the entries
+    /// were replied. RESP2 clients get a flat array of keys and values.
+    /// See [DeferredReply].
+    #[must_use]
+    pub fn reply_map_deferred(&self) -> DeferredReply {
+        DeferredReply::new(self, DeferredReplyKind::Map)
+    }
+
+    /// Start a set reply whose length is set once all the elements were
+    /// replied. RESP2 clients get an array. See [DeferredReply].
+    #[must_use]
+    pub fn reply_set_deferred(&self) -> DeferredReply {
+        DeferredReply::new(self, DeferredReplyKind::Set)
+    }
+
     pub fn reply_with_key(&self, result: RedisValueKey) -> raw::Status {
+        self.note_reply();
         match result {
             RedisValueKey::Integer(i) => raw::reply_with_long_long(self.ctx, i),
             RedisValueKey::String(s) => {
@@ -491,14 +1167,97 @@ impl Context {
             }
             RedisValueKey::BulkRedisString(s) => raw::reply_with_string(self.ctx, s.inner),
             RedisValueKey::Bool(b) => raw::reply_with_bool(self.ctx, b.into()),
+            RedisValueKey::Double(d) => raw::reply_with_double(self.ctx, d.0),
+        }
+    }
+
+    /// Reply with the value returned by a command handler, used by the commands
+    /// registered with [crate::redis_module] and the `command` attribute.
+    ///
+    /// If the command returns [RedisValue::NoReply] after a [bulk_reply::BulkReply] was
+    /// aborted, and nothing was replied since, `ERR bulk reply aborted` is
+    /// replied so that the client is not left waiting.
+    ///
+    /// With the `test` feature, it also warns about the mistakes that leave a
+    /// client without a reply or with two replies: returning
+    /// [RedisValue::NoReply] without replying or blocking the client, and
+    /// returning a value after replying directly with the context.
+    #[doc(hidden)]
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_command_result(&self, result: RedisResult) -> raw::Status {
+        if self.bulk_aborted.take() && matches!(result, Ok(RedisValue::NoReply)) {
+            return self.reply_error_string("ERR bulk reply aborted");
+        }
+        #[cfg(feature = "test")]
+        self.check_command_result(&result);
+        self.reply(result)
+    }
+
+    #[cfg(feature = "test")]
+    fn check_command_result(&self, result: &RedisResult) {
+        if self.is_keys_position_request() {
+            return;
+        }
+        match result {
+            Ok(RedisValue::NoReply) if !self.replied.get() && !self.blocked.get() => self
+                .log_warning(
+                    "The command returned RedisValue::NoReply without replying or blocking \
+                     the client, the client will never get a reply",
+                ),
+            Ok(RedisValue::NoReply) => (),
+            _ if self.replied.get() => self.log_warning(
+                "The command replied with the context and also returned a value, the \
+                 client gets two replies",
+            ),
+            _ => (),
         }
     }
 
+    /// Record that the command replied, see [Self::reply_command_result].
+    #[inline]
+    fn note_reply(&self) {
+        self.bulk_aborted.set(false);
+        #[cfg(feature = "test")]
+        self.replied.set(true);
+    }
+
+    /// Record that the command blocked the client, see [Self::reply_command_result].
+    #[inline]
+    pub(crate) fn note_blocked(&self) {
+        #[cfg(feature = "test")]
+        self.blocked.set(true);
+    }
+
+    /// Values with containers nested deeper than [Self::set_max_reply_depth] are
+    /// not replied, an error is replied instead.
+    ///
     /// # Panics
     ///
     /// Will panic if methods used are missing in redismodule.h
     #[allow(clippy::must_use_candidate)]
     pub fn reply(&self, result: RedisResult) -> raw::Status {
+        if !matches!(result, Ok(RedisValue::NoReply)) {
+            self.note_reply();
+        }
+        if let Ok(value) = &result {
+            let depth = value.depth();
+            if depth > MAX_REPLY_DEPTH.load(Ordering::Relaxed) {
+                self.log_warning(&format!(
+                    "Refusing to reply a value nested {depth} levels deep"
+                ));
+                if let Ok(value) = result {
+                    crate::redisvalue::drop_nested(value);
+                }
+                self.reply_error_string("ERR reply nested too deeply");
+                return raw::Status::Err;
+            }
+        }
+        self.reply_value(result)
+    }
+
+    /// Same as [Self::reply], without the depth check, which was already done
+    /// for the value enclosing `result`.
+    fn reply_value(&self, result: RedisResult) -> raw::Status {
         match result {
             Ok(RedisValue::Bool(v)) => raw::reply_with_bool(self.ctx, v.into()),
             Ok(RedisValue::Integer(v)) => raw::reply_with_long_long(self.ctx, v),
@@ -518,6 +1277,13 @@ impl Context {
             }
 
             Ok(RedisValue::BigNumber(s)) => {
+                if !is_big_number(&s) {
+                    // Reply with an error rather than a malformed big number, which
+                    // would break the connection, and keep any enclosing array valid.
+                    self.log_warning(&format!("Refusing to reply an invalid big number: {s:?}"));
+                    self.reply_error_string("ERR invalid big number");
+                    return raw::Status::Err;
+                }
                 raw::reply_with_big_number(self.ctx, s.as_ptr().cast::<c_char>(), s.len())
             }
 
@@ -537,8 +1303,31 @@ impl Context {
             Ok(RedisValue::Array(array)) => {
                 raw::reply_with_array(self.ctx, array.len() as c_long);
 
+                // Large flat arrays of strings are common, so reply the strings
+                // directly, copying the simple strings into a single reused buffer
+                // to NUL terminate them.
+                let mut buf = Vec::new();
                 for elem in array {
-                    self.reply(Ok(elem));
+                    match elem {
+                        RedisValue::BulkString(s) => raw::reply_with_string_buffer(
+                            self.ctx,
+                            s.as_ptr().cast::<c_char>(),
+                            s.len(),
+                        ),
+                        RedisValue::StringBuffer(s) => raw::reply_with_string_buffer(
+                            self.ctx,
+                            s.as_ptr().cast::<c_char>(),
+                            s.len(),
+                        ),
+                        RedisValue::BulkRedisString(s) => raw::reply_with_string(self.ctx, s.inner),
+                        RedisValue::SimpleString(s) if !s.contains('\0') => {
+                            buf.clear();
+                            buf.extend_from_slice(s.as_bytes());
+                            buf.push(0);
+                            raw::reply_with_simple_string(self.ctx, buf.as_ptr().cast())
+                        }
+                        elem => self.reply_value(Ok(elem)),
+                    };
                 }
 
                 raw::Status::Ok
@@ -549,7 +1338,7 @@ impl Context {
 
                 for (key, value) in map {
                     self.reply_with_key(key);
-                    self.reply(Ok(value));
+                    self.reply_value(Ok(value));
                 }
 
                 raw::Status::Ok
@@ -560,7 +1349,18 @@ impl Context {
 
                 for (key, value) in map {
                     self.reply_with_key(key);
-                    self.reply(Ok(value));
+                    self.reply_value(Ok(value));
+                }
+
+                raw::Status::Ok
+            }
+
+            Ok(RedisValue::MapEntries(entries)) => {
+                raw::reply_with_map(self.ctx, entries.len() as c_long);
+
+                for (key, value) in entries {
+                    self.reply_with_key(key);
+                    self.reply_value(Ok(value));
                 }
 
                 raw::Status::Ok
@@ -590,6 +1390,8 @@ impl Context {
 
             Ok(RedisValue::StaticError(s)) => self.reply_error_string(s),
 
+            Ok(RedisValue::Error(s)) => self.reply_error_string(&s),
+
             Err(RedisError::WrongArity) => unsafe {
                 if self.is_keys_position_request() {
                     // We can't return a result since we don't have a client
@@ -606,14 +1408,89 @@ impl Context {
             Err(RedisError::String(s)) => self.reply_error_string(s.as_str()),
 
             Err(RedisError::Str(s)) => self.reply_error_string(s),
+
+            Err(RedisError::Detailed(err)) => {
+                let ErrorDetails {
+                    code,
+                    message,
+                    details,
+                } = *err;
+                let details = details
+                    .into_iter()
+                    .map(|(key, value)| (key.into(), value.into()))
+                    .collect();
+                self.reply_error_with_details(&code, &message, details)
+            }
         }
     }
 
+    /// Reply with any value implementing [serde::Serialize], see
+    /// [crate::redisvalue_serde::to_redis_value] for how values are mapped.
+    /// A value that can not be converted is replied as an error.
+    #[cfg(feature = "serde-conversions")]
+    #[allow(clippy::must_use_candidate)]
+    pub fn reply_serialized<T: serde::Serialize + ?Sized>(&self, value: &T) -> raw::Status {
+        self.reply(crate::redisvalue_serde::to_redis_value(value).map_err(RedisError::from))
+    }
+
+    /// Open a key for reading. The same key can be opened several times during a
+    /// command, for example by different helper functions: each handle is
+    /// independent and closed when dropped. A handle must not be used after the
+    /// key was written, with a writable handle or a command run with
+    /// [Self::call], since the value it points to may have been replaced.
     #[must_use]
     pub fn open_key(&self, key: &RedisString) -> RedisKey {
         RedisKey::open(self.ctx, key)
     }
 
+    /// Open a key for reading, reusing the handle opened by a previous call for
+    /// the same key during the command instead of opening the key again.
+    ///
+    /// The cached handles are dropped once a key may have been written: when a
+    /// [RedisKeyWritable] is opened or closed, by any context, and when a command
+    /// is run with [Self::call] or its variants. While a writable handle is open
+    /// the key is opened again on every call, since it can be written at any
+    /// time. As with [Self::open_key], a handle obtained before a write must not
+    /// be used after it.
+    #[must_use]
+    pub fn open_key_cached(&self, key: &RedisString) -> Rc<RedisKey> {
+        let mut open_keys = self.open_keys.borrow_mut();
+        let Some(epoch) = key_write_epoch() else {
+            open_keys.clear();
+            return Rc::new(self.open_key(key));
+        };
+        if self.open_keys_epoch.replace(epoch) != epoch {
+            open_keys.clear();
+        }
+        if let Some((_, open_key)) = open_keys
+            .iter()
+            .find(|(name, _)| name.as_slice() == key.as_slice())
+        {
+            return Rc::clone(open_key);
+        }
+        let open_key = Rc::new(self.open_key(key));
+        open_keys.push((key.as_slice().to_vec(), Rc::clone(&open_key)));
+        open_key
+    }
+
+    /// Drop the handles cached by [Self::open_key_cached].
+    fn invalidate_open_keys(&self) {
+        self.open_keys.borrow_mut().clear();
+    }
+
+    /// Open a key for reading and verify that it holds a value of `expected_type`.
+    /// A key that does not exist is returned as `Ok` (see [RedisKey::is_null]),
+    /// a key that holds another type is a [RedisError::WrongType].
+    pub fn try_open_key(
+        &self,
+        key: &RedisString,
+        expected_type: raw::KeyType,
+    ) -> Result<RedisKey, RedisError> {
+        let key = RedisKey::open(self.ctx, key);
+        verify_key_type(key.key_inner, expected_type)?;
+        Ok(key)
+    }
+
     #[must_use]
     pub fn open_key_with_flags(&self, key: &RedisString, flags: KeyFlags) -> RedisKey {
         RedisKey::open_with_flags(self.ctx, key, flags)
@@ -633,13 +1510,62 @@ impl Context {
         RedisKeyWritable::open_with_flags(self.ctx, key, flags)
     }
 
+    /// Replicate the current command, as is, to the replica and AOF.
+    ///
+    /// Replicating the command verbatim and also replicating commands with
+    /// [Self::replicate], or replicating verbatim twice, would make the replicas
+    /// diverge. Such a conflicting call is not replicated and a warning is logged.
     pub fn replicate_verbatim(&self) {
+        if self.replication.get() != Replication::None {
+            self.log_warning(&format!(
+                "replicate_verbatim ignored, the command was already replicated ({:?})",
+                self.replication.get()
+            ));
+            return;
+        }
+        self.replication.set(Replication::Verbatim);
         raw::replicate_verbatim(self.ctx);
     }
 
     /// Replicate command to the replica and AOF.
+    ///
+    /// May be called multiple times in the same command, but not after
+    /// [Self::replicate_verbatim], in which case the call is not replicated and
+    /// a warning is logged.
     pub fn replicate<'a, T: Into<StrCallArgs<'a>>>(&self, command: &str, args: T) {
-        raw::replicate(self.ctx, command, args);
+        self.propagate(PropagationTargets::all(), command, args);
+    }
+
+    /// Same as [Self::replicate], but only to the given targets, for example only to
+    /// the replicas for data that does not need to survive a restart.
+    ///
+    /// A command called with [CallOptionsBuilder::replicate] (the `!` call option)
+    /// is replicated to both the replicas and the AOF. To send it to one of them
+    /// only, call it without the option and propagate it with this function.
+    pub fn propagate<'a, T: Into<StrCallArgs<'a>>>(
+        &self,
+        targets: PropagationTargets,
+        command: &str,
+        args: T,
+    ) {
+        if targets.is_empty() {
+            return;
+        }
+        if self.replication.get() == Replication::Verbatim {
+            self.log_warning(&format!(
+                "replicate '{command}' ignored, the command was already replicated verbatim"
+            ));
+            return;
+        }
+        self.replication.set(Replication::Commands);
+        let fmt: &[u8] = if !targets.contains(PropagationTargets::AOF) {
+            b"vA\0"
+        } else if !targets.contains(PropagationTargets::REPLICAS) {
+            b"vR\0"
+        } else {
+            b"v\0"
+        };
+        raw::replicate_with_format(self.ctx, command, fmt.as_ptr().cast(), args);
     }
 
     #[must_use]
@@ -647,6 +1573,52 @@ impl Context {
         RedisString::create(NonNull::new(self.ctx), s)
     }
 
+    /// Create a string holding the concatenation of `parts`, for example to build
+    /// a key such as `prefix:{userid}:field`.
+    ///
+    /// The total length is computed first and the parts are gathered in a buffer
+    /// of exactly that size, which is then copied once into the Redis string.
+    /// Appending the parts to the Redis string one by one instead could
+    /// reallocate it on every append.
+    #[must_use]
+    pub fn create_string_concat(&self, parts: &[&[u8]]) -> RedisString {
+        if let [part] = parts {
+            return RedisString::create_from_slice(self.ctx, part);
+        }
+        let len = parts.iter().map(|part| part.len()).sum();
+        let mut buffer = Vec::with_capacity(len);
+        parts.iter().for_each(|part| buffer.extend_from_slice(part));
+        RedisString::create_from_slice(self.ctx, &buffer)
+    }
+
+    /// Create a string from a buffer the caller owns and does not need anymore,
+    /// for example a large value built in a `Vec`.
+    ///
+    /// The module API has no way to hand a buffer over to Redis, so the bytes
+    /// are copied once into the Redis string and the `Vec` is freed. Unlike
+    /// [Self::create_string], the buffer is not copied a second time to append
+    /// a NUL terminator, and it can contain NUL bytes.
+    #[must_use]
+    pub fn create_string_from_vec(&self, bytes: Vec<u8>) -> RedisString {
+        RedisString::create_from_slice(self.ctx, &bytes)
+    }
+
+    /// Create a string holding the decimal representation of `value`, formatted
+    /// by Redis exactly as native commands such as `INCRBY` store integers.
+    #[must_use]
+    pub fn create_string_from_i64(&self, value: i64) -> RedisString {
+        let inner = unsafe { raw::RedisModule_CreateStringFromLongLong.unwrap()(self.ctx, value) };
+        RedisString::from_redis_module_string(self.ctx, inner)
+    }
+
+    /// Create a string holding `value` formatted by Redis, which differs from
+    /// Rust's formatting for example for infinity (`inf`) and exponents.
+    #[must_use]
+    pub fn create_string_from_f64(&self, value: f64) -> RedisString {
+        let inner = unsafe { raw::RedisModule_CreateStringFromDouble.unwrap()(self.ctx, value) };
+        RedisString::from_redis_module_string(self.ctx, inner)
+    }
+
     #[must_use]
     pub const fn get_raw(&self) -> *mut raw::RedisModuleCtx {
         self.ctx
@@ -663,6 +1635,21 @@ impl Context {
         raw::export_shared_api(self.ctx, func, name);
     }
 
+    /// Fire the keyspace notification `event` for `keyname`.
+    ///
+    /// Notifications are delivered synchronously: the callbacks that modules
+    /// subscribed with `RM_SubscribeToKeyspaceEvents` run before this returns, and
+    /// the Pub/Sub messages are added to the output buffers of the subscribed
+    /// clients, which are written to the sockets after the command returns.
+    /// There is nothing to flush, but a subscriber that reads the key sees its
+    /// state at the time of the notification, so fire it only after the key was
+    /// modified. Writes that must follow the notification belong in
+    /// [Self::add_post_notification_job].
+    ///
+    /// Commands replicated with [Self::replicate] are propagated after the
+    /// command returns, so after its notifications were delivered. Replicas
+    /// fire their own notifications when they execute the replicated commands.
+    ///
     /// # Safety
     ///
     /// See [raw::notify_keyspace_event].
@@ -687,6 +1674,29 @@ impl Context {
         }
     }
 
+    /// The current time in microseconds of the monotonic clock Redis uses to
+    /// measure durations, such as the command latencies of `INFO commandstats`.
+    /// Only differences between two values are meaningful. Redis versions
+    /// before 7.0 do not export their clock, a clock of the module is used instead.
+    #[must_use]
+    pub fn microseconds(&self) -> u64 {
+        monotonic_microseconds()
+    }
+
+    /// Start a [LatencyTimer], which calls `on_drop` with the elapsed time when
+    /// it is dropped, typically at the end of the command:
+    ///
+    /// ```ignore
+    /// let _timer = ctx.latency_timer(|elapsed| STATS.lock(ctx).record(elapsed));
+    /// ```
+    #[must_use]
+    pub fn latency_timer<F: FnOnce(Duration)>(&self, on_drop: F) -> LatencyTimer<F> {
+        LatencyTimer {
+            start: monotonic_microseconds(),
+            on_drop: Some(on_drop),
+        }
+    }
+
     /// Returns the redis version either by calling `RedisModule_GetServerVersion` API,
     /// Or if it is not available, by calling "info server" API and parsing the reply
     pub fn get_redis_version(&self) -> Result<Version, RedisError> {
@@ -698,20 +1708,66 @@ impl Context {
         self.get_redis_version_internal(true)
     }
 
-    pub fn version_from_info(info: RedisValue) -> Result<Version, RedisError> {
-        if let RedisValue::SimpleString(info_str) = info {
-            if let Some(ver) = utils::get_regexp_captures(
-                info_str.as_str(),
-                r"(?m)\bredis_version:([0-9]+)\.([0-9]+)\.([0-9]+)\b",
-            ) {
-                return Ok(Version {
-                    major: ver[0].parse::<c_int>().unwrap(),
-                    minor: ver[1].parse::<c_int>().unwrap(),
-                    patch: ver[2].parse::<c_int>().unwrap(),
-                });
-            }
+    /// The name the module was registered with by [crate::redis_module], for
+    /// example to prefix logs or info sections without repeating it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called before the module is registered in `RedisModule_OnLoad`.
+    #[must_use]
+    pub fn module_name(&self) -> &str {
+        &module_identity().0
+    }
+
+    /// The version the module was registered with by [crate::redis_module].
+    ///
+    /// # Panics
+    ///
+    /// Panics if called before the module is registered in `RedisModule_OnLoad`.
+    #[must_use]
+    pub fn module_version(&self) -> i32 {
+        module_identity().1
+    }
+
+    /// Same as [Self::get_redis_version], but the version is only determined on the
+    /// first successful call and then remembered, as it can not change while the
+    /// module is loaded. Prefer it when checking the version in hot paths, as on
+    /// servers without `RedisModule_GetServerVersion` the version is parsed from
+    /// `info server`.
+    pub fn get_redis_version_cached(&self) -> Result<Version, RedisError> {
+        if let Some(version) = REDIS_VERSION.get() {
+            return Ok(*version);
         }
-        Err(RedisError::Str("Error getting redis_version"))
+        let version = self.get_redis_version()?;
+        Ok(*REDIS_VERSION.get_or_init(|| version))
+    }
+
+    /// Parse the version from the `redis_version` field of an `info server` reply.
+    /// A missing patch number is taken as 0. Returns an error, rather than
+    /// panicking, when the reply does not contain a version.
+    pub fn version_from_info(info: RedisValue) -> Result<Version, RedisError> {
+        let info_str = match &info {
+            RedisValue::SimpleString(s) | RedisValue::BulkString(s) => s.as_str(),
+            RedisValue::BulkRedisString(s) => s.try_as_str()?,
+            RedisValue::StringBuffer(b) => std::str::from_utf8(b)?,
+            _ => return Err(RedisError::Str("Error getting redis_version")),
+        };
+        let ver = utils::get_regexp_captures(
+            info_str,
+            r"(?m)\bredis_version:([0-9]+)\.([0-9]+)(?:\.([0-9]+))?\b",
+        )
+        .filter(|ver| ver.len() >= 3)
+        .ok_or(RedisError::Str("Error getting redis_version"))?;
+        let parse = |part: &str| {
+            part.parse::<c_int>().map_err(|e| {
+                RedisError::String(format!("Error parsing redis_version '{part}': {e}"))
+            })
+        };
+        Ok(Version {
+            major: parse(ver[0])?,
+            minor: parse(ver[1])?,
+            patch: if ver[2].is_empty() { 0 } else { parse(ver[2])? },
+        })
     }
 
     #[allow(clippy::not_unsafe_ptr_arg_deref)]
@@ -746,10 +1802,76 @@ impl Context {
         })
     }
 
+    /// Return `true` if the command is running inside a `MULTI`/`EXEC` transaction.
+    /// Clients can not be blocked inside a transaction, so blocking commands
+    /// should take a non blocking path instead, as `BLPOP` does.
+    /// See also [ContextFlags::DENY_BLOCKING] which is also set for Lua scripts
+    /// and `RM_Call`.
+    pub fn in_transaction(&self) -> bool {
+        self.get_flags().contains(ContextFlags::MULTI)
+    }
+
+    /// Return an error, the same `READONLY` error Redis replies to writes on a
+    /// replica, unless this server is a primary.
+    ///
+    /// Redis does not expose whether a failover is in progress. During a failover,
+    /// `FAILOVER` or `CLUSTER FAILOVER`, the primary pauses the clients running
+    /// commands declared with the `write` flag until the replica took over, so a
+    /// write command should be declared as such rather than detect the failover.
+    /// Once the role changed, the demoted server is a replica and this check
+    /// rejects the writes that would otherwise be lost. Modules that need to
+    /// act on the role change can use the
+    /// [crate::server_events::ServerEventHandler::RuleChanged] event.
+    pub fn ensure_primary(&self) -> Result<(), RedisError> {
+        if self.get_flags().contains(ContextFlags::MASTER) {
+            Ok(())
+        } else {
+            Err(RedisError::Str(
+                "READONLY You can't write against a read only replica.",
+            ))
+        }
+    }
+
     /// Return the current user name attached to the context
     pub fn get_current_user(&self) -> RedisString {
         let user = unsafe { raw::RedisModule_GetCurrentUserName.unwrap()(self.ctx) };
-        RedisString::from_redis_module_string(ptr::null_mut(), user)
+        // The string is added to the auto memory of the context, so it must be
+        // freed with the context.
+        RedisString::from_redis_module_string(self.ctx, user)
+    }
+
+    /// Return the name of the user the client with the given id is authenticated
+    /// as, for example to audit the commands seen by a command filter. An unknown
+    /// client id, or a client without a user such as the one running a Lua
+    /// script, is an error.
+    pub fn get_client_username(&self, client_id: u64) -> Result<RedisString, RedisError> {
+        let user = unsafe { raw::RedisModule_GetClientUserNameById.unwrap()(self.ctx, client_id) };
+        if user.is_null() {
+            let no_such_client =
+                std::io::Error::last_os_error().raw_os_error() == Some(libc::ENOENT);
+            return Err(if no_such_client {
+                RedisError::String(format!("ERR no client with id {client_id}"))
+            } else {
+                RedisError::String(format!("ERR client {client_id} has no user"))
+            });
+        }
+        Ok(RedisString::from_redis_module_string(self.ctx, user))
+    }
+
+    /// Return the X.509 certificate the client with the given id presented when
+    /// connecting over TLS, or `None` if the client does not exist, is not using
+    /// TLS or did not present a certificate.
+    ///
+    /// Redis returns the certificate PEM encoded. The bytes are copied out,
+    /// so the result does not depend on the context. A module can map the
+    /// certificate subject to a user name and pass it to [Self::authenticate_user].
+    pub fn get_client_cert(&self, client_id: u64) -> Option<Vec<u8>> {
+        let cert = unsafe { raw::RedisModule_GetClientCertificate.unwrap()(self.ctx, client_id) };
+        if cert.is_null() {
+            return None;
+        }
+        let cert = RedisString::from_redis_module_string(self.ctx, cert);
+        Some(cert.as_slice().to_vec())
     }
 
     /// Attach the given user to the current context so each operation performed from
@@ -812,12 +1934,16 @@ impl Context {
         /// as a logical bug that need to be fixed in the module, an attempt to protect against
         /// infinite loops by halting the execution could result in violation of the feature correctness
         /// and so Redis will make no attempt to protect the module from infinite loops.
+        ///
+        /// The callback is dropped once the job ran. Redis refuses jobs while loading
+        /// and on read only replicas, in which case [Status::Err] is returned and the
+        /// callback is dropped without running.
         pub fn add_post_notification_job<F: FnOnce(&Context) + 'static>(
             &self,
             callback: F,
         ) -> Status {
             let callback = Box::into_raw(Box::new(Some(callback)));
-            unsafe {
+            let status: Status = unsafe {
                 RedisModule_AddPostNotificationJob(
                     self.ctx,
                     Some(post_notification_job::<F>),
@@ -825,7 +1951,145 @@ impl Context {
                     Some(post_notification_job_free_callback::<F>),
                 )
             }
-            .into()
+            .into();
+            if status == Status::Err {
+                // Redis does not call the free callback for a job it did not accept.
+                drop(unsafe { Box::from_raw(callback) });
+            }
+            status
+        }
+    );
+
+    api!(
+        [RedisModule_GetCommand, RedisModule_SetCommandACLCategories],
+        /// Set the ACL categories of a command registered by the module, for example
+        /// `&["read", "fast"]`, so ACL rules such as `-@write` apply to it. The given
+        /// categories replace the ones set before. A leading `@` is ignored.
+        ///
+        /// Redis only allows this while the module is loading, so call it from the
+        /// module's `init` function. An unknown category, or a call after loading,
+        /// returns an error.
+        pub fn set_command_acl_categories(
+            &self,
+            command: &str,
+            categories: &[&str],
+        ) -> Result<(), RedisError> {
+            let name = CString::new(command)?;
+            let cmd = unsafe { RedisModule_GetCommand(self.ctx, name.as_ptr()) };
+            if cmd.is_null() {
+                return Err(RedisError::String(format!(
+                    "Command {command} is not registered by the module"
+                )));
+            }
+            let categories = categories
+                .iter()
+                .map(|c| c.trim_start_matches('@'))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let categories_c = CString::new(categories.as_str())?;
+            if unsafe { RedisModule_SetCommandACLCategories(cmd, categories_c.as_ptr()) }
+                == raw::Status::Err as c_int
+            {
+                return Err(RedisError::String(format!(
+                    "Failed setting ACL categories '{categories}' of command {command}"
+                )));
+            }
+            Ok(())
+        }
+    );
+
+    api!(
+        [RedisModule_GetClientNameById],
+        /// Return the name the client with the given id set with `CLIENT SETNAME`,
+        /// or [None] if the client does not exist or has no name.
+        pub fn get_client_name(&self, client_id: u64) -> Option<RedisString> {
+            let name = unsafe { RedisModule_GetClientNameById(self.ctx, client_id) };
+            if name.is_null() {
+                return None;
+            }
+            Some(RedisString::from_redis_module_string(self.ctx, name))
+        }
+    );
+
+    api!(
+        [RedisModule_SetClientNameById],
+        /// Set the name of the client with the given id, as `CLIENT SETNAME` does.
+        /// Fails if the client does not exist or the name contains characters
+        /// Redis does not allow in client names, such as spaces or newlines.
+        pub fn set_client_name(
+            &self,
+            client_id: u64,
+            name: &RedisString,
+        ) -> Result<(), RedisError> {
+            let status: raw::Status =
+                unsafe { RedisModule_SetClientNameById(client_id, name.inner) }.into();
+            match status {
+                raw::Status::Ok => Ok(()),
+                raw::Status::Err => {
+                    Err(RedisError::Str("ERR no such client or invalid client name"))
+                }
+            }
+        }
+    );
+
+    api!(
+        [RedisModule_GetCommandKeys],
+        /// Return the positions of the keys in `args`, a full command line starting
+        /// with the command name, for example to route the keys of a command to
+        /// their shards. The keys are found with the key specs of the command, so
+        /// commands such as `GEORADIUS ... STORE` or `SORT ... STORE` are handled.
+        /// A command without keys gives an empty vector, an unknown command or a
+        /// wrong number of arguments is an error.
+        pub fn get_command_keys(&self, args: &[&[u8]]) -> Result<Vec<usize>, RedisError> {
+            let mut args = StrCallArgs::from(args);
+            let args = args.args_mut();
+            let mut num_keys: c_int = 0;
+            let positions = unsafe {
+                RedisModule_GetCommandKeys(
+                    self.ctx,
+                    args.as_mut_ptr(),
+                    args.len() as c_int,
+                    &mut num_keys,
+                )
+            };
+            let positions = command_keys(positions, num_keys)?;
+            Ok(positions.into_iter().map(|pos| pos as usize).collect())
+        }
+    );
+
+    api!(
+        [RedisModule_GetCommandKeysWithFlags],
+        /// Same as [Self::get_command_keys], with the flags of each key, for example
+        /// [commands::KeySpecFlags::READ_ONLY] or [commands::KeySpecFlags::INSERT].
+        pub fn get_command_keys_with_flags(
+            &self,
+            args: &[&[u8]],
+        ) -> Result<Vec<(usize, commands::KeySpecFlags)>, RedisError> {
+            let mut args = StrCallArgs::from(args);
+            let args = args.args_mut();
+            let mut num_keys: c_int = 0;
+            let mut flags: *mut c_int = ptr::null_mut();
+            let positions = unsafe {
+                RedisModule_GetCommandKeysWithFlags(
+                    self.ctx,
+                    args.as_mut_ptr(),
+                    args.len() as c_int,
+                    &mut num_keys,
+                    &mut flags,
+                )
+            };
+            let positions = command_keys(positions, num_keys)?;
+            let flags = command_keys(flags, num_keys)?;
+            Ok(positions
+                .into_iter()
+                .zip(flags)
+                .map(|(pos, flags)| {
+                    (
+                        pos as usize,
+                        commands::KeySpecFlags::from_bits_truncate(flags as u32),
+                    )
+                })
+                .collect())
         }
     );
 
@@ -873,7 +2137,7 @@ impl Context {
 }
 
 extern "C" fn post_notification_job_free_callback<F: FnOnce(&Context)>(pd: *mut c_void) {
-    unsafe { Box::from_raw(pd as *mut Option<F>) };
+    drop(unsafe { Box::from_raw(pd as *mut Option<F>) });
 }
 
 extern "C" fn post_notification_job<F: FnOnce(&Context)>(
@@ -897,6 +2161,18 @@ extern "C" fn post_notification_job<F: FnOnce(&Context)>(
 
 unsafe impl RedisLockIndicator for Context {}
 
+bitflags! {
+    /// Where [Context::propagate] sends a command.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PropagationTargets : u8 {
+        /// The append only file.
+        const AOF = 1;
+
+        /// The replicas.
+        const REPLICAS = 1 << 1;
+    }
+}
+
 bitflags! {
     /// An object represent ACL permissions.
     /// Used to check ACL permission using `acl_check_key_permission`.
@@ -1383,3 +2659,136 @@ bitflags! {
         const ASYNC_LOADING = raw::REDISMODULE_CTX_FLAGS_ASYNC_LOADING as c_int;
     }
 }
+
+/// Copy and free an array returned by `RedisModule_GetCommandKeys`, which is
+/// null when the command has no keys or when it can not be found.
+fn command_keys(array: *mut c_int, len: c_int) -> Result<Vec<c_int>, RedisError> {
+    if array.is_null() {
+        return match std::io::Error::last_os_error().raw_os_error() {
+            Some(libc::ENOENT) => Err(RedisError::Str("ERR unknown command")),
+            Some(libc::EINVAL) => Err(RedisError::Str("ERR wrong number of arguments")),
+            _ => Ok(Vec::new()),
+        };
+    }
+    let values = unsafe { std::slice::from_raw_parts(array, len as usize) }.to_vec();
+    unsafe { raw::RedisModule_Free.unwrap()(array.cast::<c_void>()) };
+    Ok(values)
+}
+
+/// Whether `s` is a valid RESP3 big number: an optional `-` followed by digits.
+fn is_big_number(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_big_number, Context};
+    use crate::raw::{self, Version};
+    use crate::RedisValue;
+    use std::cell::RefCell;
+    use std::ffi::CStr;
+    use std::os::raw::{c_char, c_int};
+
+    fn version(info: &str) -> Option<Version> {
+        Context::version_from_info(RedisValue::SimpleString(info.to_string())).ok()
+    }
+
+    #[test]
+    fn version_from_info() {
+        let expected = Version {
+            major: 7,
+            minor: 2,
+            patch: 4,
+        };
+        assert_eq!(
+            version("# Server\r\nredis_version:7.2.4\r\nredis_mode:standalone\r\n"),
+            Some(expected)
+        );
+        assert_eq!(version("redis_version:7.2\r\n").map(|v| v.patch), Some(0));
+        assert_eq!(version("# Server\r\nredis_mode:standalone\r\n"), None);
+        assert_eq!(version("redis_version:99999999999.0.0\r\n"), None);
+        assert_eq!(version("redis_version:unstable\r\n"), None);
+        assert!(Context::version_from_info(RedisValue::Integer(1)).is_err());
+    }
+
+    thread_local! {
+        static ERRORS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    }
+
+    extern "C" fn resp3_flags(_ctx: *mut raw::RedisModuleCtx) -> c_int {
+        raw::REDISMODULE_CTX_FLAGS_RESP3 as c_int
+    }
+
+    extern "C" fn reply_with_error(_ctx: *mut raw::RedisModuleCtx, err: *const c_char) -> c_int {
+        let err = unsafe { CStr::from_ptr(err) }
+            .to_string_lossy()
+            .into_owned();
+        ERRORS.with(|errors| errors.borrow_mut().push(err));
+        raw::REDISMODULE_OK as c_int
+    }
+
+    #[test]
+    fn reply_error_with_details_without_attributes() {
+        // Stand in for a RESP3 client of a Redis version without attributes.
+        unsafe {
+            if raw::RedisModule_ReplyWithAttribute.is_some() {
+                return;
+            }
+            raw::RedisModule_GetContextFlags = Some(resp3_flags);
+            raw::RedisModule_ReplyWithError = Some(reply_with_error);
+        }
+        let ctx = Context::dummy();
+        let details = vec![(RedisValue::from("limit"), RedisValue::Integer(100))];
+        ctx.reply_error_with_details("INDEX_FULL", "the index is full", details);
+        let errors = ERRORS.with(RefCell::take);
+        assert_eq!(errors, ["INDEX_FULL the index is full"]);
+    }
+
+    #[test]
+    fn big_number_validation() {
+        assert!(is_big_number("1234567999999999999999999999999999999"));
+        assert!(is_big_number("-42"));
+        assert!(!is_big_number(""));
+        assert!(!is_big_number("-"));
+        assert!(!is_big_number("1.5"));
+        assert!(!is_big_number("+1"));
+        assert!(!is_big_number("12\r\n"));
+    }
+
+    #[test]
+    fn legal_resp_string() {
+        let s = Context::str_as_legal_resp_string("café\r\nnaïve\0");
+        assert_eq!(s.to_bytes(), "café  naïve ".as_bytes());
+    }
+
+    #[test]
+    fn create_string_concat() {
+        crate::test_strings::install();
+        let ctx = Context::dummy();
+        let key = ctx.create_string_concat(&[b"prefix:{", b"42", b"", b"}:field"]);
+        assert_eq!(key.as_slice(), b"prefix:{42}:field");
+        assert!(ctx.create_string_concat(&[]).is_empty());
+        assert_eq!(ctx.create_string_concat(&[b"key"]).as_slice(), b"key");
+    }
+
+    #[test]
+    fn create_string_from_vec() {
+        crate::test_strings::install();
+        let ctx = Context::dummy();
+        let s = ctx.create_string_from_vec(b"binary\0value".to_vec());
+        assert_eq!(s.as_slice(), b"binary\0value");
+        assert!(ctx.create_string_from_vec(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn call_invalid_command_name() {
+        let ctx = Context::dummy();
+        for command in ["", "GET\0SET"] {
+            let err = ctx.call(command, &["key"]).unwrap_err();
+            assert!(err.to_string().starts_with("ERR invalid command name"));
+            let err = ctx.call_fire_and_forget(command, &["key"]).unwrap_err();
+            assert!(err.to_string().starts_with("ERR invalid command name"));
+        }
+    }
+}
@@ -31,6 +31,13 @@ pub enum ModuleChangeSubevent {
     Unloaded,
 }
 
+/// The server events a module can subscribe to.
+///
+/// Redis does not emit a server event when a client runs `RESET`, so there is
+/// no hook to clear per client module state at that point. Modules keeping
+/// such state should key it by client id and validate it when it is used, for
+/// example by checking the client's current user, rather than rely on being
+/// notified of the reset.
 #[derive(Clone)]
 pub enum ServerEventHandler {
     RuleChanged(fn(&Context, ServerRole)),
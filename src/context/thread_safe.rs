@@ -2,9 +2,18 @@ use std::borrow::Borrow;
 use std::cell::UnsafeCell;
 use std::ops::{Deref, DerefMut};
 use std::ptr;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::context::blocked::BlockedClient;
-use crate::{raw, Context, RedisResult};
+use crate::context::StrCallArgs;
+use crate::rediserror::codes;
+use crate::{raw, Context, RedisError, RedisResult};
+
+/// The first delay of [ThreadSafeContext::call_await_ready], doubled after each
+/// retry up to [MAX_LOADING_BACKOFF].
+const MIN_LOADING_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_LOADING_BACKOFF: Duration = Duration::from_secs(1);
 
 pub struct RedisGILGuardScope<'ctx, 'mutex, T, G: RedisLockIndicator> {
     _context: &'ctx G,
@@ -93,6 +102,8 @@ unsafe impl RedisLockIndicator for ContextGuard {}
 
 impl Drop for ContextGuard {
     fn drop(&mut self) {
+        // Close the cached keys before the context is unlocked and freed.
+        self.ctx.invalidate_open_keys();
         unsafe {
             raw::RedisModule_ThreadSafeContextUnlock.unwrap()(self.ctx.ctx);
             raw::RedisModule_FreeThreadSafeContext.unwrap()(self.ctx.ctx);
@@ -175,8 +186,114 @@ impl<B: Send> ThreadSafeContext<B> {
     }
 }
 
+impl<B: Send> ThreadSafeContext<B> {
+    /// Same as [Context::call], but while Redis is loading the dataset and the
+    /// command fails with a `LOADING` error, the call is retried with an
+    /// increasing delay until it succeeds or `timeout` elapses. Any other error
+    /// is returned at once. Once the timeout elapses, an error saying so is
+    /// returned.
+    ///
+    /// The context is locked for each attempt and unlocked while waiting, so that
+    /// Redis can go on loading. This is meant for background initialization
+    /// running alongside the load, waiting for the load from a command or any
+    /// other code holding the lock would never end.
+    pub fn call_await_ready<'a, T: Into<StrCallArgs<'a>> + Copy>(
+        &self,
+        command: &str,
+        args: T,
+        timeout: Duration,
+    ) -> RedisResult {
+        retry_while_loading(timeout, thread::sleep, || self.lock().call(command, args))
+    }
+}
+
+/// Run `call` until it returns anything but a `LOADING` error, waiting with
+/// `sleep` between attempts, or until `timeout` elapses.
+fn retry_while_loading(
+    timeout: Duration,
+    mut sleep: impl FnMut(Duration),
+    mut call: impl FnMut() -> RedisResult,
+) -> RedisResult {
+    let start = Instant::now();
+    let mut waited = Duration::ZERO;
+    let mut backoff = MIN_LOADING_BACKOFF;
+    loop {
+        match call() {
+            Err(err) if err.is(codes::LOADING) => {}
+            res => return res,
+        }
+        let elapsed = start.elapsed().max(waited);
+        if elapsed >= timeout {
+            return Err(RedisError::String(format!(
+                "ERR timed out after {timeout:?} waiting for Redis to load the dataset"
+            )));
+        }
+        let delay = backoff.min(timeout - elapsed);
+        sleep(delay);
+        waited += delay;
+        backoff = (backoff * 2).min(MAX_LOADING_BACKOFF);
+    }
+}
+
 impl<B: Send> Drop for ThreadSafeContext<B> {
     fn drop(&mut self) {
         unsafe { raw::RedisModule_FreeThreadSafeContext.unwrap()(self.ctx) };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisValue;
+
+    const LOADING: RedisError = RedisError::Str("LOADING Redis is loading the dataset in memory");
+
+    #[test]
+    fn retry_while_loading_until_loaded() {
+        let mut attempts = 0;
+        let mut delays = vec![];
+        let res = retry_while_loading(
+            Duration::from_secs(60),
+            |delay| delays.push(delay),
+            || {
+                attempts += 1;
+                if attempts < 4 {
+                    Err(LOADING)
+                } else {
+                    Ok(RedisValue::SimpleStringStatic("OK"))
+                }
+            },
+        );
+        assert!(matches!(res, Ok(RedisValue::SimpleStringStatic("OK"))));
+        assert_eq!(delays, [10, 20, 40].map(Duration::from_millis).to_vec());
+    }
+
+    #[test]
+    fn retry_while_loading_other_errors() {
+        let mut attempts = 0;
+        let res = retry_while_loading(
+            Duration::from_secs(60),
+            |_| panic!("must not wait"),
+            || {
+                attempts += 1;
+                Err(RedisError::Str("ERR unknown command"))
+            },
+        );
+        assert!(matches!(res, Err(RedisError::Str("ERR unknown command"))));
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn retry_while_loading_timeout() {
+        let mut waited = Duration::ZERO;
+        let res = retry_while_loading(
+            Duration::from_millis(100),
+            |delay| waited += delay,
+            || Err(LOADING),
+        );
+        assert_eq!(waited, Duration::from_millis(100));
+        let err = res.unwrap_err();
+        assert!(err.is(codes::ERR));
+        assert!(err.to_string().contains("timed out after 100ms"));
+    }
+}
@@ -4,21 +4,23 @@ use std::ops::DerefMut;
 use std::os::raw::c_void;
 use std::ptr;
 use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::Duration;
 
 use libc::size_t;
-use std::os::raw::c_int;
+use std::os::raw::{c_int, c_longlong};
 
 use raw::KeyType;
 
-use crate::native_types::RedisType;
+use crate::native_types::{registered_type_name, RedisType};
 use crate::raw;
 use crate::redismodule::REDIS_OK;
 pub use crate::redisraw::bindings::*;
-use crate::stream::StreamIterator;
+use crate::stream::{StreamAddId, StreamAddOptions, StreamId, StreamIter, StreamIterator};
 use crate::RedisError;
 use crate::RedisResult;
 use crate::RedisString;
+use crate::RedisValue;
 use bitflags::bitflags;
 
 /// `RedisKey` is an abstraction over a Redis key that allows readonly
@@ -50,6 +52,18 @@ bitflags! {
     }
 }
 
+/// The metric a key is evicted by, see [RedisKey::eviction_metric].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvictionMetric {
+    /// Keys are never evicted: `maxmemory` is not set, or the
+    /// `maxmemory-policy` is `noeviction`.
+    NoEviction,
+    /// The time since the key was last accessed.
+    Lru(Duration),
+    /// The logarithmic access frequency counter of the key.
+    Lfu(u8),
+}
+
 #[derive(Debug)]
 pub struct RedisKey {
     pub(crate) ctx: *mut raw::RedisModuleCtx,
@@ -85,6 +99,11 @@ impl RedisKey {
         Self { ctx, key_inner }
     }
 
+    /// Return the value of a key holding the module type `redis_type`, or `None`
+    /// if the key does not exist. Returns [RedisError::WrongType] if the key holds
+    /// a native Redis type or another module type. `T` must be the type the
+    /// module stores for `redis_type`.
+    ///
     /// # Panics
     ///
     /// Will panic if `RedisModule_ModuleTypeGetValue` is missing in redismodule.h
@@ -111,6 +130,100 @@ impl RedisKey {
         unsafe { raw::RedisModule_KeyType.unwrap()(self.key_inner) }.into()
     }
 
+    /// Returns the name of the type of the value, as the `TYPE` command does:
+    /// `string`, `list`, `hash`, `set`, `zset`, `stream`, or `none` when the key
+    /// does not exist. For a data type created by this module it is the name
+    /// the type was registered with. The name of a data type of another
+    /// module is not available through the module API, so it is `module`.
+    #[must_use]
+    pub fn type_name(&self) -> &'static str {
+        key_type_name(self.key_inner)
+    }
+
+    /// Return the time since the key was last accessed. Only tracked when the
+    /// `maxmemory-policy` is not an LFU policy, otherwise an error is returned.
+    ///
+    /// Opening the key counts as an access, open it with [KeyFlags::NOTOUCH]
+    /// to get the idle time from before the current command.
+    pub fn idle_time(&self) -> Result<Duration, RedisError> {
+        let mut idle: raw::mstime_t = 0;
+        let status: raw::Status = if self.is_null() {
+            raw::Status::Err
+        } else {
+            unsafe { raw::RedisModule_GetLRU.unwrap()(self.key_inner, &mut idle) }.into()
+        };
+        if status == raw::Status::Err {
+            return Err(RedisError::Str("ERR no such key"));
+        }
+        u64::try_from(idle).map(Duration::from_millis).map_err(|_| {
+            RedisError::Str("ERR idle time is not tracked with an LFU maxmemory-policy")
+        })
+    }
+
+    /// Return the logarithmic access frequency counter of the key. Only tracked
+    /// when the `maxmemory-policy` is an LFU policy, otherwise an error is returned.
+    pub fn freq(&self) -> Result<u8, RedisError> {
+        let mut freq: c_longlong = 0;
+        let status: raw::Status = if self.is_null() {
+            raw::Status::Err
+        } else {
+            unsafe { raw::RedisModule_GetLFU.unwrap()(self.key_inner, &mut freq) }.into()
+        };
+        if status == raw::Status::Err {
+            return Err(RedisError::Str("ERR no such key"));
+        }
+        u8::try_from(freq).map_err(|_| {
+            RedisError::Str("ERR access frequency is only tracked with an LFU maxmemory-policy")
+        })
+    }
+
+    /// Return the size of the value, whatever its type: the length of a string
+    /// in bytes, the number of elements of a list, set or sorted set, the number
+    /// of fields of a hash or the number of entries of a stream. A key that does
+    /// not exist has a size of 0. The size of a module type value is not defined,
+    /// so it is an error.
+    pub fn cardinality(&self) -> Result<usize, RedisError> {
+        match self.key_type() {
+            KeyType::Empty => Ok(0),
+            KeyType::Module => Err(RedisError::Str(
+                "ERR cardinality is not defined for module types",
+            )),
+            _ => Ok(unsafe { raw::RedisModule_ValueLength.unwrap()(self.key_inner) }),
+        }
+    }
+
+    /// Return the metric Redis uses to evict the key under the configured
+    /// `maxmemory-policy`: the idle time for the LRU (and other non LFU)
+    /// policies, the access frequency for the LFU policies, or
+    /// [EvictionMetric::NoEviction] if keys are never evicted.
+    ///
+    /// Redis tracks the idle time of a key unless the policy is an LFU policy,
+    /// so the metric is the one of [Self::idle_time] and [Self::freq] that is
+    /// tracked, without reading the policy.
+    pub fn eviction_metric(&self) -> Result<EvictionMetric, RedisError> {
+        let flags = unsafe { raw::RedisModule_GetContextFlags.unwrap()(self.ctx) };
+        if flags & raw::REDISMODULE_CTX_FLAGS_EVICT as c_int == 0 {
+            return Ok(EvictionMetric::NoEviction);
+        }
+        match self.idle_time() {
+            Ok(idle) => Ok(EvictionMetric::Lru(idle)),
+            Err(_) => self.freq().map(EvictionMetric::Lfu),
+        }
+    }
+
+    /// Returns `true` if the key holds a value. A key that has expired but was
+    /// not deleted yet does not exist.
+    #[must_use]
+    pub fn exists(&self) -> bool {
+        self.key_type() != KeyType::Empty
+    }
+
+    /// Returns `true` if the key does not exist, the opposite of [Self::exists].
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        !self.exists()
+    }
+
     /// Detects whether the key pointer given to us by Redis is null.
     #[must_use]
     pub fn is_null(&self) -> bool {
@@ -122,6 +235,7 @@ impl RedisKey {
         if self.is_null() {
             Ok(None)
         } else {
+            verify_key_type(self.key_inner, KeyType::String)?;
             let mut length: size_t = 0;
             let dma = raw::string_dma(self.key_inner, &mut length, raw::KeyMode::READ);
             if dma.is_null() {
@@ -134,6 +248,34 @@ impl RedisKey {
         }
     }
 
+    /// Returns the bytes of a string key between `start` and `end` (both inclusive),
+    /// with the same semantics as `GETRANGE`: negative indices count from the end of
+    /// the string and out of range indices are clamped to the string. The result is
+    /// empty if the key does not exist or the range is empty.
+    pub fn string_range(&self, start: i64, end: i64) -> Result<Vec<u8>, RedisError> {
+        let value = self.read()?.unwrap_or_default();
+        Ok(string_range_slice(value, start, end).to_vec())
+    }
+
+    /// Returns the bit at `offset` of a string key, like `GETBIT`. Bits are numbered
+    /// from the most significant bit of the first byte. Bits beyond the end of the
+    /// string, or of a missing key, are 0.
+    pub fn get_bit(&self, offset: u64) -> Result<bool, RedisError> {
+        let value = self.read()?.unwrap_or_default();
+        Ok(bit_at(value, offset))
+    }
+
+    /// Counts the set bits in the bytes between `start` and `end` (both inclusive),
+    /// like `BITCOUNT key start end`. The indices have the same semantics as in
+    /// [`Self::string_range`], use `0` and `-1` to count the whole string.
+    pub fn bit_count(&self, start: i64, end: i64) -> Result<u64, RedisError> {
+        let value = self.read()?.unwrap_or_default();
+        Ok(string_range_slice(value, start, end)
+            .iter()
+            .map(|b| u64::from(b.count_ones()))
+            .sum())
+    }
+
     pub fn hash_get(&self, field: &str) -> Result<Option<RedisString>, RedisError> {
         let val = if self.is_null() {
             None
@@ -171,6 +313,14 @@ impl RedisKey {
         StreamIterator::new(self, None, None, false, reverse)
     }
 
+    /// Returns an iterator over the entries of the stream with IDs between
+    /// `start` and `end`, both included, like `XRANGE`. Use [StreamId::MIN] and
+    /// [StreamId::MAX] for `-` and `+`. A missing key is an empty stream, and a
+    /// key of another type is a [RedisError::WrongType] error.
+    pub fn stream_iter(&self, start: StreamId, end: StreamId) -> Result<StreamIter, RedisError> {
+        StreamIter::new(self, start, end)
+    }
+
     pub fn get_stream_range_iterator(
         &self,
         from: Option<raw::RedisModuleStreamID>,
@@ -193,15 +343,50 @@ impl Drop for RedisKey {
 
 /// `RedisKeyWritable` is an abstraction over a Redis key that allows read and
 /// write operations.
+///
+/// Writing through the key does not publish keyspace notifications, only opening
+/// it may (a `keymiss`, or an `expired` when a lazily expired key is deleted), so
+/// several writes can be published as a single logical change: open the key with
+/// [KeyFlags::NONOTIFY] (and [KeyFlags::NOEXPIRE] if needed), write, and then call
+/// [crate::Context::notify_keyspace_event] once. Commands run with
+/// [crate::Context::call] publish their own notifications.
 pub struct RedisKeyWritable {
     ctx: *mut raw::RedisModuleCtx,
     key_inner: *mut raw::RedisModuleKey,
 }
 
+/// Bumped whenever a [RedisKeyWritable] is opened or closed, or a key is written
+/// otherwise, see [key_write_epoch].
+static KEY_WRITE_EPOCH: AtomicU64 = AtomicU64::new(0);
+
+/// The number of [RedisKeyWritable] handles currently open.
+static WRITABLE_KEYS: AtomicUsize = AtomicUsize::new(0);
+
+/// A value that changes whenever a key may have been written through a
+/// [RedisKeyWritable], whichever context opened it, or `None` while a writable
+/// handle is open, since it can write at any time. Used by
+/// [crate::Context::open_key_cached] to know when its handles may be stale.
+pub(crate) fn key_write_epoch() -> Option<u64> {
+    let epoch = KEY_WRITE_EPOCH.load(Ordering::Relaxed);
+    (WRITABLE_KEYS.load(Ordering::Relaxed) == 0).then_some(epoch)
+}
+
+/// Record that keys may have been written other than through a
+/// [RedisKeyWritable], for example by a command run with [crate::Context::call].
+pub(crate) fn note_key_write() {
+    KEY_WRITE_EPOCH.fetch_add(1, Ordering::Relaxed);
+}
+
 impl RedisKeyWritable {
+    fn from_inner(ctx: *mut raw::RedisModuleCtx, key_inner: *mut raw::RedisModuleKey) -> Self {
+        KEY_WRITE_EPOCH.fetch_add(1, Ordering::Relaxed);
+        WRITABLE_KEYS.fetch_add(1, Ordering::Relaxed);
+        Self { ctx, key_inner }
+    }
+
     pub fn open(ctx: *mut raw::RedisModuleCtx, key: &RedisString) -> Self {
         let key_inner = raw::open_key(ctx, key.inner, to_raw_mode(KeyMode::ReadWrite));
-        Self { ctx, key_inner }
+        Self::from_inner(ctx, key_inner)
     }
 
     pub(crate) fn open_with_flags(
@@ -215,7 +400,7 @@ impl RedisKeyWritable {
             to_raw_mode(KeyMode::ReadWrite),
             flags.bits(),
         );
-        Self { ctx, key_inner }
+        Self::from_inner(ctx, key_inner)
     }
 
     /// Returns `true` if the key is of type [KeyType::Empty].
@@ -243,10 +428,56 @@ impl RedisKeyWritable {
         self.key_type() == KeyType::Empty
     }
 
+    /// Returns `true` if the key holds a value, the opposite of [Self::is_empty].
+    /// A key that has expired but was not deleted yet does not exist.
+    #[must_use]
+    pub fn exists(&self) -> bool {
+        !self.is_empty()
+    }
+
     pub fn as_string_dma(&self) -> Result<StringDMA, RedisError> {
         StringDMA::new(self)
     }
 
+    /// Overwrite part of a string key starting at `offset`, like `SETRANGE`.
+    /// If the string is shorter than `offset + data.len()` it is padded with zero bytes.
+    /// A missing key is created, unless `data` is empty, in which case the value is
+    /// left as is whatever the offset. Returns the length of the string after the
+    /// operation. The string can not grow past 512MB, the limit `SETRANGE` enforces.
+    pub fn set_range(&self, offset: usize, data: &[u8]) -> Result<usize, RedisError> {
+        if data.is_empty() && self.is_empty() {
+            return Ok(0);
+        }
+        let mut dma = self.as_string_dma()?;
+        dma.set_range(offset, data)?;
+        Ok(dma.len())
+    }
+
+    /// Sets or clears the bit at `offset` of a string key, like `SETBIT`, and returns
+    /// the previous value of the bit. The string is grown with zero bytes if it is
+    /// too short, and a missing key is created. The offset must be smaller than
+    /// 2^32, the same limit `SETBIT` enforces.
+    pub fn set_bit(&self, offset: u64, value: bool) -> Result<bool, RedisError> {
+        if offset > MAX_BIT_OFFSET {
+            return Err(RedisError::Str(
+                "ERR bit offset is not an integer or out of range",
+            ));
+        }
+        let mut dma = self.as_string_dma()?;
+        let byte = (offset >> 3) as usize;
+        if dma.len() <= byte {
+            dma.truncate(byte + 1)?;
+        }
+        let mask = bit_mask(offset);
+        let old = dma[byte] & mask != 0;
+        if value {
+            dma[byte] |= mask;
+        } else {
+            dma[byte] &= !mask;
+        }
+        Ok(old)
+    }
+
     #[allow(clippy::must_use_candidate)]
     pub fn hash_set(&self, field: &str, value: RedisString) -> raw::Status {
         raw::hash_set(self.key_inner, field, value.inner)
@@ -348,6 +579,69 @@ impl RedisKeyWritable {
         }
     }
 
+    /// Sets the key to the string `value` and its time to live to `ttl` in one
+    /// step, like `SET key value PX ttl`, overwriting a value of any type. Without
+    /// a `ttl` the key is persisted, clearing the previous time to live, like
+    /// `SET` without `KEEPTTL`, see [Self::string_set_keep_ttl] to keep it.
+    ///
+    /// Unlike a write followed by [Self::set_expire], a single `set` keyspace
+    /// notification is published for the change, so open the key with
+    /// [KeyFlags::NONOTIFY] to avoid a `keymiss` notification when it does not
+    /// exist yet.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `RedisModule_GetKeyNameFromModuleKey` is missing in
+    /// redismodule.h, which is the case before Redis 7.0.
+    pub fn string_set_with_expire(
+        &self,
+        value: &RedisString,
+        ttl: Option<Duration>,
+    ) -> RedisResult {
+        if ttl.is_some_and(|ttl| ttl.as_millis() == 0) {
+            return Err(RedisError::Str("ERR invalid expire time in 'set' command"));
+        }
+        self.string_set_notify(value, |key| match ttl {
+            Some(ttl) => key.set_expire(ttl).map(|_| ()),
+            None => Ok(()),
+        })
+    }
+
+    /// Like [Self::string_set_with_expire], keeping the previous time to live
+    /// of the key, like `SET key value KEEPTTL`.
+    pub fn string_set_keep_ttl(&self, value: &RedisString) -> RedisResult {
+        let ttl = unsafe { raw::RedisModule_GetExpire.unwrap()(self.key_inner) };
+        self.string_set_notify(value, |key| {
+            if ttl == REDISMODULE_NO_EXPIRE.into() {
+                return Ok(());
+            }
+            match raw::set_expire(key.key_inner, ttl) {
+                raw::Status::Ok => Ok(()),
+                raw::Status::Err => Err(RedisError::Str("Error while setting key expire")),
+            }
+        })
+    }
+
+    /// Sets the key to `value`, calls `then` and publishes a `set` notification,
+    /// also when `then` fails since the value was set.
+    fn string_set_notify(
+        &self,
+        value: &RedisString,
+        then: impl FnOnce(&Self) -> Result<(), RedisError>,
+    ) -> RedisResult {
+        let key_name = unsafe { raw::RedisModule_GetKeyNameFromModuleKey.unwrap()(self.key_inner) };
+        let key_name = RedisString::new(
+            NonNull::new(self.ctx),
+            key_name as *mut raw::RedisModuleString,
+        );
+        if raw::string_set(self.key_inner, value.inner) == raw::Status::Err {
+            return Err(RedisError::Str("Error while setting key"));
+        }
+        let res = then(self);
+        unsafe { raw::notify_keyspace_event(self.ctx, raw::NotifyEvent::STRING, "set", &key_name) };
+        res.map(|_| RedisValue::SimpleStringStatic("OK"))
+    }
+
     pub fn write(&self, val: &str) -> RedisResult {
         let val_str = RedisString::create(NonNull::new(self.ctx), val);
         match raw::string_set(self.key_inner, val_str.inner) {
@@ -380,12 +674,18 @@ impl RedisKeyWritable {
         unsafe { raw::RedisModule_KeyType.unwrap()(self.key_inner) }.into()
     }
 
+    /// See [RedisKey::type_name].
+    #[must_use]
+    pub fn type_name(&self) -> &'static str {
+        key_type_name(self.key_inner)
+    }
+
     pub fn open_with_redis_string(
         ctx: *mut raw::RedisModuleCtx,
         key: *mut raw::RedisModuleString,
     ) -> Self {
         let key_inner = raw::open_key(ctx, key, to_raw_mode(KeyMode::ReadWrite));
-        Self { ctx, key_inner }
+        Self::from_inner(ctx, key_inner)
     }
 
     /// # Panics
@@ -410,41 +710,154 @@ impl RedisKeyWritable {
         Ok(Some(value))
     }
 
+    /// Set the key to `value` of the module type `redis_type`, replacing any
+    /// previous value of that type, which Redis frees with the type's `free`
+    /// callback. Returns [RedisError::WrongType] if the key holds another type.
+    ///
     /// # Panics
     ///
     /// Will panic if `RedisModule_ModuleTypeSetValue` is missing in redismodule.h
     pub fn set_value<T>(&self, redis_type: &RedisType, value: T) -> Result<(), RedisError> {
         verify_type(self.key_inner, redis_type)?;
-        let value = Box::into_raw(Box::new(value)).cast::<c_void>();
+        let value = Box::into_raw(Box::new(value));
         let status: raw::Status = unsafe {
             raw::RedisModule_ModuleTypeSetValue.unwrap()(
                 self.key_inner,
                 *redis_type.raw_type.borrow(),
-                value,
+                value.cast::<c_void>(),
             )
         }
         .into();
+        if status == raw::Status::Err {
+            // Redis only takes ownership of the value when it was set.
+            drop(unsafe { Box::from_raw(value) });
+        }
 
         status.into()
     }
 
-    pub fn trim_stream_by_id(
+    /// Adds an entry with the given fields to the stream, like `XADD`, creating
+    /// the stream if the key does not exist, and returns the ID of the entry.
+    ///
+    /// An explicit ID must be greater than the last ID of the stream, otherwise
+    /// the entry is not added and an error is returned, as is the case when the
+    /// key holds another type.
+    pub fn stream_add(
         &self,
-        mut id: raw::RedisModuleStreamID,
-        approx: bool,
-    ) -> Result<usize, RedisError> {
-        let flags = if approx {
-            raw::REDISMODULE_STREAM_TRIM_APPROX
-        } else {
-            0
+        id: StreamAddId,
+        fields: &[(&RedisString, &RedisString)],
+    ) -> Result<StreamId, RedisError> {
+        let (flags, mut id) = match id {
+            StreamAddId::Auto => (
+                raw::REDISMODULE_STREAM_ADD_AUTOID as c_int,
+                raw::RedisModuleStreamID { ms: 0, seq: 0 },
+            ),
+            StreamAddId::Explicit(id) => (0, id.into()),
         };
+        let mut argv: Vec<*mut raw::RedisModuleString> = fields
+            .iter()
+            .flat_map(|(field, value)| [field.inner, value.inner])
+            .collect();
         let res = unsafe {
-            raw::RedisModule_StreamTrimByID.unwrap()(self.key_inner, flags as i32, &mut id)
+            raw::RedisModule_StreamAdd.unwrap()(
+                self.key_inner,
+                flags,
+                &mut id,
+                argv.as_mut_ptr(),
+                fields.len() as i64,
+            )
         };
-        if res <= 0 {
-            Err(RedisError::Str("Failed trimming the stream"))
+        if raw::Status::Ok == res.into() {
+            Ok(id.into())
         } else {
-            Ok(res as usize)
+            Err(stream_add_error())
+        }
+    }
+
+    /// Like [Self::stream_add], with the `NOMKSTREAM` and `MAXLEN` options of
+    /// `XADD`. Returns `None` when the key does not exist and
+    /// [StreamAddOptions::no_mkstream] is set.
+    pub fn stream_add_with_options(
+        &self,
+        id: StreamAddId,
+        fields: &[(&RedisString, &RedisString)],
+        options: StreamAddOptions,
+    ) -> Result<Option<StreamId>, RedisError> {
+        if options.no_mkstream && self.key_type() == KeyType::Empty {
+            return Ok(None);
+        }
+        let id = self.stream_add(id, fields)?;
+        if let Some(max_len) = options.max_len {
+            self.stream_trim_by_length(max_len, options.approx)?;
+        }
+        Ok(Some(id))
+    }
+
+    /// Trims the stream to its `max_len` last entries, like `XTRIM MAXLEN`, and
+    /// returns the number of entries removed. With `approx`, like `MAXLEN ~`,
+    /// only whole nodes of the stream are removed, which is more efficient but
+    /// may leave more than `max_len` entries. A missing key is an empty stream.
+    pub fn stream_trim_by_length(&self, max_len: usize, approx: bool) -> Result<usize, RedisError> {
+        if self.key_type() == KeyType::Empty {
+            return Ok(0);
+        }
+        let res = unsafe {
+            raw::RedisModule_StreamTrimByLength.unwrap()(
+                self.key_inner,
+                stream_trim_flags(approx),
+                max_len as c_longlong,
+            )
+        };
+        stream_trim_result(res)
+    }
+
+    /// Removes the entries of the stream with an ID smaller than `min_id`, like
+    /// `XTRIM MINID`, and returns the number of entries removed. `approx` has the
+    /// same meaning as in [Self::stream_trim_by_length].
+    pub fn stream_trim_by_id(&self, min_id: StreamId, approx: bool) -> Result<usize, RedisError> {
+        if self.key_type() == KeyType::Empty {
+            return Ok(0);
+        }
+        let mut min_id = min_id.into();
+        let res = unsafe {
+            raw::RedisModule_StreamTrimByID.unwrap()(
+                self.key_inner,
+                stream_trim_flags(approx),
+                &mut min_id,
+            )
+        };
+        stream_trim_result(res)
+    }
+
+    /// Deletes the entry with the given ID from the stream, like `XDEL`, and
+    /// returns whether it existed.
+    pub fn stream_delete(&self, id: StreamId) -> Result<bool, RedisError> {
+        if self.key_type() == KeyType::Empty {
+            return Ok(false);
+        }
+        let mut id = id.into();
+        let res = unsafe { raw::RedisModule_StreamDelete.unwrap()(self.key_inner, &mut id) };
+        if raw::Status::Ok == res.into() {
+            return Ok(true);
+        }
+        match std::io::Error::last_os_error().raw_os_error() {
+            Some(libc::ENOENT) => Ok(false),
+            Some(libc::ENOTSUP) => Err(RedisError::WrongType),
+            _ => Err(RedisError::Str("ERR Failed deleting from the stream")),
+        }
+    }
+
+    /// Unlike [Self::stream_trim_by_id], returns an error when no entry was
+    /// removed.
+    #[deprecated = "Please use [`Self::stream_trim_by_id`] instead."]
+    pub fn trim_stream_by_id(
+        &self,
+        id: raw::RedisModuleStreamID,
+        approx: bool,
+    ) -> Result<usize, RedisError> {
+        match self.stream_trim_by_id(id.into(), approx) {
+            Ok(0) | Err(_) => Err(RedisError::Str("Failed trimming the stream")),
+            res => res,
         }
     }
 }
@@ -580,6 +993,7 @@ impl<'a> DerefMut for StringDMA<'a> {
 
 impl<'a> StringDMA<'a> {
     fn new(key: &'a RedisKeyWritable) -> Result<StringDMA<'a>, RedisError> {
+        verify_key_type(key.key_inner, KeyType::String)?;
         let mut length: size_t = 0;
         let dma = raw::string_dma(key.key_inner, &mut length, raw::KeyMode::WRITE);
         if dma.is_null() {
@@ -590,15 +1004,21 @@ impl<'a> StringDMA<'a> {
         }
     }
 
+    /// Resize the string, new bytes are set to zero.
+    fn truncate(&mut self, new_len: usize) -> Result<(), RedisError> {
+        if raw::Status::Ok == raw::string_truncate(self.key.key_inner, new_len) {
+            let mut length: size_t = 0;
+            let dma = raw::string_dma(self.key.key_inner, &mut length, raw::KeyMode::WRITE);
+            self.buffer = unsafe { std::slice::from_raw_parts_mut(dma.cast::<u8>(), length) };
+            Ok(())
+        } else {
+            Err(RedisError::Str("Failed to truncate string"))
+        }
+    }
+
     pub fn write(&mut self, data: &[u8]) -> Result<&mut Self, RedisError> {
         if self.buffer.len() != data.len() {
-            if raw::Status::Ok == raw::string_truncate(self.key.key_inner, data.len()) {
-                let mut length: size_t = 0;
-                let dma = raw::string_dma(self.key.key_inner, &mut length, raw::KeyMode::WRITE);
-                self.buffer = unsafe { std::slice::from_raw_parts_mut(dma.cast::<u8>(), length) };
-            } else {
-                return Err(RedisError::Str("Failed to truncate string"));
-            }
+            self.truncate(data.len())?;
         }
         self.buffer[..data.len()].copy_from_slice(data);
         Ok(self)
@@ -607,22 +1027,69 @@ impl<'a> StringDMA<'a> {
     pub fn append(&mut self, data: &[u8]) -> Result<&mut Self, RedisError> {
         let current_len = self.buffer.len();
         let new_len = current_len + data.len();
-        if raw::Status::Ok == raw::string_truncate(self.key.key_inner, new_len) {
-            let mut length: size_t = 0;
-            let dma = raw::string_dma(self.key.key_inner, &mut length, raw::KeyMode::WRITE);
-            self.buffer = unsafe { std::slice::from_raw_parts_mut(dma.cast::<u8>(), length) };
-        } else {
-            return Err(RedisError::Str("Failed to truncate string"));
-        }
+        self.truncate(new_len)?;
         self.buffer[current_len..new_len].copy_from_slice(data);
         Ok(self)
     }
+
+    /// Overwrite the bytes starting at `offset`, growing the string with zero bytes
+    /// if it is too short. Nothing is changed when `data` is empty, and the string
+    /// can not grow past 512MB.
+    pub fn set_range(&mut self, offset: usize, data: &[u8]) -> Result<&mut Self, RedisError> {
+        if data.is_empty() {
+            return Ok(self);
+        }
+        let end = offset
+            .checked_add(data.len())
+            .filter(|end| *end <= MAX_STRING_SIZE)
+            .ok_or(RedisError::Str("ERR string exceeds maximum allowed size"))?;
+        if self.buffer.len() < end {
+            self.truncate(end)?;
+        }
+        self.buffer[offset..end].copy_from_slice(data);
+        Ok(self)
+    }
+}
+
+/// The largest size of a string value, the default `proto-max-bulk-len`.
+const MAX_STRING_SIZE: usize = 512 * 1024 * 1024;
+
+/// The largest bit offset `SETBIT` accepts, which limits strings to 512MB.
+const MAX_BIT_OFFSET: u64 = u32::MAX as u64;
+
+/// The mask of the bit at `offset` within its byte, most significant bit first.
+const fn bit_mask(offset: u64) -> u8 {
+    0x80 >> (offset & 7)
+}
+
+/// The `GETBIT` semantics applied to a slice.
+fn bit_at(value: &[u8], offset: u64) -> bool {
+    usize::try_from(offset >> 3)
+        .ok()
+        .and_then(|byte| value.get(byte))
+        .is_some_and(|b| b & bit_mask(offset) != 0)
+}
+
+/// The `GETRANGE` index semantics applied to a slice.
+fn string_range_slice(value: &[u8], start: i64, end: i64) -> &[u8] {
+    let len = value.len() as i64;
+    if len == 0 || (start < 0 && end < 0 && start > end) {
+        return &[];
+    }
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let end = if end < 0 { len + end } else { end }.clamp(0, len - 1);
+    if start > end {
+        return &[];
+    }
+    &value[start as usize..=end as usize]
 }
 
 impl Drop for RedisKeyWritable {
     // Frees resources appropriately as a RedisKey goes out of scope.
     fn drop(&mut self) {
         raw::close_key(self.key_inner);
+        KEY_WRITE_EPOCH.fetch_add(1, Ordering::Relaxed);
+        WRITABLE_KEYS.fetch_sub(1, Ordering::Relaxed);
     }
 }
 
@@ -638,6 +1105,8 @@ where
 {
     const BATCH_SIZE: usize = 12;
 
+    verify_key_type(key, KeyType::Hash)?;
+
     let mut values = Vec::with_capacity(fields.len());
     let mut values_raw = [std::ptr::null_mut(); BATCH_SIZE];
 
@@ -675,9 +1144,79 @@ pub fn verify_type(key_inner: *mut raw::RedisModuleKey, redis_type: &RedisType)
         let raw_type = unsafe { raw::RedisModule_ModuleTypeGetType.unwrap()(key_inner) };
 
         if raw_type != *redis_type.raw_type.borrow() {
-            return Err(RedisError::Str("Existing key has wrong Redis type"));
+            return Err(RedisError::WrongType);
         }
     }
 
     REDIS_OK
 }
+
+/// Returns [RedisError::WrongType] if the key exists and holds a value of
+/// another type than `expected`. A missing key is never of the wrong type.
+pub(crate) fn verify_key_type(
+    key_inner: *mut raw::RedisModuleKey,
+    expected: KeyType,
+) -> Result<(), RedisError> {
+    if key_inner.is_null() {
+        return Ok(());
+    }
+    let key_type: KeyType = unsafe { raw::RedisModule_KeyType.unwrap()(key_inner) }.into();
+    if key_type == KeyType::Empty || key_type == expected {
+        Ok(())
+    } else {
+        Err(RedisError::WrongType)
+    }
+}
+
+/// The error of a failed `RedisModule_StreamAdd`, from `errno`.
+fn stream_add_error() -> RedisError {
+    match std::io::Error::last_os_error().raw_os_error() {
+        Some(libc::ENOTSUP) => RedisError::WrongType,
+        Some(libc::EDOM) => RedisError::Str(
+            "ERR The ID specified in XADD is equal or smaller than the target stream top item",
+        ),
+        Some(libc::EFBIG) => RedisError::Str(
+            "ERR The stream has exhausted the last possible ID, unable to add more items",
+        ),
+        Some(libc::ERANGE) => RedisError::Str("ERR Elements are too large to be stored"),
+        _ => RedisError::Str("ERR Failed adding to the stream"),
+    }
+}
+
+fn stream_trim_flags(approx: bool) -> c_int {
+    if approx {
+        raw::REDISMODULE_STREAM_TRIM_APPROX as c_int
+    } else {
+        0
+    }
+}
+
+/// The result of `RedisModule_StreamTrimByLength` or `RedisModule_StreamTrimByID`,
+/// the number of entries removed or -1 with `errno` set.
+fn stream_trim_result(res: c_longlong) -> Result<usize, RedisError> {
+    if res >= 0 {
+        return Ok(res as usize);
+    }
+    match std::io::Error::last_os_error().raw_os_error() {
+        Some(libc::ENOTSUP) => Err(RedisError::WrongType),
+        _ => Err(RedisError::Str("ERR Failed trimming the stream")),
+    }
+}
+
+/// See [RedisKey::type_name].
+fn key_type_name(key_inner: *mut raw::RedisModuleKey) -> &'static str {
+    let key_type: KeyType = unsafe { raw::RedisModule_KeyType.unwrap()(key_inner) }.into();
+    match key_type {
+        KeyType::Empty => "none",
+        KeyType::String => "string",
+        KeyType::List => "list",
+        KeyType::Hash => "hash",
+        KeyType::Set => "set",
+        KeyType::ZSet => "zset",
+        KeyType::Stream => "stream",
+        KeyType::Module => {
+            let raw_type = unsafe { raw::RedisModule_ModuleTypeGetType.unwrap()(key_inner) };
+            registered_type_name(raw_type).unwrap_or("module")
+        }
+    }
+}
@@ -10,7 +10,12 @@ pub mod rediserror;
 mod redismodule;
 pub mod redisraw;
 pub mod redisvalue;
+mod redisvalue_resp;
+#[cfg(feature = "serde-conversions")]
+pub mod redisvalue_serde;
 pub mod stream;
+#[cfg(feature = "test")]
+mod test_strings;
 
 pub mod configuration;
 mod context;
@@ -19,7 +24,9 @@ pub mod logging;
 mod macros;
 mod utils;
 
+pub use crate::context::auth::{AuthBlockedClient, AuthCallback, AuthResult};
 pub use crate::context::blocked::BlockedClient;
+pub use crate::context::bulk_reply::BulkReply;
 pub use crate::context::thread_safe::{
     ContextGuard, DetachedFromClient, RedisGILGuard, RedisLockIndicator, ThreadSafeContext,
 };
@@ -29,19 +36,28 @@ pub use crate::configuration::ConfigurationValue;
 pub use crate::configuration::EnumConfigurationValue;
 pub use crate::context::call_reply::FutureCallReply;
 pub use crate::context::call_reply::{CallReply, CallResult, ErrorReply, PromiseCallReply};
+pub use crate::context::command_table::{CommandHandler, CommandTable};
 pub use crate::context::commands;
+pub use crate::context::dict::{Dict, DictIter, SeekOp};
 pub use crate::context::keys_cursor::KeysCursor;
 pub use crate::context::server_events;
+#[doc(hidden)]
+pub use crate::context::set_module_identity;
 pub use crate::context::AclPermissions;
 #[cfg(feature = "min-redis-compatibility-version-7-2")]
 pub use crate::context::BlockingCallOptions;
+pub use crate::context::CallHook;
 pub use crate::context::CallOptionResp;
 pub use crate::context::CallOptions;
 pub use crate::context::CallOptionsBuilder;
 pub use crate::context::Context;
 pub use crate::context::ContextFlags;
+pub use crate::context::DeferredReply;
 pub use crate::context::DetachedContext;
 pub use crate::context::DetachedContextGuard;
+pub use crate::context::LatencyTimer;
+pub use crate::context::PropagationTargets;
+pub use crate::context::DEFAULT_MAX_REPLY_DEPTH;
 pub use crate::context::{
     InfoContextBuilderFieldBottomLevelValue, InfoContextBuilderFieldTopLevelValue,
     InfoContextFieldBottomLevelData, InfoContextFieldTopLevelData, OneInfoSectionData,
@@ -101,6 +117,12 @@ pub fn basic_info_command_handler(ctx: &InfoContext, for_crash_report: bool) {
         .iter()
         .filter_map(|callback| callback(ctx, for_crash_report).err())
         .for_each(|e| log::error!("Couldn't build info for the module's custom handler: {e}"));
+
+    if !for_crash_report {
+        if let Err(e) = context::info::add_info_gauges(ctx) {
+            log::error!("Couldn't build info for the module's gauges: {e}");
+        }
+    }
 }
 
 /// Initialize RedisModuleAPI without register as a module.
@@ -1,6 +1,8 @@
 use crate::raw;
+use crate::RedisError;
 use std::ffi::CString;
 use std::ptr;
+use std::str::FromStr;
 use strum_macros::AsRefStr;
 
 const NOT_INITIALISED_MESSAGE: &str = "Redis module hasn't been initialised.";
@@ -17,6 +19,25 @@ pub enum RedisLogLevel {
     Warning,
 }
 
+impl RedisLogLevel {
+    /// Parse one of the level names of the `loglevel` configuration of Redis,
+    /// `debug`, `verbose`, `notice` or `warning`, ignoring case.
+    pub fn from_redis_str(s: &str) -> Result<Self, RedisError> {
+        [Self::Debug, Self::Verbose, Self::Notice, Self::Warning]
+            .into_iter()
+            .find(|level| level.as_ref().eq_ignore_ascii_case(s))
+            .ok_or_else(|| RedisError::String(format!("Unknown log level '{s}'")))
+    }
+}
+
+impl FromStr for RedisLogLevel {
+    type Err = RedisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_redis_str(s)
+    }
+}
+
 impl From<log::Level> for RedisLogLevel {
     fn from(value: log::Level) -> Self {
         match value {
@@ -193,3 +214,20 @@ pub mod standard_log_implementation {
     }
 }
 pub use standard_log_implementation::*;
+
+#[cfg(test)]
+mod tests {
+    use super::RedisLogLevel;
+
+    #[test]
+    fn parse_log_level() {
+        let level: RedisLogLevel = "WARNING".parse().unwrap();
+        assert!(matches!(level, RedisLogLevel::Warning));
+        assert!(matches!(
+            RedisLogLevel::from_redis_str("verbose"),
+            Ok(RedisLogLevel::Verbose)
+        ));
+        assert!("warn".parse::<RedisLogLevel>().is_err());
+        assert!("".parse::<RedisLogLevel>().is_err());
+    }
+}
@@ -20,7 +20,7 @@ macro_rules! redis_command {
 
             let args = $crate::decode_args(ctx, argv, argc);
             let response = $command_handler(&context, args);
-            context.reply(response.map(|v| v.into())) as c_int
+            context.reply_command_result(response.map(|v| v.into())) as c_int
         }
         /////////////////////
 
@@ -227,6 +227,7 @@ macro_rules! redis_module {
                 module_version,
                 raw::REDISMODULE_APIVER_1 as c_int,
             ) } == raw::Status::Err as c_int { return raw::Status::Err as c_int; }
+            $crate::set_module_identity($module_name, module_version);
 
             let context = $crate::Context::new(ctx);
             unsafe {
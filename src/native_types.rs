@@ -1,9 +1,25 @@
 use std::cell::RefCell;
 use std::ffi::CString;
 use std::ptr;
+use std::sync::Mutex;
 
 use crate::raw;
 
+/// The names of the data types created by this module, by their raw type,
+/// see [registered_type_name].
+static REGISTERED_TYPES: Mutex<Vec<(usize, &'static str)>> = Mutex::new(Vec::new());
+
+/// The name of a data type created by this module with
+/// [RedisType::create_data_type], `None` for the types of other modules.
+pub(crate) fn registered_type_name(raw_type: *mut raw::RedisModuleType) -> Option<&'static str> {
+    REGISTERED_TYPES
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(registered, _)| *registered == raw_type as usize)
+        .map(|(_, name)| *name)
+}
+
 pub struct RedisType {
     name: &'static str,
     version: i32,
@@ -55,6 +71,10 @@ impl RedisType {
         }
 
         *self.raw_type.borrow_mut() = redis_type;
+        REGISTERED_TYPES
+            .lock()
+            .unwrap()
+            .push((redis_type as usize, self.name));
 
         redis_log(
             ctx,
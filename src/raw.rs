@@ -136,6 +136,9 @@ bitflags! {
         const STREAM = REDISMODULE_NOTIFY_STREAM;
         /// Available only starting from Redis `7.0.1`.
         const NEW = REDISMODULE_NOTIFY_NEW;
+        /// Events of module data types, or any custom event a module sends.
+        /// Delivered to subscribers when `notify-keyspace-events` includes `d`
+        /// (or `A`), together with `K` and/or `E`.
         const MODULE = REDISMODULE_NOTIFY_MODULE;
         const LOADED = REDISMODULE_NOTIFY_LOADED;
         const MISSED = REDISMODULE_NOTIFY_KEY_MISS;
@@ -381,7 +384,7 @@ pub fn reply_with_map(ctx: *mut RedisModuleCtx, len: c_long) -> Status {
     unsafe {
         RedisModule_ReplyWithMap
             .map_or_else(
-                || RedisModule_ReplyWithArray.unwrap()(ctx, len * 2),
+                || RedisModule_ReplyWithArray.unwrap()(ctx, flat_map_len(len)),
                 |f| f(ctx, len),
             )
             .into()
@@ -394,13 +397,57 @@ pub fn reply_with_set(ctx: *mut RedisModuleCtx, len: c_long) -> Status {
     unsafe {
         RedisModule_ReplyWithSet
             .map_or_else(
-                || RedisModule_ReplyWithArray.unwrap()(ctx, len * 2),
+                || RedisModule_ReplyWithArray.unwrap()(ctx, len),
                 |f| f(ctx, len),
             )
             .into()
     }
 }
 
+/// The length of the array a map of `len` entries is flattened to, keeping
+/// `REDISMODULE_POSTPONED_LEN` as is.
+const fn flat_map_len(len: c_long) -> c_long {
+    if len == REDISMODULE_POSTPONED_LEN as c_long {
+        len
+    } else {
+        len * 2
+    }
+}
+
+/// Sets the length of the latest array replied with `REDISMODULE_POSTPONED_LEN`.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[inline]
+pub fn reply_set_array_length(ctx: *mut RedisModuleCtx, len: c_long) {
+    unsafe { RedisModule_ReplySetArrayLength.unwrap()(ctx, len) }
+}
+
+/// Sets the number of entries of the latest map replied with
+/// `REDISMODULE_POSTPONED_LEN`. Redis doubles it for RESP2 clients, which get
+/// the map as a flat array.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[inline]
+pub fn reply_set_map_length(ctx: *mut RedisModuleCtx, len: c_long) {
+    unsafe {
+        RedisModule_ReplySetMapLength.map_or_else(
+            || RedisModule_ReplySetArrayLength.unwrap()(ctx, len * 2),
+            |f| f(ctx, len),
+        )
+    }
+}
+
+/// Sets the number of elements of the latest set replied with
+/// `REDISMODULE_POSTPONED_LEN`.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[inline]
+pub fn reply_set_set_length(ctx: *mut RedisModuleCtx, len: c_long) {
+    unsafe {
+        RedisModule_ReplySetSetLength.map_or_else(
+            || RedisModule_ReplySetArrayLength.unwrap()(ctx, len),
+            |f| f(ctx, len),
+        )
+    }
+}
+
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 #[inline]
 pub fn reply_with_attribute(ctx: *mut RedisModuleCtx, len: c_long) -> Status {
@@ -421,6 +468,18 @@ pub fn reply_with_null(ctx: *mut RedisModuleCtx) -> Status {
     unsafe { RedisModule_ReplyWithNull.unwrap()(ctx).into() }
 }
 
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[inline]
+pub fn reply_with_empty_string(ctx: *mut RedisModuleCtx) -> Status {
+    unsafe { RedisModule_ReplyWithEmptyString.unwrap()(ctx).into() }
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[inline]
+pub fn reply_with_null_array(ctx: *mut RedisModuleCtx) -> Status {
+    unsafe { RedisModule_ReplyWithNullArray.unwrap()(ctx).into() }
+}
+
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 #[inline]
 pub fn reply_with_bool(ctx: *mut RedisModuleCtx, b: c_int) -> Status {
@@ -718,6 +777,18 @@ pub fn replicate<'a, T: Into<StrCallArgs<'a>>>(
     ctx: *mut RedisModuleCtx,
     command: &str,
     args: T,
+) -> Status {
+    replicate_with_format(ctx, command, FMT, args)
+}
+
+/// Same as [replicate], with a format string which may also contain the
+/// `A` (no AOF) or `R` (no replicas) flags.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn replicate_with_format<'a, T: Into<StrCallArgs<'a>>>(
+    ctx: *mut RedisModuleCtx,
+    command: &str,
+    fmt: *const c_char,
+    args: T,
 ) -> Status {
     let mut call_args: StrCallArgs = args.into();
     let final_args = call_args.args_mut();
@@ -728,7 +799,7 @@ pub fn replicate<'a, T: Into<StrCallArgs<'a>>>(
         RedisModule_Replicate.unwrap()(
             ctx,
             cmd.as_ptr(),
-            FMT,
+            fmt,
             final_args.as_ptr(),
             final_args.len(),
         )
@@ -930,7 +1001,7 @@ pub fn get_keyspace_notification_flags_all() -> NotifyEvent {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Version {
     pub major: i32,
     pub minor: i32,
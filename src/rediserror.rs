@@ -9,6 +9,20 @@ pub enum RedisError {
     Str(&'static str),
     String(String),
     WrongType,
+    /// An error with details for RESP3 clients, see [RedisError::with_details].
+    Detailed(Box<ErrorDetails>),
+}
+
+/// The code, message and details of a [RedisError::Detailed].
+///
+/// The details are strings rather than [crate::RedisValue]s so that the error
+/// can still be sent between threads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorDetails {
+    pub code: String,
+    pub message: String,
+    /// Sent to RESP3 clients as an attribute map preceding the error.
+    pub details: Vec<(String, String)>,
 }
 
 impl<'root> From<ErrorCallReply<'root>> for RedisError {
@@ -39,8 +53,175 @@ impl RedisError {
     pub const fn short_read() -> Self {
         Self::Str("ERR short read or OOM loading DB")
     }
+
+    /// An error replied as `<code> <message>` in both protocols, with `details`
+    /// sent to RESP3 clients as an attribute map preceding the error, as
+    /// [crate::Context::reply_error_with_details] does. This lets a command
+    /// handler return a detailed error through [crate::RedisResult].
+    /// Whitespace in `code` is replaced with `_`, and an empty code with `ERR`.
+    #[must_use]
+    pub fn with_details<K: Into<String>, V: Into<String>>(
+        code: &str,
+        message: &str,
+        details: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        let code: String = code
+            .chars()
+            .map(|c| if c.is_whitespace() { '_' } else { c })
+            .collect();
+        Self::Detailed(Box::new(ErrorDetails {
+            code: if code.is_empty() {
+                "ERR".to_owned()
+            } else {
+                code
+            },
+            message: message.to_owned(),
+            details: details
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }))
+    }
+
+    /// Return the error code, the leading upper case word of the error message,
+    /// for example `WRONGTYPE` for `WRONGTYPE Operation against a key...`.
+    /// [RedisError::WrongArity] and [RedisError::WrongType] return the codes
+    /// Redis replies them with, `ERR` and `WRONGTYPE`, and [RedisError::Detailed]
+    /// its own code. Returns `None` if the message does not start with a code.
+    #[must_use]
+    pub fn code(&self) -> Option<&str> {
+        let msg = match self {
+            Self::WrongArity => return Some("ERR"),
+            Self::WrongType => return Some("WRONGTYPE"),
+            Self::Detailed(err) => return Some(err.code.as_str()),
+            Self::Str(s) => s,
+            Self::String(s) => s.as_str(),
+        };
+        let code = msg.split_whitespace().next()?;
+        let is_code = code.bytes().any(|b| b.is_ascii_uppercase())
+            && code
+                .bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_');
+        is_code.then_some(code)
+    }
+
+    /// Whether the error code, see [Self::code], is `code`, ignoring case. An
+    /// error without a code never matches. The codes Redis replies with are in
+    /// [codes], for example to handle a specific error returned by
+    /// [crate::Context::call]:
+    ///
+    /// ```ignore
+    /// match ctx.call("EVALSHA", &[sha, "0"]) {
+    ///     Err(err) if err.is(codes::NOSCRIPT) => ctx.call("EVAL", &[script, "0"]),
+    ///     res => res,
+    /// }
+    /// ```
+    #[must_use]
+    pub fn is(&self, code: &str) -> bool {
+        self.code()
+            .is_some_and(|own| own.eq_ignore_ascii_case(code))
+    }
+
+    /// A transient error with the `TRYAGAIN` code, which clients recognize as
+    /// retriable, for example `TRYAGAIN the index is being rebuilt`.
+    #[must_use]
+    pub fn try_again(message: &str) -> Self {
+        Self::String(format!("TRYAGAIN {message}"))
+    }
+
+    /// A transient error with a module specific code, for clients that retry on
+    /// the module's own errors. By convention the code of a retriable error ends
+    /// with `_RETRY`, which is added if missing: `retriable("MYMOD", "busy")` is
+    /// the error `MYMOD_RETRY busy`. The code is upper cased and whitespace is
+    /// replaced with `_`.
+    #[must_use]
+    pub fn retriable(code: &str, message: &str) -> Self {
+        let mut code: String = code
+            .chars()
+            .map(|c| {
+                if c.is_whitespace() {
+                    '_'
+                } else {
+                    c.to_ascii_uppercase()
+                }
+            })
+            .collect();
+        if !code.ends_with(RETRY_SUFFIX) {
+            code.push_str(RETRY_SUFFIX);
+        }
+        Self::String(format!("{code} {message}"))
+    }
+
+    /// Whether a client may retry the command that failed with this error: its
+    /// code is one of the transient error codes of Redis (`TRYAGAIN`, `LOADING`,
+    /// `BUSY`, `MASTERDOWN` and `CLUSTERDOWN`) or ends with `_RETRY`, see
+    /// [Self::retriable]. This also applies to errors received from
+    /// [crate::Context::call].
+    #[must_use]
+    pub fn is_retriable(&self) -> bool {
+        self.code()
+            .is_some_and(|code| RETRIABLE_CODES.contains(&code) || code.ends_with(RETRY_SUFFIX))
+    }
+}
+
+/// The codes of the errors Redis replies when a command can succeed later.
+const RETRIABLE_CODES: &[&str] = &[
+    codes::TRYAGAIN,
+    codes::LOADING,
+    codes::BUSY,
+    codes::MASTERDOWN,
+    codes::CLUSTERDOWN,
+];
+
+/// The error codes Redis replies with, to be used with [RedisError::is].
+pub mod codes {
+    /// A generic error.
+    pub const ERR: &str = "ERR";
+    /// The key holds a value of another type.
+    pub const WRONGTYPE: &str = "WRONGTYPE";
+    /// The target key already exists, for example with `RESTORE` or `COPY`.
+    pub const BUSYKEY: &str = "BUSYKEY";
+    /// No script matches the SHA1 given to `EVALSHA`.
+    pub const NOSCRIPT: &str = "NOSCRIPT";
+    /// A script or a module command is running.
+    pub const BUSY: &str = "BUSY";
+    /// The server is loading the dataset.
+    pub const LOADING: &str = "LOADING";
+    /// A multi-key operation can not be served during resharding.
+    pub const TRYAGAIN: &str = "TRYAGAIN";
+    /// The replica is not connected to its master.
+    pub const MASTERDOWN: &str = "MASTERDOWN";
+    /// The cluster is down.
+    pub const CLUSTERDOWN: &str = "CLUSTERDOWN";
+    /// The keys are not in the same hash slot.
+    pub const CROSSSLOT: &str = "CROSSSLOT";
+    /// The hash slot is served by another node.
+    pub const MOVED: &str = "MOVED";
+    /// The hash slot is being migrated to another node.
+    pub const ASK: &str = "ASK";
+    /// The command is not allowed against a read only replica.
+    pub const READONLY: &str = "READONLY";
+    /// The memory limit was reached.
+    pub const OOM: &str = "OOM";
+    /// The client is not authenticated.
+    pub const NOAUTH: &str = "NOAUTH";
+    /// The user has no permission to run the command or access the key.
+    pub const NOPERM: &str = "NOPERM";
+    /// The transaction was discarded because of a previous error.
+    pub const EXECABORT: &str = "EXECABORT";
+    /// Not enough replicas acknowledged the write.
+    pub const NOREPLICAS: &str = "NOREPLICAS";
+    /// Writes are rejected because the last RDB save failed.
+    pub const MISCONF: &str = "MISCONF";
+    /// The stream consumer group does not exist.
+    pub const NOGROUP: &str = "NOGROUP";
+    /// The stream consumer group already exists.
+    pub const BUSYGROUP: &str = "BUSYGROUP";
 }
 
+/// The suffix of the codes of retriable module errors.
+const RETRY_SUFFIX: &str = "_RETRY";
+
 impl<T: std::error::Error> From<T> for RedisError {
     fn from(e: T) -> Self {
         Self::String(format!("ERR {e}"))
@@ -50,6 +231,7 @@ impl<T: std::error::Error> From<T> for RedisError {
 impl fmt::Display for RedisError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let d = match self {
+            Self::Detailed(err) => return write!(f, "{} {}", err.code, err.message),
             Self::WrongArity => "Wrong Arity",
             // remove NUL from the end of raw::REDISMODULE_ERRORMSG_WRONGTYPE
             // before converting &[u8] to &str to ensure CString::new() doesn't
@@ -67,3 +249,83 @@ impl fmt::Display for RedisError {
         write!(f, "{d}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{codes, RedisError};
+
+    #[test]
+    fn code() {
+        assert_eq!(RedisError::WrongArity.code(), Some("ERR"));
+        assert_eq!(RedisError::WrongType.code(), Some("WRONGTYPE"));
+        assert_eq!(
+            RedisError::Str("NOPERM this user has no permissions").code(),
+            Some("NOPERM")
+        );
+        assert_eq!(
+            RedisError::String("MY_ERR2 something failed".to_string()).code(),
+            Some("MY_ERR2")
+        );
+        assert_eq!(RedisError::Str("Couldn't parse as integer").code(), None);
+        assert_eq!(RedisError::Str("").code(), None);
+        assert_eq!(RedisError::Str("42 is not a code").code(), None);
+    }
+
+    #[test]
+    fn retriable() {
+        let err = RedisError::try_again("the index is being rebuilt");
+        assert_eq!(err.to_string(), "TRYAGAIN the index is being rebuilt");
+        assert!(err.is_retriable());
+
+        let err = RedisError::retriable("my mod", "busy");
+        assert_eq!(err.to_string(), "MY_MOD_RETRY busy");
+        assert_eq!(err.code(), Some("MY_MOD_RETRY"));
+        assert!(err.is_retriable());
+        let err = RedisError::retriable("MYMOD_RETRY", "busy");
+        assert_eq!(err.to_string(), "MYMOD_RETRY busy");
+
+        assert!(RedisError::Str("LOADING Redis is loading the dataset in memory").is_retriable());
+        assert!(!RedisError::Str("ERR syntax error").is_retriable());
+        assert!(!RedisError::WrongType.is_retriable());
+        assert!(!RedisError::Str("retry later").is_retriable());
+    }
+
+    #[test]
+    fn with_details() {
+        let err = RedisError::with_details(
+            "MY ERR",
+            "the index is full",
+            [("limit", "100"), ("used", "100")],
+        );
+        assert_eq!(err.to_string(), "MY_ERR the index is full");
+        assert_eq!(err.code(), Some("MY_ERR"));
+        let RedisError::Detailed(details) = err else {
+            panic!("expected a detailed error");
+        };
+        assert_eq!(
+            details.details,
+            [
+                ("limit".to_owned(), "100".to_owned()),
+                ("used".to_owned(), "100".to_owned())
+            ]
+        );
+        assert_eq!(
+            RedisError::with_details("", "failed", Vec::<(String, String)>::new()).to_string(),
+            "ERR failed"
+        );
+    }
+
+    #[test]
+    fn is() {
+        let err = RedisError::Str("BUSYKEY Target key name already exists.");
+        assert!(err.is(codes::BUSYKEY));
+        assert!(err.is("busykey"));
+        assert!(!err.is(codes::BUSY));
+        assert!(!err.is("BUSYKEY Target"));
+        assert!(RedisError::String("NOSCRIPT No matching script.".to_string()).is(codes::NOSCRIPT));
+        assert!(RedisError::WrongType.is(codes::WRONGTYPE));
+        assert!(RedisError::WrongArity.is(codes::ERR));
+        assert!(!RedisError::Str("Couldn't parse as integer").is("Couldn't"));
+        assert!(!RedisError::Str("").is(""));
+    }
+}
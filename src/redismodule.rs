@@ -1,7 +1,11 @@
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
+use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::ffi::CString;
 use std::fmt::Display;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
 use std::ops::Deref;
 use std::os::raw::{c_char, c_int, c_void};
 use std::ptr::NonNull;
@@ -110,6 +114,262 @@ pub fn decode_args(
         .collect()
 }
 
+/// Decodes the arguments given to the module on load (`loadmodule mymod.so arg1 arg2`
+/// or `MODULE LOAD mymod.so arg1 arg2`). The strings are retained without a context,
+/// so they can be kept after `RedisModule_OnLoad` returns.
+pub fn parse_module_args(argv: *mut *mut raw::RedisModuleString, argc: c_int) -> Vec<RedisString> {
+    decode_args(ptr::null_mut(), argv, argc)
+}
+
+/// The arguments of a command, borrowed from the `argv` Redis passed to it.
+///
+/// Unlike [decode_args], the strings are not retained nor freed, so an [Args]
+/// can not outlive the command invocation it was created for. [Self::iter]
+/// walks `argv` without allocating. It also derefs to a slice, so the arguments
+/// are indexed (`args[1]`) and sliced (`&args[1..]` skips the command name)
+/// with bounds checks, the slice is created the first time it is used. Use
+/// [RedisString::safe_clone] to keep an argument after the command returns.
+pub struct Args<'a> {
+    ctx: *mut raw::RedisModuleCtx,
+    argv: &'a [*mut raw::RedisModuleString],
+    strings: OnceCell<Vec<RedisString>>,
+}
+
+impl<'a> Args<'a> {
+    /// Borrow the `argc` arguments at `argv`, which must stay valid as long as `ctx`
+    /// is borrowed, as is the case for the arguments of a command.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn new(ctx: &'a Context, argv: *mut *mut raw::RedisModuleString, argc: c_int) -> Self {
+        let argv: &[*mut raw::RedisModuleString] = if argv.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(argv, argc as usize) }
+        };
+        Self {
+            ctx: ctx.ctx,
+            argv,
+            strings: OnceCell::new(),
+        }
+    }
+}
+
+impl Args<'_> {
+    /// The number of arguments, the command name included.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.argv.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.argv.is_empty()
+    }
+
+    /// Iterate over the arguments, the command name included.
+    pub fn iter(&self) -> ArgsIter<'_> {
+        ArgsIter {
+            ctx: self.ctx,
+            argv: self.argv.iter(),
+        }
+    }
+
+    /// Render the whole invocation, the command name included, for logging,
+    /// the way `MONITOR` does: each argument is quoted and the bytes that are
+    /// not printable are escaped, for example `"SET" "key" "a\x00b"`.
+    ///
+    /// The arguments are the ones the command was invoked with, also when it
+    /// was called by a module through `RM_Call`. Use `to_vec()` to keep them,
+    /// for example to issue the same command again.
+    #[must_use]
+    pub fn to_command_line(&self) -> String {
+        let mut line = String::new();
+        for (i, arg) in self.iter().enumerate() {
+            if i > 0 {
+                line.push(' ');
+            }
+            line.push('"');
+            for &b in arg.as_slice() {
+                match b {
+                    b'\\' => line.push_str("\\\\"),
+                    b'"' => line.push_str("\\\""),
+                    b'\n' => line.push_str("\\n"),
+                    b'\r' => line.push_str("\\r"),
+                    b'\t' => line.push_str("\\t"),
+                    0x20..=0x7e => line.push(b as char),
+                    _ => line.push_str(&format!("\\x{b:02x}")),
+                }
+            }
+            line.push('"');
+        }
+        line
+    }
+}
+
+impl Deref for Args<'_> {
+    type Target = [RedisString];
+
+    fn deref(&self) -> &Self::Target {
+        self.strings.get_or_init(|| {
+            self.argv
+                .iter()
+                .map(|&arg| RedisString::from_redis_module_string(self.ctx, arg))
+                .collect()
+        })
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b Args<'a> {
+    type Item = BorrowedArg<'b>;
+    type IntoIter = ArgsIter<'b>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Drop for Args<'_> {
+    fn drop(&mut self) {
+        // The strings are owned by Redis, release them without freeing.
+        if let Some(strings) = self.strings.take() {
+            strings.into_iter().for_each(|arg| {
+                arg.take();
+            });
+        }
+    }
+}
+
+/// The iterator returned by [Args::iter].
+pub struct ArgsIter<'a> {
+    ctx: *mut raw::RedisModuleCtx,
+    argv: slice::Iter<'a, *mut raw::RedisModuleString>,
+}
+
+impl<'a> ArgsIter<'a> {
+    fn borrow(&self, arg: *mut raw::RedisModuleString) -> BorrowedArg<'a> {
+        BorrowedArg {
+            string: ManuallyDrop::new(RedisString::from_redis_module_string(self.ctx, arg)),
+            _args: PhantomData,
+        }
+    }
+}
+
+impl<'a> Iterator for ArgsIter<'a> {
+    type Item = BorrowedArg<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let arg = *self.argv.next()?;
+        Some(self.borrow(arg))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.argv.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for ArgsIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let arg = *self.argv.next_back()?;
+        Some(self.borrow(arg))
+    }
+}
+
+impl ExactSizeIterator for ArgsIter<'_> {}
+
+/// An argument yielded by [ArgsIter], which derefs to a [RedisString] that is
+/// not freed when dropped.
+pub struct BorrowedArg<'a> {
+    string: ManuallyDrop<RedisString>,
+    _args: PhantomData<&'a RedisString>,
+}
+
+impl Deref for BorrowedArg<'_> {
+    type Target = RedisString;
+
+    fn deref(&self) -> &Self::Target {
+        &self.string
+    }
+}
+
+/// A lookup table over module arguments. Supports the `key=value`, the
+/// `--key=value` and the `--key value` styles. A `--key` which is not followed by a value (because it
+/// is the last argument or the next argument is also a `--key`) is a flag with
+/// no value. Everything else is kept, in order, as a positional argument.
+#[derive(Debug, Default)]
+pub struct ArgMap {
+    values: HashMap<String, Option<RedisString>>,
+    positional: Vec<RedisString>,
+}
+
+impl ArgMap {
+    pub fn parse(args: &[RedisString]) -> Self {
+        let mut map = Self::default();
+        let mut iter = args.iter().peekable();
+        while let Some(arg) = iter.next() {
+            let bytes = arg.as_slice();
+            let (name, value) = match bytes.strip_prefix(b"--") {
+                Some(name) => match split_key_value(name) {
+                    Some((name, value)) => (name, Some(value)),
+                    None => {
+                        let value = iter
+                            .next_if(|next| !next.as_slice().starts_with(b"--"))
+                            .cloned();
+                        (name, value)
+                    }
+                },
+                None => match split_key_value(bytes) {
+                    Some((name, value)) => (name, Some(value)),
+                    None => {
+                        map.positional.push(arg.clone());
+                        continue;
+                    }
+                },
+            };
+            map.values
+                .insert(String::from_utf8_lossy(name).into_owned(), value);
+        }
+        map
+    }
+
+    /// Returns `true` if the argument was given, with or without a value.
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RedisString> {
+        self.values.get(name).and_then(Option::as_ref)
+    }
+
+    pub fn get_str(&self, name: &str) -> Result<Option<&str>, RedisError> {
+        self.get(name).map(RedisString::try_as_str).transpose()
+    }
+
+    pub fn get_i64(&self, name: &str) -> Result<Option<i64>, RedisError> {
+        self.get(name).map(RedisString::parse_integer).transpose()
+    }
+
+    pub fn get_u64(&self, name: &str) -> Result<Option<u64>, RedisError> {
+        self.get(name)
+            .map(RedisString::parse_unsigned_integer)
+            .transpose()
+    }
+
+    pub fn get_f64(&self, name: &str) -> Result<Option<f64>, RedisError> {
+        self.get(name).map(RedisString::parse_float).transpose()
+    }
+
+    /// The arguments that are neither `key=value` nor `--key [value]`.
+    pub fn positional(&self) -> &[RedisString] {
+        &self.positional
+    }
+}
+
+/// Split `key=value` on the first `=`, the value may contain more of them.
+fn split_key_value(bytes: &[u8]) -> Option<(&[u8], RedisString)> {
+    let pos = bytes.iter().position(|&b| b == b'=')?;
+    let value = RedisString::create_from_slice(ptr::null_mut(), &bytes[pos + 1..]);
+    Some((&bytes[..pos], value))
+}
+
 ///////////////////////////////////////////////////
 
 #[derive(Debug)]
@@ -150,6 +410,17 @@ impl RedisString {
         }
     }
 
+    /// Copy the string into a new [OwnedRedisString] that is not tied to any context.
+    ///
+    /// Unlike [Self::safe_clone], which retains the same `RedisModuleString` and so
+    /// shares its (non atomic) reference count, the bytes are copied into a
+    /// new string that is exclusively owned by the returned value. It can be kept
+    /// in a global cache or moved to another thread, and is freed when dropped.
+    #[must_use]
+    pub fn to_owned_detached(&self) -> OwnedRedisString {
+        OwnedRedisString::new(self.as_slice())
+    }
+
     #[allow(clippy::not_unsafe_ptr_arg_deref)]
     pub fn create<T: Into<Vec<u8>>>(ctx: Option<NonNull<raw::RedisModuleCtx>>, s: T) -> Self {
         let ctx = ctx.map_or(std::ptr::null_mut(), |v| v.as_ptr());
@@ -170,6 +441,16 @@ impl RedisString {
         Self { ctx, inner }
     }
 
+    /// Create a string that does not depend on a [Context], to unit test code
+    /// that consumes [RedisString]s without a running Redis. Outside of Redis
+    /// the string is a plain byte buffer supporting reading, comparing, cloning
+    /// and parsing. Inside Redis it is created with `RM_CreateString`.
+    #[cfg(feature = "test")]
+    pub fn from_bytes_owned(s: &[u8]) -> Self {
+        crate::test_strings::install();
+        Self::create_from_slice(ptr::null_mut(), s)
+    }
+
     pub const fn from_redis_module_string(
         ctx: *mut raw::RedisModuleCtx,
         inner: *mut raw::RedisModuleString,
@@ -187,6 +468,24 @@ impl RedisString {
         raw::string_append_buffer(self.ctx, self.inner, s)
     }
 
+    /// Free the memory allocated for the string beyond its length, for example
+    /// after appending to it, or for an argument built from a network buffer.
+    /// Worth it for strings kept for a long time rather than short lived ones.
+    ///
+    /// The string is reallocated, which is why `&mut self` is required: the
+    /// borrowed command arguments of [Args] can not be trimmed. This is a no-op
+    /// on servers without `RM_TrimStringAllocation` (before Redis 7.0).
+    pub fn trim_allocation(&mut self) {
+        if self.inner.is_null() {
+            return;
+        }
+        if let Some(trim) = unsafe { raw::RedisModule_TrimStringAllocation } {
+            unsafe { trim(self.inner) };
+        }
+    }
+
+    /// The length of the string in bytes, as stored by Redis. The bytes are not
+    /// read, so it is cheap even for large values, and NUL bytes are counted.
     #[must_use]
     pub fn len(&self) -> usize {
         let mut len: usize = 0;
@@ -196,9 +495,14 @@ impl RedisString {
 
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        let mut len: usize = 0;
-        raw::string_ptr_len(self.inner, &mut len);
-        len == 0
+        self.len() == 0
+    }
+
+    /// Compare the string to `other` ignoring ASCII case, as Redis compares
+    /// command options such as `WITHSCORES`, without allocating.
+    #[must_use]
+    pub fn eq_ignore_ascii_case(&self, other: &[u8]) -> bool {
+        self.as_slice().eq_ignore_ascii_case(other)
     }
 
     pub fn try_as_str<'a>(&self) -> Result<&'a str, RedisError> {
@@ -227,7 +531,20 @@ impl RedisString {
     /// Will panic if `RedisModule_StringPtrLen` is missing in redismodule.h
     #[must_use]
     pub fn to_string_lossy(&self) -> String {
-        String::from_utf8_lossy(self.as_slice()).into_owned()
+        self.as_str_lossy().into_owned()
+    }
+
+    /// Like [Self::to_string_lossy], without copying when the string is valid
+    /// UTF-8, for example to include a possibly binary value in a log message.
+    /// See [Self::try_as_str] to fail on invalid UTF-8 instead, and
+    /// [Self::as_slice] for the raw bytes.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `RedisModule_StringPtrLen` is missing in redismodule.h
+    #[must_use]
+    pub fn as_str_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.as_slice())
     }
 
     pub fn parse_unsigned_integer(&self) -> Result<u64, RedisError> {
@@ -297,7 +614,7 @@ impl core::hash::Hash for RedisString {
 
 impl Display for RedisString {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_string_lossy())
+        write!(f, "{}", self.as_str_lossy())
     }
 }
 
@@ -393,6 +710,111 @@ impl<'de> serde::Deserialize<'de> for RedisString {
 
 ///////////////////////////////////////////////////
 
+/// A `RedisModuleString` created without a context and exclusively owned,
+/// see [RedisString::to_owned_detached].
+#[derive(Debug)]
+pub struct OwnedRedisString {
+    inner: *mut raw::RedisModuleString,
+}
+
+// The string is never shared, so it is safe to move and read it from
+// other threads.
+unsafe impl Send for OwnedRedisString {}
+unsafe impl Sync for OwnedRedisString {}
+
+impl OwnedRedisString {
+    fn new(s: &[u8]) -> Self {
+        let inner = unsafe {
+            raw::RedisModule_CreateString.unwrap()(
+                ptr::null_mut(),
+                s.as_ptr().cast::<c_char>(),
+                s.len(),
+            )
+        };
+        Self { inner }
+    }
+
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        RedisString::string_as_slice(self.inner)
+    }
+
+    pub fn try_as_str(&self) -> Result<&str, RedisError> {
+        str::from_utf8(self.as_slice())
+            .map_err(|_| RedisError::Str("Couldn't parse as UTF-8 string"))
+    }
+
+    #[must_use]
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(self.as_slice()).into_owned()
+    }
+
+    /// Convert into a [RedisString] without copying. The string has no
+    /// context, so it is not freed by auto memory.
+    #[must_use]
+    pub fn into_redis_string(self) -> RedisString {
+        let inner = self.inner;
+        std::mem::forget(self);
+        RedisString::from_redis_module_string(ptr::null_mut(), inner)
+    }
+}
+
+impl Drop for OwnedRedisString {
+    fn drop(&mut self) {
+        unsafe {
+            raw::RedisModule_FreeString.unwrap()(ptr::null_mut(), self.inner);
+        }
+    }
+}
+
+impl Deref for OwnedRedisString {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl Borrow<[u8]> for OwnedRedisString {
+    fn borrow(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl PartialEq for OwnedRedisString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for OwnedRedisString {}
+
+impl PartialOrd for OwnedRedisString {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OwnedRedisString {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl core::hash::Hash for OwnedRedisString {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+impl Display for OwnedRedisString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_string_lossy())
+    }
+}
+
+///////////////////////////////////////////////////
+
 #[derive(Debug)]
 pub struct RedisBuffer {
     buffer: *mut c_char,
@@ -422,3 +844,129 @@ impl Drop for RedisBuffer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ArgMap, Args, RedisString};
+    use crate::Context;
+    use std::borrow::Cow;
+
+    #[test]
+    fn borrow_command_args() {
+        let strings: Vec<RedisString> = ["CMD", "key", "value"]
+            .iter()
+            .map(|s| RedisString::from_bytes_owned(s.as_bytes()))
+            .collect();
+        let mut argv: Vec<_> = strings.iter().map(|s| s.inner).collect();
+        let ctx = Context::dummy();
+
+        let args = Args::new(&ctx, argv.as_mut_ptr(), argv.len() as _);
+        assert_eq!(args.len(), 3);
+        assert_eq!(args[1], strings[1]);
+        assert_eq!(&args[1..], &strings[1..]);
+        assert!(args.get(3).is_none());
+        let names: Vec<String> = args.iter().map(|s| s.to_string_lossy()).collect();
+        assert_eq!(names, ["CMD", "key", "value"]);
+        drop(args);
+
+        // Iterating does not create the slice.
+        let args = Args::new(&ctx, argv.as_mut_ptr(), argv.len() as _);
+        assert_eq!(args.iter().len(), 3);
+        assert_eq!(*args.iter().next_back().unwrap(), strings[2]);
+        assert!(args.strings.get().is_none());
+        drop(args);
+
+        // The borrowed strings were not freed.
+        assert_eq!(strings[2].try_as_str().unwrap(), "value");
+    }
+
+    #[test]
+    fn command_line() {
+        let strings: Vec<RedisString> = [&b"SET"[..], b"k\"ey", b"a\0\r\n\xff"]
+            .iter()
+            .map(|s| RedisString::from_bytes_owned(s))
+            .collect();
+        let mut argv: Vec<_> = strings.iter().map(|s| s.inner).collect();
+        let ctx = Context::dummy();
+
+        let args = Args::new(&ctx, argv.as_mut_ptr(), argv.len() as _);
+        assert_eq!(args.to_command_line(), r#""SET" "k\"ey" "a\x00\r\n\xff""#);
+    }
+
+    #[test]
+    fn eq_ignore_ascii_case() {
+        let s = RedisString::from_bytes_owned(b"WithScores");
+        assert!(s.eq_ignore_ascii_case(b"WITHSCORES"));
+        assert!(!s.eq_ignore_ascii_case(b"WITHSCORE"));
+        let s = RedisString::from_bytes_owned("É\0a".as_bytes());
+        assert!(s.eq_ignore_ascii_case("É\0A".as_bytes()));
+        assert!(!s.eq_ignore_ascii_case("é\0a".as_bytes()));
+    }
+
+    #[test]
+    fn as_str_lossy() {
+        let s = RedisString::from_bytes_owned(b"key");
+        assert!(matches!(s.as_str_lossy(), Cow::Borrowed("key")));
+        let s = RedisString::from_bytes_owned(b"a\xffb");
+        assert_eq!(s.as_str_lossy(), "a\u{FFFD}b");
+        assert_eq!(s.to_string_lossy(), "a\u{FFFD}b");
+        assert!(s.try_as_str().is_err());
+    }
+
+    #[test]
+    fn len_counts_nul_bytes() {
+        let s = RedisString::from_bytes_owned(b"a\0b\0");
+        assert_eq!(s.len(), 4);
+        assert!(!s.is_empty());
+        assert!(RedisString::from_bytes_owned(b"").is_empty());
+    }
+
+    fn arg_map(args: &[&str]) -> ArgMap {
+        let args: Vec<RedisString> = args
+            .iter()
+            .map(|s| RedisString::from_bytes_owned(s.as_bytes()))
+            .collect();
+        ArgMap::parse(&args)
+    }
+
+    #[test]
+    fn arg_map_styles() {
+        let map = arg_map(&[
+            "pos1",
+            "--flag",
+            "--size",
+            "10",
+            "--name=idx",
+            "--expr=a=b",
+            "ratio=0.5",
+            "pos2",
+            "--empty=",
+            "--last",
+        ]);
+        assert!(map.contains("flag"));
+        assert!(map.get("flag").is_none());
+        assert_eq!(map.get_u64("size").unwrap(), Some(10));
+        assert_eq!(map.get_str("name").unwrap(), Some("idx"));
+        assert_eq!(map.get_str("expr").unwrap(), Some("a=b"));
+        assert_eq!(map.get_f64("ratio").unwrap(), Some(0.5));
+        assert_eq!(map.get_str("empty").unwrap(), Some(""));
+        assert!(map.contains("last"));
+        assert!(map.get("last").is_none());
+        assert!(!map.contains("missing"));
+        let positional: Vec<&str> = map
+            .positional()
+            .iter()
+            .map(|s| s.try_as_str().unwrap())
+            .collect();
+        assert_eq!(positional, ["pos1", "pos2"]);
+    }
+
+    #[test]
+    fn arg_map_repeated_key() {
+        let map = arg_map(&["--size", "1", "--size=2", "size=3"]);
+        assert_eq!(map.get_i64("size").unwrap(), Some(3));
+        let map = arg_map(&["size=3", "--size"]);
+        assert!(map.contains("size"));
+        assert!(map.get("size").is_none());
+    }
+}
@@ -4,19 +4,84 @@ use crate::{
 };
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
     hash::Hash,
 };
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+#[derive(PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
 pub enum RedisValueKey {
     Integer(i64),
     String(String),
     BulkRedisString(RedisString),
     BulkString(Vec<u8>),
     Bool(bool),
+    Double(DoubleKey),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// A double that can be used as a map key or a set element.
+/// Equality, hashing and ordering follow the IEEE 754 total order
+/// ([f64::total_cmp]), so `NaN` is equal to itself and `0.0` differs from `-0.0`.
+#[derive(Debug, Clone, Copy)]
+pub struct DoubleKey(pub f64);
+
+impl PartialEq for DoubleKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for DoubleKey {}
+
+impl PartialOrd for DoubleKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DoubleKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hash for DoubleKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+/// A reply of a command, or a value returned by [crate::Context::call].
+///
+/// Command handlers can build a [crate::RedisResult] with `into()` from the
+/// common Rust types:
+///
+/// | Rust type                                   | [RedisValue]                      |
+/// |---------------------------------------------|-----------------------------------|
+/// | `()`                                        | [RedisValue::Null]                |
+/// | `i64`, `i32`, `usize`                       | [RedisValue::Integer]             |
+/// | `f64`                                       | [RedisValue::Float]               |
+/// | `bool`                                      | [RedisValue::Bool]                |
+/// | `String`, `&String`, `&str`                 | [RedisValue::BulkString]          |
+/// | `Vec<u8>`                                   | [RedisValue::StringBuffer]        |
+/// | [RedisString], `&RedisString`               | [RedisValue::BulkRedisString]     |
+/// | `Option<T>`                                 | `T` converted, or [RedisValue::Null] for `None` |
+/// | `Vec<T>`                                    | [RedisValue::Array]               |
+/// | `HashMap<K, V>`, `BTreeMap<K, V>`           | [RedisValue::Map], [RedisValue::OrderedMap] |
+/// | `HashSet<K>`, `BTreeSet<K>`                 | [RedisValue::Set], [RedisValue::OrderedSet] |
+///
+/// For example `Ok(42.into())` or `Ok(Some(vec!["a", "b"]).into())`.
+///
+/// Values are equal when they are the same variant with equal contents, compared
+/// recursively. [RedisValue::Map] and [RedisValue::Set] compare by content
+/// regardless of their iteration order, but a [RedisValue::Map] is never equal to
+/// a [RedisValue::OrderedMap], see `RedisValue::canonicalize` (feature `test`).
+///
+/// The [fmt::Debug] output is deterministic, to make test failures readable: the
+/// entries of a [RedisValue::Map] or a [RedisValue::Set] are sorted by key, UTF-8
+/// strings are quoted and the other bytes of binary strings are hex escaped, for
+/// example `Map({String("a"): StringBuffer("\xff")})`. With `{:#?}` each element
+/// is printed on its own line.
+#[derive(PartialEq, Clone)]
 pub enum RedisValue {
     SimpleStringStatic(&'static str),
     SimpleString(String),
@@ -24,16 +89,26 @@ pub enum RedisValue {
     BulkRedisString(RedisString),
     StringBuffer(Vec<u8>),
     Integer(i64),
+    /// A RESP3 boolean, replied as `:1` or `:0` to RESP2 clients. See
+    /// [crate::Context::reply_bool_as_int] to always reply with an integer.
     Bool(bool),
     Float(f64),
+    /// Digits with an optional leading `-`. Anything else is replied as an error.
     BigNumber(String),
     VerbatimString((VerbatimStringFormat, Vec<u8>)),
     Array(Vec<RedisValue>),
     StaticError(&'static str),
+    /// An error element, for example inside the array replied by `EXEC`.
+    /// Replied as an error, so when it is the whole reply the command fails.
+    Error(String),
     Map(HashMap<RedisValueKey, RedisValue>),
     Set(HashSet<RedisValueKey>),
     OrderedMap(BTreeMap<RedisValueKey, RedisValue>),
     OrderedSet(BTreeSet<RedisValueKey>),
+    /// A map whose entries are replied in the order they are given, unlike
+    /// [RedisValue::Map] (no order) and [RedisValue::OrderedMap] (sorted by key).
+    /// Duplicate keys are not removed.
+    MapEntries(Vec<(RedisValueKey, RedisValue)>),
     Null,
     NoReply, // No reply at all (as opposed to a Null reply)
 }
@@ -52,6 +127,393 @@ impl TryFrom<RedisValue> for String {
     }
 }
 
+impl RedisValue {
+    /// The value of an [RedisValue::Integer]. Strings are not parsed.
+    #[must_use]
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Self::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The value of a [RedisValue::Float]. Strings are not parsed.
+    #[must_use]
+    pub fn as_double(&self) -> Option<f64> {
+        match self {
+            Self::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// The bytes of any of the string variants, or of the text of a
+    /// [RedisValue::VerbatimString].
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::SimpleStringStatic(s) => Some(s.as_bytes()),
+            Self::SimpleString(s) | Self::BulkString(s) => Some(s.as_bytes()),
+            Self::BulkRedisString(s) => Some(s.as_slice()),
+            Self::StringBuffer(b) | Self::VerbatimString((_, b)) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Same as [Self::as_bytes], for strings which are valid UTF-8.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(self.as_bytes()?).ok()
+    }
+
+    /// The elements of a [RedisValue::Array].
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[RedisValue]> {
+        match self {
+            Self::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// The entries of any of the map variants, in their iteration order.
+    #[must_use]
+    pub fn as_map(&self) -> Option<Vec<(&RedisValueKey, &RedisValue)>> {
+        match self {
+            Self::Map(map) => Some(map.iter().collect()),
+            Self::OrderedMap(map) => Some(map.iter().collect()),
+            Self::MapEntries(entries) => Some(entries.iter().map(|(k, v)| (k, v)).collect()),
+            _ => None,
+        }
+    }
+}
+
+impl RedisValue {
+    /// How deeply containers are nested in the value: 0 for a scalar, 1 for a
+    /// container holding only scalars (or a set), and so on. The value is
+    /// walked without recursion, so any depth can be measured.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        let mut max = 0;
+        let mut stack = vec![(self, 0)];
+        while let Some((value, level)) = stack.pop() {
+            let children: Box<dyn Iterator<Item = &Self>> = match value {
+                Self::Array(array) => Box::new(array.iter()),
+                Self::Map(map) => Box::new(map.values()),
+                Self::OrderedMap(map) => Box::new(map.values()),
+                Self::MapEntries(entries) => Box::new(entries.iter().map(|(_, v)| v)),
+                Self::Set(_) | Self::OrderedSet(_) => Box::new(std::iter::empty()),
+                _ => continue,
+            };
+            max = max.max(level + 1);
+            // Only containers are walked, so a large flat array is scanned once.
+            stack.extend(
+                children
+                    .filter(|child| child.is_container())
+                    .map(|child| (child, level + 1)),
+            );
+        }
+        max
+    }
+
+    const fn is_container(&self) -> bool {
+        matches!(
+            self,
+            Self::Array(_)
+                | Self::Map(_)
+                | Self::OrderedMap(_)
+                | Self::MapEntries(_)
+                | Self::Set(_)
+                | Self::OrderedSet(_)
+        )
+    }
+}
+
+#[cfg(feature = "test")]
+impl RedisValue {
+    /// Convert the value to a canonical form, to compare replies in tests
+    /// regardless of the iteration order of hash based containers:
+    /// [RedisValue::Map] becomes a [RedisValue::OrderedMap] and [RedisValue::Set]
+    /// becomes a [RedisValue::OrderedSet], sorted by key. Arrays and
+    /// [RedisValue::MapEntries], whose order is meaningful, keep their order,
+    /// their elements are canonicalized.
+    #[must_use]
+    pub fn canonicalize(self) -> Self {
+        match self {
+            Self::Array(array) => Self::Array(array.into_iter().map(Self::canonicalize).collect()),
+            Self::Map(map) => Self::OrderedMap(
+                map.into_iter()
+                    .map(|(k, v)| (k, v.canonicalize()))
+                    .collect(),
+            ),
+            Self::OrderedMap(map) => Self::OrderedMap(
+                map.into_iter()
+                    .map(|(k, v)| (k, v.canonicalize()))
+                    .collect(),
+            ),
+            Self::MapEntries(entries) => Self::MapEntries(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, v.canonicalize()))
+                    .collect(),
+            ),
+            Self::Set(set) => Self::OrderedSet(set.into_iter().collect()),
+            value => value,
+        }
+    }
+}
+
+/// Drop a value without recursing into its containers, which would overflow
+/// the stack for deeply nested values.
+pub(crate) fn drop_nested(value: RedisValue) {
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value {
+            RedisValue::Array(array) => stack.extend(array),
+            RedisValue::Map(map) => stack.extend(map.into_values()),
+            RedisValue::OrderedMap(map) => stack.extend(map.into_values()),
+            RedisValue::MapEntries(entries) => stack.extend(entries.into_iter().map(|(_, v)| v)),
+            _ => {}
+        }
+    }
+}
+
+/// Containers nested deeper than this are rendered as `...` by [debug_string].
+pub(crate) const DEBUG_MAX_DEPTH: usize = 16;
+
+fn write_debug_bytes(out: &mut String, bytes: &[u8]) {
+    out.push('"');
+    match std::str::from_utf8(bytes) {
+        Ok(s) => out.extend(s.chars().flat_map(char::escape_debug)),
+        Err(_) => bytes.iter().for_each(|b| match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            0x20..=0x7e => out.push(*b as char),
+            _ => out.push_str(&format!("\\x{b:02x}")),
+        }),
+    }
+    out.push('"');
+}
+
+fn write_debug_key(out: &mut String, key: &RedisValueKey) {
+    match key {
+        RedisValueKey::Integer(i) => out.push_str(&i.to_string()),
+        RedisValueKey::String(s) => write_debug_bytes(out, s.as_bytes()),
+        RedisValueKey::BulkRedisString(s) => write_debug_bytes(out, s.as_slice()),
+        RedisValueKey::BulkString(b) => write_debug_bytes(out, b),
+        RedisValueKey::Bool(b) => out.push_str(&b.to_string()),
+        RedisValueKey::Double(d) => out.push_str(&d.0.to_string()),
+    }
+}
+
+fn write_debug_list<'a, T: 'a>(
+    out: &mut String,
+    (open, close): (char, char),
+    items: impl IntoIterator<Item = T>,
+    mut write_item: impl FnMut(&mut String, T),
+) {
+    out.push(open);
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_item(out, item);
+    }
+    out.push(close);
+}
+
+fn write_debug(out: &mut String, value: &RedisValue, depth: usize) {
+    if value.is_container() && depth >= DEBUG_MAX_DEPTH {
+        out.push_str("...");
+        return;
+    }
+
+    let write_entry = |out: &mut String, (key, value): (&RedisValueKey, &RedisValue)| {
+        write_debug_key(out, key);
+        out.push_str(": ");
+        write_debug(out, value, depth + 1);
+    };
+    match value {
+        RedisValue::SimpleStringStatic(s) => write_debug_bytes(out, s.as_bytes()),
+        RedisValue::SimpleString(s) | RedisValue::BulkString(s) => {
+            write_debug_bytes(out, s.as_bytes())
+        }
+        RedisValue::BulkRedisString(s) => write_debug_bytes(out, s.as_slice()),
+        RedisValue::StringBuffer(b) => write_debug_bytes(out, b),
+        RedisValue::VerbatimString((format, data)) => {
+            let format: Vec<u8> = format.0.iter().map(|c| *c as u8).collect();
+            out.push_str(&String::from_utf8_lossy(&format));
+            out.push(':');
+            write_debug_bytes(out, data);
+        }
+        RedisValue::Integer(i) => out.push_str(&i.to_string()),
+        RedisValue::Bool(b) => out.push_str(&b.to_string()),
+        RedisValue::Float(f) => out.push_str(&f.to_string()),
+        RedisValue::BigNumber(n) => out.push_str(n),
+        RedisValue::StaticError(e) => {
+            out.push_str("(error) ");
+            out.push_str(e);
+        }
+        RedisValue::Error(e) => {
+            out.push_str("(error) ");
+            out.push_str(e);
+        }
+        RedisValue::Null => out.push_str("null"),
+        RedisValue::NoReply => out.push_str("(no reply)"),
+        RedisValue::Array(array) => write_debug_list(out, ('[', ']'), array, |out, v| {
+            write_debug(out, v, depth + 1)
+        }),
+        RedisValue::Set(set) => write_debug_list(out, ('[', ']'), set, write_debug_key),
+        RedisValue::OrderedSet(set) => write_debug_list(out, ('[', ']'), set, write_debug_key),
+        RedisValue::Map(map) => write_debug_list(out, ('{', '}'), map, write_entry),
+        RedisValue::OrderedMap(map) => write_debug_list(out, ('{', '}'), map, write_entry),
+        RedisValue::MapEntries(entries) => write_debug_list(
+            out,
+            ('{', '}'),
+            entries.iter().map(|(k, v)| (k, v)),
+            write_entry,
+        ),
+    }
+}
+
+/// Render a value as a single line, JSON like, string for debugging.
+/// Binary strings are hex escaped and containers nested deeper than
+/// [DEBUG_MAX_DEPTH] are cut off.
+pub(crate) fn debug_string(value: &RedisValue) -> String {
+    let mut out = String::new();
+    write_debug(&mut out, value, 0);
+    out
+}
+
+/// Debug bytes as a quoted string, see [write_debug_bytes].
+struct DebugBytes<'a>(&'a [u8]);
+
+impl fmt::Debug for DebugBytes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        write_debug_bytes(&mut out, self.0);
+        f.write_str(&out)
+    }
+}
+
+impl fmt::Debug for RedisValueKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Integer(i) => f.debug_tuple("Integer").field(i).finish(),
+            Self::String(s) => f.debug_tuple("String").field(s).finish(),
+            Self::BulkRedisString(s) => f
+                .debug_tuple("BulkRedisString")
+                .field(&DebugBytes(s.as_slice()))
+                .finish(),
+            Self::BulkString(b) => f.debug_tuple("BulkString").field(&DebugBytes(b)).finish(),
+            Self::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+            Self::Double(d) => f.debug_tuple("Double").field(&d.0).finish(),
+        }
+    }
+}
+
+/// Debug the entries of a map sorted by key.
+struct SortedMap<'a>(Vec<(&'a RedisValueKey, &'a RedisValue)>);
+
+impl<'a> SortedMap<'a> {
+    fn new(entries: impl Iterator<Item = (&'a RedisValueKey, &'a RedisValue)>) -> Self {
+        let mut entries: Vec<_> = entries.collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Self(entries)
+    }
+}
+
+impl fmt::Debug for SortedMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.iter().copied()).finish()
+    }
+}
+
+/// Debug the elements of a set sorted.
+struct SortedSet<'a>(Vec<&'a RedisValueKey>);
+
+impl<'a> SortedSet<'a> {
+    fn new(elements: impl Iterator<Item = &'a RedisValueKey>) -> Self {
+        let mut elements: Vec<_> = elements.collect();
+        elements.sort();
+        Self(elements)
+    }
+}
+
+impl fmt::Debug for SortedSet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(&self.0).finish()
+    }
+}
+
+impl fmt::Debug for RedisValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SimpleStringStatic(s) => f.debug_tuple("SimpleStringStatic").field(s).finish(),
+            Self::SimpleString(s) => f.debug_tuple("SimpleString").field(s).finish(),
+            Self::BulkString(s) => f.debug_tuple("BulkString").field(s).finish(),
+            Self::BulkRedisString(s) => f
+                .debug_tuple("BulkRedisString")
+                .field(&DebugBytes(s.as_slice()))
+                .finish(),
+            Self::StringBuffer(b) => f.debug_tuple("StringBuffer").field(&DebugBytes(b)).finish(),
+            Self::Integer(i) => f.debug_tuple("Integer").field(i).finish(),
+            Self::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+            Self::Float(d) => f.debug_tuple("Float").field(d).finish(),
+            Self::BigNumber(n) => f.debug_tuple("BigNumber").field(n).finish(),
+            Self::VerbatimString((format, data)) => {
+                let format: Vec<u8> = format.0.iter().map(|c| *c as u8).collect();
+                f.debug_tuple("VerbatimString")
+                    .field(&DebugBytes(&format))
+                    .field(&DebugBytes(data))
+                    .finish()
+            }
+            Self::Array(array) => f.debug_tuple("Array").field(array).finish(),
+            Self::StaticError(e) => f.debug_tuple("StaticError").field(e).finish(),
+            Self::Error(e) => f.debug_tuple("Error").field(e).finish(),
+            Self::Map(map) => f
+                .debug_tuple("Map")
+                .field(&SortedMap::new(map.iter()))
+                .finish(),
+            Self::Set(set) => f
+                .debug_tuple("Set")
+                .field(&SortedSet::new(set.iter()))
+                .finish(),
+            Self::OrderedMap(map) => f.debug_tuple("OrderedMap").field(map).finish(),
+            Self::OrderedSet(set) => f.debug_tuple("OrderedSet").field(set).finish(),
+            Self::MapEntries(entries) => f
+                .debug_tuple("MapEntries")
+                .field(&DebugEntries(entries))
+                .finish(),
+            Self::Null => f.write_str("Null"),
+            Self::NoReply => f.write_str("NoReply"),
+        }
+    }
+}
+
+/// Debug the entries of a [RedisValue::MapEntries] as a map, in their order.
+struct DebugEntries<'a>(&'a [(RedisValueKey, RedisValue)]);
+
+impl fmt::Debug for DebugEntries<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
+
+#[cfg(feature = "serde-conversions")]
+impl RedisValue {
+    /// Deserialize the value into `T`, matching struct fields to map keys.
+    /// Arrays of `key, value` pairs (maps in RESP2) are accepted as maps, and
+    /// strings are parsed when a number or a bool is expected.
+    /// The returned error names the path of the field that failed, see
+    /// [crate::redisvalue_serde::Error::path].
+    pub fn deserialize<T: serde::de::DeserializeOwned>(
+        &self,
+    ) -> Result<T, crate::redisvalue_serde::Error> {
+        crate::redisvalue_serde::from_redis_value(self)
+    }
+}
+
 impl From<String> for RedisValueKey {
     fn from(s: String) -> Self {
         Self::String(s)
@@ -94,6 +556,12 @@ impl From<bool> for RedisValueKey {
     }
 }
 
+impl From<f64> for RedisValueKey {
+    fn from(d: f64) -> Self {
+        Self::Double(DoubleKey(d))
+    }
+}
+
 impl From<()> for RedisValue {
     fn from(_: ()) -> Self {
         Self::Null
@@ -106,6 +574,12 @@ impl From<i64> for RedisValue {
     }
 }
 
+impl From<i32> for RedisValue {
+    fn from(i: i32) -> Self {
+        i64::from(i).into()
+    }
+}
+
 impl From<bool> for RedisValue {
     fn from(b: bool) -> Self {
         Self::Bool(b)
@@ -217,6 +691,7 @@ impl<'root> TryFrom<&CallReply<'root>> for RedisValueKey {
                     RedisValueKey::String(v)
                 })),
             CallReply::Bool(b) => Ok(RedisValueKey::Bool(b.to_bool())),
+            CallReply::Double(d) => Ok(RedisValueKey::from(d.to_double())),
             _ => Err(RedisError::String(format!(
                 "Given CallReply can not be used as a map key or a set element, {:?}",
                 reply
@@ -225,45 +700,132 @@ impl<'root> TryFrom<&CallReply<'root>> for RedisValueKey {
     }
 }
 
+/// A container reply whose elements are being converted by
+/// `From<&CallReply> for RedisValue`.
+enum CallReplyFrame<'root> {
+    Array {
+        elements: std::vec::IntoIter<CallResult<'root>>,
+        values: Vec<RedisValue>,
+    },
+    // Keep the entries in the order the server sent them.
+    Map {
+        entries: std::vec::IntoIter<(CallResult<'root>, CallResult<'root>)>,
+        values: Vec<(RedisValueKey, RedisValue)>,
+        key: Option<RedisValueKey>,
+    },
+}
+
+impl<'root> CallReplyFrame<'root> {
+    /// The next element to convert, if any.
+    fn next_element(&mut self) -> Option<CallResult<'root>> {
+        match self {
+            Self::Array { elements, .. } => elements.next(),
+            Self::Map { entries, key, .. } => entries.next().map(|(k, v)| {
+                *key =
+                    Some((&k).try_into().unwrap_or_else(|e| {
+                        panic!("Got unhashable map key from Redis, {k:?}, {e}")
+                    }));
+                v
+            }),
+        }
+    }
+
+    /// Add the converted value of the element returned by [Self::next_element].
+    fn push(&mut self, value: RedisValue) {
+        match self {
+            Self::Array { values, .. } => values.push(value),
+            Self::Map { values, key, .. } => values.push((key.take().unwrap(), value)),
+        }
+    }
+
+    fn into_value(self) -> RedisValue {
+        match self {
+            Self::Array { values, .. } => RedisValue::Array(values),
+            Self::Map { values, .. } => RedisValue::MapEntries(values),
+        }
+    }
+}
+
+/// Either a converted value, or a container whose elements are yet to be converted.
+enum ConvertedCallReply<'root> {
+    Value(RedisValue),
+    Container(CallReplyFrame<'root>),
+}
+
+fn convert_call_reply<'root>(reply: &CallReply<'root>) -> ConvertedCallReply<'root> {
+    let value = match reply {
+        CallReply::Unknown => RedisValue::StaticError("Error on method call"),
+        CallReply::Array(reply) => {
+            return ConvertedCallReply::Container(CallReplyFrame::Array {
+                elements: reply.elements().into_iter(),
+                values: Vec::with_capacity(reply.len()),
+            })
+        }
+        CallReply::I64(reply) => RedisValue::Integer(reply.to_i64()),
+        CallReply::String(reply) => RedisValue::SimpleString(reply.to_string().unwrap()),
+        CallReply::Null(_) => RedisValue::Null,
+        CallReply::Map(reply) => {
+            return ConvertedCallReply::Container(CallReplyFrame::Map {
+                entries: reply.entries().into_iter(),
+                values: Vec::with_capacity(reply.len()),
+                key: None,
+            })
+        }
+        CallReply::Set(reply) => RedisValue::Set(
+            reply
+                .iter()
+                .map(|v| {
+                    (&v).try_into().unwrap_or_else(|e| {
+                        panic!("Got unhashable set element from Redis, {v:?}, {e}")
+                    })
+                })
+                .collect(),
+        ),
+        CallReply::Bool(reply) => RedisValue::Bool(reply.to_bool()),
+        CallReply::Double(reply) => RedisValue::Float(reply.to_double()),
+        CallReply::BigNumber(reply) => RedisValue::BigNumber(reply.to_string().unwrap()),
+        CallReply::VerbatimString(reply) => RedisValue::VerbatimString(reply.to_parts().unwrap()),
+    };
+    ConvertedCallReply::Value(value)
+}
+
+fn convert_call_result<'root>(reply: &CallResult<'root>) -> ConvertedCallReply<'root> {
+    match reply {
+        Ok(reply) => convert_call_reply(reply),
+        Err(e) => ConvertedCallReply::Value(RedisValue::Error(
+            String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        )),
+    }
+}
+
+/// The reply is converted without recursion, using a stack of the containers
+/// being converted, so that a deeply nested reply, for example of a command
+/// whose reply depth is influenced by a client, can not overflow the stack.
 impl<'root> From<&CallReply<'root>> for RedisValue {
     fn from(reply: &CallReply<'root>) -> Self {
-        match reply {
-            CallReply::Unknown => RedisValue::StaticError("Error on method call"),
-            CallReply::Array(reply) => {
-                RedisValue::Array(reply.iter().map(|v| (&v).into()).collect())
-            }
-            CallReply::I64(reply) => RedisValue::Integer(reply.to_i64()),
-            CallReply::String(reply) => RedisValue::SimpleString(reply.to_string().unwrap()),
-            CallReply::Null(_) => RedisValue::Null,
-            CallReply::Map(reply) => RedisValue::Map(
-                reply
-                    .iter()
-                    .map(|(key, val)| {
-                        (
-                            (&key).try_into().unwrap_or_else(|e| {
-                                panic!("Got unhashable map key from Redis, {key:?}, {e}")
-                            }),
-                            (&val).into(),
-                        )
-                    })
-                    .collect(),
-            ),
-            CallReply::Set(reply) => RedisValue::Set(
-                reply
-                    .iter()
-                    .map(|v| {
-                        (&v).try_into().unwrap_or_else(|e| {
-                            panic!("Got unhashable set element from Redis, {v:?}, {e}")
-                        })
-                    })
-                    .collect(),
-            ),
-            CallReply::Bool(reply) => RedisValue::Bool(reply.to_bool()),
-            CallReply::Double(reply) => RedisValue::Float(reply.to_double()),
-            CallReply::BigNumber(reply) => RedisValue::BigNumber(reply.to_string().unwrap()),
-            CallReply::VerbatimString(reply) => {
-                RedisValue::VerbatimString(reply.to_parts().unwrap())
+        let mut stack: Vec<CallReplyFrame> = Vec::new();
+        let mut converted = convert_call_reply(reply);
+        loop {
+            match converted {
+                ConvertedCallReply::Container(frame) => stack.push(frame),
+                ConvertedCallReply::Value(value) => match stack.last_mut() {
+                    Some(parent) => parent.push(value),
+                    None => return value,
+                },
             }
+            // Find the next element to convert, completing the containers
+            // whose elements were all converted.
+            converted = loop {
+                let frame = stack.last_mut().unwrap();
+                if let Some(element) = frame.next_element() {
+                    break convert_call_result(&element);
+                }
+                let value = stack.pop().unwrap().into_value();
+                match stack.last_mut() {
+                    Some(parent) => parent.push(value),
+                    None => return value,
+                }
+            };
         }
     }
 }
@@ -271,12 +833,7 @@ impl<'root> From<&CallReply<'root>> for RedisValue {
 impl<'root> From<&CallResult<'root>> for RedisValue {
     fn from(reply: &CallResult<'root>) -> Self {
         reply.as_ref().map_or_else(
-            |e| {
-                // [RedisValue] does not support error, we can change that but to avoid
-                // drastic changes and try to keep backword compatability, currently
-                // we will stansform the error into a String buffer.
-                RedisValue::StringBuffer(e.as_bytes().to_vec())
-            },
+            |e| RedisValue::Error(String::from_utf8_lossy(e.as_bytes()).into_owned()),
             |v| (v).into(),
         )
     }
@@ -300,7 +857,8 @@ impl<'root> TryFrom<&CallResult<'root>> for RedisValueKey {
 
 #[cfg(test)]
 mod tests {
-    use super::RedisValue;
+    use super::{RedisValue, RedisValueKey};
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
     #[test]
     fn from_vec_string() {
@@ -347,4 +905,364 @@ mod tests {
     fn from_option_none() {
         assert_eq!(RedisValue::from(None::<()>), RedisValue::Null,);
     }
+
+    #[test]
+    fn from_scalars() {
+        assert_eq!(RedisValue::from(42), RedisValue::Integer(42));
+        assert_eq!(RedisValue::from(42_i64), RedisValue::Integer(42));
+        assert_eq!(RedisValue::from(42_usize), RedisValue::Integer(42));
+        assert_eq!(RedisValue::from(1.5), RedisValue::Float(1.5));
+        assert_eq!(RedisValue::from(true), RedisValue::Bool(true));
+        assert_eq!(RedisValue::from(()), RedisValue::Null);
+    }
+
+    #[test]
+    fn from_option_collections() {
+        assert_eq!(
+            RedisValue::from(Some(vec![1, 2])),
+            RedisValue::Array(vec![RedisValue::Integer(1), RedisValue::Integer(2)])
+        );
+        assert_eq!(
+            RedisValue::from(vec![Some("a"), None]),
+            RedisValue::Array(vec![
+                RedisValue::BulkString("a".to_owned()),
+                RedisValue::Null
+            ])
+        );
+        assert_eq!(RedisValue::from(None::<Vec<i64>>), RedisValue::Null);
+    }
+
+    #[test]
+    fn from_maps_and_sets() {
+        let map = BTreeMap::from([("a", 1), ("b", 2)]);
+        assert_eq!(
+            RedisValue::from(map),
+            RedisValue::OrderedMap(BTreeMap::from([
+                (
+                    RedisValueKey::String("a".to_owned()),
+                    RedisValue::Integer(1)
+                ),
+                (
+                    RedisValueKey::String("b".to_owned()),
+                    RedisValue::Integer(2)
+                ),
+            ]))
+        );
+        assert_eq!(
+            RedisValue::from(HashSet::from([1_i64])),
+            RedisValue::Set(HashSet::from([RedisValueKey::Integer(1)]))
+        );
+    }
+
+    #[test]
+    fn accessors() {
+        let reply = RedisValue::Array(vec![
+            RedisValue::Integer(7),
+            RedisValue::SimpleString("a".to_owned()),
+            RedisValue::StringBuffer(b"\xff".to_vec()),
+        ]);
+        let array = reply.as_array().unwrap();
+        assert_eq!(array[0].as_integer(), Some(7));
+        assert_eq!(array[1].as_str(), Some("a"));
+        assert_eq!(array[2].as_bytes(), Some(&b"\xff"[..]));
+        assert_eq!(array[2].as_str(), None);
+        assert_eq!(array[1].as_integer(), None);
+        assert_eq!(RedisValue::Float(0.5).as_double(), Some(0.5));
+        assert_eq!(reply.as_map(), None);
+
+        let map = RedisValue::MapEntries(vec![(RedisValueKey::Integer(1), RedisValue::Null)]);
+        assert_eq!(
+            map.as_map(),
+            Some(vec![(&RedisValueKey::Integer(1), &RedisValue::Null)])
+        );
+    }
+
+    #[test]
+    fn set_keeps_element_types() {
+        let set: std::collections::HashSet<RedisValueKey> = [
+            RedisValueKey::from(1),
+            RedisValueKey::from(1.0),
+            RedisValueKey::from("1"),
+            RedisValueKey::from(1.0),
+            RedisValueKey::from(f64::NAN),
+            RedisValueKey::from(f64::NAN),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(&RedisValueKey::Double(super::DoubleKey(1.0))));
+        assert!(set.contains(&RedisValueKey::Integer(1)));
+        assert_ne!(RedisValueKey::from(0.0), RedisValueKey::from(-0.0));
+    }
+
+    #[test]
+    fn debug_string() {
+        let value = RedisValue::MapEntries(vec![
+            (
+                RedisValueKey::String("name".to_owned()),
+                RedisValue::BulkString("a \"b\"".to_owned()),
+            ),
+            (
+                RedisValueKey::Integer(1),
+                RedisValue::Array(vec![
+                    RedisValue::StringBuffer(vec![b'x', 0, 255]),
+                    RedisValue::Float(1.5),
+                    RedisValue::Null,
+                ]),
+            ),
+        ]);
+        assert_eq!(
+            super::debug_string(&value),
+            r#"{"name": "a \"b\"", 1: ["x\x00\xff", 1.5, null]}"#
+        );
+    }
+
+    #[test]
+    fn debug_is_deterministic() {
+        let map: HashMap<RedisValueKey, RedisValue> = (0..32)
+            .map(|i| (RedisValueKey::Integer(i), RedisValue::Integer(i)))
+            .collect();
+        let set: HashSet<RedisValueKey> = (0..32).map(RedisValueKey::Integer).collect();
+        let expected_map = format!(
+            "Map({{{}}})",
+            (0..32)
+                .map(|i| format!("Integer({i}): Integer({i})"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let expected_set = format!(
+            "Set({{{}}})",
+            (0..32)
+                .map(|i| format!("Integer({i})"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        assert_eq!(format!("{:?}", RedisValue::Map(map)), expected_map);
+        assert_eq!(format!("{:?}", RedisValue::Set(set)), expected_set);
+    }
+
+    #[test]
+    fn debug_nested_binary() {
+        let value = RedisValue::Array(vec![
+            RedisValue::StringBuffer(vec![b'a', b'"', 0, 255]),
+            RedisValue::MapEntries(vec![(
+                RedisValueKey::BulkString(vec![0xfe]),
+                RedisValue::Set([RedisValueKey::from("b"), RedisValueKey::from("a")].into()),
+            )]),
+            RedisValue::Null,
+        ]);
+        assert_eq!(
+            format!("{value:?}"),
+            r#"Array([StringBuffer("a\"\x00\xff"), MapEntries({BulkString("\xfe"): Set({String("a"), String("b")})}), Null])"#
+        );
+        assert_eq!(
+            format!(
+                "{:?}",
+                RedisValue::StringBuffer("café\n".as_bytes().to_vec())
+            ),
+            r#"StringBuffer("café\n")"#
+        );
+    }
+
+    #[test]
+    fn eq_ignores_map_and_set_order() {
+        let a: RedisValue = HashMap::from([("a", vec![1]), ("b", vec![2])]).into();
+        let b: RedisValue = HashMap::from([("b", vec![2]), ("a", vec![1])]).into();
+        assert_eq!(a, b);
+        let c: RedisValue = HashMap::from([("a", vec![1]), ("b", vec![3])]).into();
+        assert_ne!(a, c);
+        assert_eq!(
+            RedisValue::from(HashSet::from(["x", "y"])),
+            RedisValue::from(HashSet::from(["y", "x"]))
+        );
+        assert_ne!(
+            a,
+            RedisValue::from(BTreeMap::from([("a", vec![1]), ("b", vec![2])]))
+        );
+    }
+
+    #[test]
+    fn depth() {
+        assert_eq!(RedisValue::Integer(1).depth(), 0);
+        assert_eq!(RedisValue::Array(vec![]).depth(), 1);
+        let map = BTreeMap::from([(
+            RedisValueKey::Integer(1),
+            RedisValue::Array(vec![RedisValue::Null]),
+        )]);
+        let value = RedisValue::Array(vec![
+            RedisValue::Integer(1),
+            RedisValue::OrderedMap(map),
+            RedisValue::Set(HashSet::new()),
+        ]);
+        assert_eq!(value.depth(), 3);
+    }
+
+    #[test]
+    fn canonicalize() {
+        let map = HashMap::from([
+            (
+                RedisValueKey::Integer(2),
+                RedisValue::Set(HashSet::from([
+                    RedisValueKey::Integer(2),
+                    RedisValueKey::Integer(1),
+                ])),
+            ),
+            (RedisValueKey::Integer(1), RedisValue::Null),
+        ]);
+        let value = RedisValue::Array(vec![RedisValue::Integer(3), RedisValue::Map(map)]);
+        let expected = RedisValue::Array(vec![
+            RedisValue::Integer(3),
+            RedisValue::OrderedMap(BTreeMap::from([
+                (RedisValueKey::Integer(1), RedisValue::Null),
+                (
+                    RedisValueKey::Integer(2),
+                    RedisValue::OrderedSet(BTreeSet::from([
+                        RedisValueKey::Integer(1),
+                        RedisValueKey::Integer(2),
+                    ])),
+                ),
+            ])),
+        ]);
+        assert_eq!(value.canonicalize(), expected);
+    }
+
+    #[test]
+    fn deeply_nested_value() {
+        let mut value = RedisValue::Null;
+        for _ in 0..100_000 {
+            value = RedisValue::Array(vec![value]);
+        }
+        assert_eq!(value.depth(), 100_000);
+        super::drop_nested(value);
+    }
+
+    #[test]
+    fn debug_string_depth_limit() {
+        let mut value = RedisValue::Integer(1);
+        for _ in 0..super::DEBUG_MAX_DEPTH + 1 {
+            value = RedisValue::Array(vec![value]);
+        }
+        let s = super::debug_string(&value);
+        assert!(s.starts_with(&"[".repeat(super::DEBUG_MAX_DEPTH)));
+        assert!(s.contains("[...]"));
+        assert!(!s.contains('1'));
+    }
+
+    #[cfg(feature = "serde-conversions")]
+    mod serde_conversions {
+        use super::{RedisValue, RedisValueKey};
+        use serde::Deserialize;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Group {
+            name: String,
+            consumers: u32,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Info {
+            length: i64,
+            groups: Vec<Group>,
+            last: Option<String>,
+        }
+
+        fn key(s: &str) -> RedisValueKey {
+            RedisValueKey::String(s.to_owned())
+        }
+
+        fn bulk(s: &str) -> RedisValue {
+            RedisValue::BulkString(s.to_owned())
+        }
+
+        #[test]
+        fn deserialize_map() {
+            let value = RedisValue::MapEntries(vec![
+                (key("length"), RedisValue::Integer(2)),
+                (
+                    key("groups"),
+                    RedisValue::Array(vec![RedisValue::MapEntries(vec![
+                        (key("name"), bulk("g1")),
+                        (key("consumers"), RedisValue::Integer(3)),
+                    ])]),
+                ),
+                (key("last"), RedisValue::Null),
+                (key("unknown"), bulk("ignored")),
+            ]);
+            assert_eq!(
+                value.deserialize::<Info>().unwrap(),
+                Info {
+                    length: 2,
+                    groups: vec![Group {
+                        name: "g1".to_owned(),
+                        consumers: 3
+                    }],
+                    last: None,
+                }
+            );
+        }
+
+        #[test]
+        fn deserialize_flat_array() {
+            let value =
+                RedisValue::Array(vec![bulk("name"), bulk("g1"), bulk("consumers"), bulk("3")]);
+            assert_eq!(
+                value.deserialize::<Group>().unwrap(),
+                Group {
+                    name: "g1".to_owned(),
+                    consumers: 3
+                }
+            );
+        }
+
+        #[test]
+        fn deserialize_errors_name_the_field() {
+            let value = RedisValue::MapEntries(vec![(key("name"), bulk("g1"))]);
+            let err = value.deserialize::<Group>().unwrap_err();
+            assert_eq!(err.to_string(), "missing field `consumers`");
+
+            let value = RedisValue::MapEntries(vec![
+                (key("length"), RedisValue::Integer(2)),
+                (
+                    key("groups"),
+                    RedisValue::Array(vec![RedisValue::MapEntries(vec![
+                        (key("name"), bulk("g1")),
+                        (key("consumers"), bulk("many")),
+                    ])]),
+                ),
+            ]);
+            let err = value.deserialize::<Info>().unwrap_err();
+            assert_eq!(err.path(), "groups[0].consumers");
+            assert!(err.to_string().contains("at `groups[0].consumers`"));
+        }
+    }
+
+    #[cfg(feature = "test")]
+    mod owned_strings {
+        use super::RedisValue;
+        use crate::RedisString;
+
+        #[test]
+        fn parse_owned_strings() {
+            let s = RedisString::from_bytes_owned(b"42");
+            assert_eq!(s.try_as_str().unwrap(), "42");
+            assert_eq!(s.parse_integer().unwrap(), 42);
+            assert_eq!(s.parse_float().unwrap(), 42.0);
+            assert!(RedisString::from_bytes_owned(b"4x")
+                .parse_integer()
+                .is_err());
+
+            let binary = RedisString::from_bytes_owned(b"\xff\x00");
+            assert_eq!(binary.as_slice(), b"\xff\x00");
+            assert!(binary.try_as_str().is_err());
+        }
+
+        #[test]
+        fn compare_and_clone_owned_strings() {
+            let a = RedisString::from_bytes_owned(b"a");
+            let b = RedisString::from_bytes_owned(b"b");
+            assert!(a < b);
+            assert_eq!(a.clone(), a);
+            assert_eq!(RedisValue::from(a.clone()), RedisValue::BulkRedisString(a));
+        }
+    }
 }
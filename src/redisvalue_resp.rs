@@ -0,0 +1,488 @@
+//! Parsing of the RESP2 and RESP3 wire format into a [RedisValue], without a
+//! server, for example to test or fuzz the code consuming the replies of
+//! [crate::Context::call].
+//!
+//! The types are mapped as for the conversion of a [crate::CallReply] into a
+//! [RedisValue]: maps keep the order of their entries as
+//! [RedisValue::MapEntries], and error replies are [RedisValue::Error] values.
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use crate::context::call_reply::VerbatimStringFormat;
+use crate::redisvalue::{debug_string, drop_nested, RedisValue, RedisValueKey};
+use crate::RedisError;
+
+impl RedisValue {
+    /// Parse a single RESP2 or RESP3 value, which must span all of `bytes`.
+    ///
+    /// Strings are [RedisValue::SimpleString] values, or
+    /// [RedisValue::StringBuffer] values when they are not valid UTF-8. An error
+    /// reply, at any depth, is a [RedisValue::Error]. RESP3 attributes are
+    /// skipped and push messages are parsed as arrays.
+    ///
+    /// Malformed or incomplete input is reported as an error, never with a
+    /// panic, and nested values are parsed without recursion, so any input can
+    /// be given, for example by a fuzzer.
+    pub fn from_resp_bytes(bytes: &[u8]) -> Result<Self, RedisError> {
+        parse_resp(bytes, false)
+    }
+}
+
+/// Parse a value to reply with, see [crate::Context::reply_raw_protocol]. Unlike
+/// [RedisValue::from_resp_bytes], bulk strings are [RedisValue::StringBuffer]
+/// values, so they are replied as bulk strings and not as simple strings.
+pub(crate) fn parse_resp_reply(bytes: &[u8]) -> Result<RedisValue, RedisError> {
+    parse_resp(bytes, true)
+}
+
+fn parse_resp(bytes: &[u8], bulk_strings: bool) -> Result<RedisValue, RedisError> {
+    let mut parser = Parser {
+        input: bytes,
+        pos: 0,
+        bulk_strings,
+    };
+    let value = parser.parse()?;
+    if parser.pos != bytes.len() {
+        drop_nested(value);
+        return Err(RedisError::Str("ERR unexpected data after the RESP value"));
+    }
+    Ok(value)
+}
+
+/// A container whose elements are being parsed.
+enum Frame {
+    Array {
+        remaining: usize,
+        values: Vec<RedisValue>,
+    },
+    Map {
+        remaining: usize,
+        values: Vec<(RedisValueKey, RedisValue)>,
+        key: Option<RedisValueKey>,
+    },
+    Set {
+        remaining: usize,
+        values: HashSet<RedisValueKey>,
+    },
+    /// The elements of an attribute are parsed and dropped.
+    Attribute { remaining: usize },
+}
+
+impl Frame {
+    fn is_complete(&self) -> bool {
+        match self {
+            Self::Array { remaining, .. }
+            | Self::Set { remaining, .. }
+            | Self::Attribute { remaining } => *remaining == 0,
+            Self::Map { remaining, key, .. } => *remaining == 0 && key.is_none(),
+        }
+    }
+
+    /// Add the value of the next element.
+    fn push(&mut self, value: RedisValue) -> Result<(), RedisError> {
+        match self {
+            Self::Array { remaining, values } => {
+                values.push(value);
+                *remaining -= 1;
+            }
+            Self::Map {
+                remaining,
+                values,
+                key,
+            } => match key.take() {
+                Some(key) => {
+                    values.push((key, value));
+                    *remaining -= 1;
+                }
+                None => *key = Some(to_key(value)?),
+            },
+            Self::Set { remaining, values } => {
+                values.insert(to_key(value)?);
+                *remaining -= 1;
+            }
+            Self::Attribute { remaining } => {
+                drop_nested(value);
+                *remaining -= 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// The value of the container, `None` for an attribute.
+    fn into_value(self) -> Option<RedisValue> {
+        match self {
+            Self::Array { values, .. } => Some(RedisValue::Array(values)),
+            Self::Map { values, .. } => Some(RedisValue::MapEntries(values)),
+            Self::Set { values, .. } => Some(RedisValue::Set(values)),
+            Self::Attribute { .. } => None,
+        }
+    }
+}
+
+/// Convert a map key or a set element, like `TryFrom<&CallReply> for RedisValueKey`.
+fn to_key(value: RedisValue) -> Result<RedisValueKey, RedisError> {
+    match value {
+        RedisValue::SimpleString(s) => Ok(RedisValueKey::String(s)),
+        RedisValue::StringBuffer(b) => Ok(RedisValueKey::BulkString(b)),
+        RedisValue::Integer(i) => Ok(RedisValueKey::Integer(i)),
+        RedisValue::Bool(b) => Ok(RedisValueKey::Bool(b)),
+        RedisValue::Float(f) => Ok(RedisValueKey::from(f)),
+        value => {
+            let err = RedisError::String(format!(
+                "ERR RESP value can not be used as a map key or a set element, {}",
+                debug_string(&value)
+            ));
+            drop_nested(value);
+            Err(err)
+        }
+    }
+}
+
+/// Either a parsed value, or a container whose elements are yet to be parsed.
+enum Parsed {
+    Value(RedisValue),
+    Container(Frame),
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    /// Whether bulk strings are always [RedisValue::StringBuffer] values.
+    bulk_strings: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(&mut self) -> Result<RedisValue, RedisError> {
+        let mut stack: Vec<Frame> = Vec::new();
+        let res = self.parse_with(&mut stack);
+        // On error, the containers being parsed may hold deeply nested values.
+        stack
+            .into_iter()
+            .filter_map(Frame::into_value)
+            .for_each(drop_nested);
+        res
+    }
+
+    fn parse_with(&mut self, stack: &mut Vec<Frame>) -> Result<RedisValue, RedisError> {
+        loop {
+            let mut value = match self.next_value()? {
+                Parsed::Value(value) => Some(value),
+                Parsed::Container(frame) if frame.is_complete() => frame.into_value(),
+                Parsed::Container(frame) => {
+                    stack.push(frame);
+                    continue;
+                }
+            };
+            // Add the value to its parent, completing the containers whose
+            // elements were all parsed.
+            while let Some(element) = value.take() {
+                let parent = match stack.last_mut() {
+                    Some(parent) => parent,
+                    None => return Ok(element),
+                };
+                parent.push(element)?;
+                if parent.is_complete() {
+                    value = stack.pop().and_then(Frame::into_value);
+                }
+            }
+        }
+    }
+
+    /// Parse the next value, or the header of the next container.
+    fn next_value(&mut self) -> Result<Parsed, RedisError> {
+        let kind = *self.input.get(self.pos).ok_or_else(incomplete)?;
+        self.pos += 1;
+        let value = match kind {
+            b'+' => string(self.line()?),
+            b'-' => RedisValue::Error(String::from_utf8_lossy(self.line()?).into_owned()),
+            b':' => RedisValue::Integer(self.integer()?),
+            b'$' => match self.length()? {
+                Some(len) if self.bulk_strings => {
+                    RedisValue::StringBuffer(self.blob(len)?.to_vec())
+                }
+                Some(len) => string(self.blob(len)?),
+                None => RedisValue::Null,
+            },
+            b'*' | b'>' => match self.length()? {
+                Some(len) => {
+                    return Ok(Parsed::Container(Frame::Array {
+                        remaining: len,
+                        values: Vec::with_capacity(self.capacity(len)),
+                    }))
+                }
+                None => RedisValue::Null,
+            },
+            b'%' => {
+                let len = self.count()?;
+                return Ok(Parsed::Container(Frame::Map {
+                    remaining: len,
+                    values: Vec::with_capacity(self.capacity(len)),
+                    key: None,
+                }));
+            }
+            b'~' => {
+                let len = self.count()?;
+                return Ok(Parsed::Container(Frame::Set {
+                    remaining: len,
+                    values: HashSet::with_capacity(self.capacity(len)),
+                }));
+            }
+            b'|' => {
+                let remaining = self.count()?.checked_mul(2).ok_or_else(invalid)?;
+                return Ok(Parsed::Container(Frame::Attribute { remaining }));
+            }
+            b'_' => {
+                if !self.line()?.is_empty() {
+                    return Err(invalid());
+                }
+                RedisValue::Null
+            }
+            b'#' => match self.line()? {
+                b"t" => RedisValue::Bool(true),
+                b"f" => RedisValue::Bool(false),
+                _ => return Err(invalid()),
+            },
+            b',' => RedisValue::Float(self.text()?.parse().map_err(|_| invalid())?),
+            b'(' => {
+                let number = self.text()?;
+                let digits = number.strip_prefix('-').unwrap_or(number);
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid());
+                }
+                RedisValue::BigNumber(number.to_owned())
+            }
+            b'!' => {
+                let len = self.count()?;
+                RedisValue::Error(String::from_utf8_lossy(self.blob(len)?).into_owned())
+            }
+            b'=' => {
+                let len = self.count()?;
+                let blob = self.blob(len)?;
+                if blob.len() < 4 || blob[3] != b':' {
+                    return Err(invalid());
+                }
+                let format = std::str::from_utf8(&blob[..3]).map_err(|_| invalid())?;
+                let format = VerbatimStringFormat::try_from(format).map_err(|_| invalid())?;
+                RedisValue::VerbatimString((format, blob[4..].to_vec()))
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(Parsed::Value(value))
+    }
+
+    /// The rest of the line, without the `\r\n` terminator.
+    fn line(&mut self) -> Result<&'a [u8], RedisError> {
+        let rest = &self.input[self.pos..];
+        let len = rest
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(incomplete)?;
+        self.pos += len + 2;
+        Ok(&rest[..len])
+    }
+
+    fn text(&mut self) -> Result<&'a str, RedisError> {
+        std::str::from_utf8(self.line()?).map_err(|_| invalid())
+    }
+
+    fn integer(&mut self) -> Result<i64, RedisError> {
+        self.text()?.parse().map_err(|_| invalid())
+    }
+
+    /// A length, or `None` for the RESP2 null length `-1`.
+    fn length(&mut self) -> Result<Option<usize>, RedisError> {
+        match self.integer()? {
+            -1 => Ok(None),
+            len => usize::try_from(len).map(Some).map_err(|_| invalid()),
+        }
+    }
+
+    /// A length that can not be null.
+    fn count(&mut self) -> Result<usize, RedisError> {
+        self.length()?.ok_or_else(invalid)
+    }
+
+    /// `len` bytes followed by `\r\n`.
+    fn blob(&mut self, len: usize) -> Result<&'a [u8], RedisError> {
+        let end = self.pos.checked_add(len).ok_or_else(invalid)?;
+        let blob = self.input.get(self.pos..end).ok_or_else(incomplete)?;
+        match self.input.get(end..end + 2) {
+            Some(b"\r\n") => {}
+            Some(_) => return Err(invalid()),
+            None => return Err(incomplete()),
+        }
+        self.pos = end + 2;
+        Ok(blob)
+    }
+
+    /// The capacity to reserve for `len` elements. Each element takes at least
+    /// 3 bytes, so a bogus length can not exhaust the memory.
+    fn capacity(&self, len: usize) -> usize {
+        len.min((self.input.len() - self.pos) / 3)
+    }
+}
+
+fn string(bytes: &[u8]) -> RedisValue {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => RedisValue::SimpleString(s.to_owned()),
+        Err(_) => RedisValue::StringBuffer(bytes.to_vec()),
+    }
+}
+
+fn incomplete() -> RedisError {
+    RedisError::Str("ERR incomplete RESP value")
+}
+
+fn invalid() -> RedisError {
+    RedisError::Str("ERR invalid RESP value")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::redisvalue::{RedisValue, RedisValueKey};
+
+    fn parse(bytes: &[u8]) -> RedisValue {
+        RedisValue::from_resp_bytes(bytes).unwrap()
+    }
+
+    #[test]
+    fn resp2() {
+        assert_eq!(parse(b"+OK\r\n"), RedisValue::SimpleString("OK".into()));
+        assert_eq!(parse(b":-42\r\n"), RedisValue::Integer(-42));
+        assert_eq!(
+            parse(b"$5\r\na\r\nbc\r\n"),
+            RedisValue::SimpleString("a\r\nbc".into())
+        );
+        assert_eq!(
+            parse(b"$2\r\n\xff\xfe\r\n"),
+            RedisValue::StringBuffer(vec![0xff, 0xfe])
+        );
+        assert_eq!(parse(b"$-1\r\n"), RedisValue::Null);
+        assert_eq!(parse(b"*-1\r\n"), RedisValue::Null);
+        assert_eq!(
+            parse(b"-ERR boom\r\n"),
+            RedisValue::Error("ERR boom".into())
+        );
+        assert_eq!(
+            parse(b"*3\r\n:1\r\n*0\r\n*1\r\n-ERR nested\r\n"),
+            RedisValue::Array(vec![
+                RedisValue::Integer(1),
+                RedisValue::Array(vec![]),
+                RedisValue::Array(vec![RedisValue::Error("ERR nested".into())]),
+            ])
+        );
+    }
+
+    #[test]
+    fn resp3() {
+        assert_eq!(parse(b"_\r\n"), RedisValue::Null);
+        assert_eq!(parse(b"#t\r\n"), RedisValue::Bool(true));
+        assert_eq!(parse(b",1.5\r\n"), RedisValue::Float(1.5));
+        assert_eq!(parse(b",-inf\r\n"), RedisValue::Float(f64::NEG_INFINITY));
+        assert_eq!(parse(b"(-123\r\n"), RedisValue::BigNumber("-123".into()));
+        assert_eq!(parse(b"!5\r\nERR x\r\n"), RedisValue::Error("ERR x".into()));
+        match parse(b"=7\r\ntxt:abc\r\n") {
+            RedisValue::VerbatimString((_, text)) => assert_eq!(text, b"abc"),
+            value => panic!("unexpected value {value:?}"),
+        }
+        assert_eq!(
+            parse(b"%2\r\n+b\r\n:1\r\n:2\r\n%0\r\n"),
+            RedisValue::MapEntries(vec![
+                (RedisValueKey::String("b".into()), RedisValue::Integer(1)),
+                (RedisValueKey::Integer(2), RedisValue::MapEntries(vec![])),
+            ])
+        );
+        assert_eq!(
+            parse(b"~2\r\n+a\r\n+a\r\n"),
+            RedisValue::Set([RedisValueKey::String("a".into())].into())
+        );
+        assert_eq!(
+            parse(b"|1\r\n+key\r\n+value\r\n*1\r\n|0\r\n:1\r\n"),
+            RedisValue::Array(vec![RedisValue::Integer(1)])
+        );
+        assert_eq!(
+            parse(b">2\r\n+message\r\n+hi\r\n"),
+            RedisValue::Array(vec![
+                RedisValue::SimpleString("message".into()),
+                RedisValue::SimpleString("hi".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        for bytes in [
+            &b""[..],
+            b"+OK",
+            b"+OK\r\n+OK\r\n",
+            b"?\r\n",
+            b":1x\r\n",
+            b"$3\r\nab\r\n",
+            b"$2\r\nabc\r\n",
+            b"$-2\r\n",
+            b"$99999999999999999999\r\n",
+            b"*2\r\n:1\r\n",
+            b"*9223372036854775807\r\n",
+            b"%1\r\n*0\r\n:1\r\n",
+            b"%-1\r\n",
+            b"|9223372036854775807\r\n",
+            b"#x\r\n",
+            b",x\r\n",
+            b"(1.5\r\n",
+            b"=3\r\ntxt\r\n",
+            b"_x\r\n",
+        ] {
+            assert!(
+                RedisValue::from_resp_bytes(bytes).is_err(),
+                "{:?}",
+                String::from_utf8_lossy(bytes)
+            );
+        }
+    }
+
+    #[test]
+    fn deeply_nested() {
+        let depth = 100_000;
+        let mut bytes = b"*1\r\n".repeat(depth);
+        bytes.extend_from_slice(b":1\r\n");
+        let value = parse(&bytes);
+        assert_eq!(value.depth(), depth);
+        drop_nested(value);
+    }
+
+    #[test]
+    fn deeply_nested_malformed() {
+        let depth = 200_000;
+        // Garbage in place of the innermost value.
+        let mut bytes = b"*1\r\n".repeat(depth);
+        bytes.extend_from_slice(b"?\r\n");
+        assert!(RedisValue::from_resp_bytes(&bytes).is_err());
+
+        // A complete deep value followed by trailing bytes.
+        let mut bytes = b"*1\r\n".repeat(depth);
+        bytes.extend_from_slice(b":1\r\n:2\r\n");
+        assert!(RedisValue::from_resp_bytes(&bytes).is_err());
+
+        // An error after a deep element of an enclosing container.
+        let mut bytes = b"*2\r\n".to_vec();
+        bytes.extend_from_slice(&b"*1\r\n".repeat(depth));
+        bytes.extend_from_slice(b":1\r\n?\r\n");
+        assert!(RedisValue::from_resp_bytes(&bytes).is_err());
+
+        // A deep attribute element is dropped, not returned.
+        let mut bytes = b"|1\r\n+key\r\n".to_vec();
+        bytes.extend_from_slice(&b"*1\r\n".repeat(depth));
+        bytes.extend_from_slice(b":1\r\n:2\r\n");
+        assert_eq!(
+            RedisValue::from_resp_bytes(&bytes).unwrap(),
+            RedisValue::Integer(2)
+        );
+
+        // A deep value used as a map key.
+        let mut bytes = b"%1\r\n".to_vec();
+        bytes.extend_from_slice(&b"*1\r\n".repeat(depth));
+        bytes.extend_from_slice(b":1\r\n:2\r\n");
+        assert!(RedisValue::from_resp_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,838 @@
+//! Conversions between [RedisValue] and types implementing the `serde` traits.
+//!
+//! Structs and maps are converted to [RedisValue::MapEntries], keeping the
+//! order of the fields, sequences and tuples are converted to [RedisValue::Array].
+//! When replying to a RESP2 client Redis sends maps as flat arrays of
+//! `key, value` pairs.
+//!
+//! In the other direction any map, or an array of `key, value` pairs, can be
+//! deserialized into a struct, and strings are parsed when a number is expected,
+//! so the same type can be read from RESP2 and RESP3 replies.
+
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor};
+use serde::ser::{self, Serialize};
+
+use crate::redisvalue::{RedisValue, RedisValueKey};
+
+/// The error returned when a value can not be converted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    msg: String,
+    path: String,
+}
+
+impl Error {
+    /// The path of the field that failed to deserialize, e.g. `groups[1].name`.
+    /// Empty when the error is about the top level value.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn at(mut self, segment: String) -> Self {
+        if !self.path.is_empty() && !self.path.starts_with('[') {
+            self.path.insert(0, '.');
+        }
+        self.path.insert_str(0, &segment);
+        self
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.msg)
+        } else {
+            write!(f, "{} at `{}`", self.msg, self.path)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self {
+            msg: msg.to_string(),
+            path: String::new(),
+        }
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        <Self as ser::Error>::custom(msg)
+    }
+}
+
+/// Convert any serializable value into a [RedisValue].
+pub fn to_redis_value<T: Serialize + ?Sized>(value: &T) -> Result<RedisValue, Error> {
+    value.serialize(Serializer)
+}
+
+/// Convert a [RedisValue] into any deserializable type, see [RedisValue::deserialize].
+pub fn from_redis_value<T: DeserializeOwned>(value: &RedisValue) -> Result<T, Error> {
+    T::deserialize(Deserializer(value))
+}
+
+fn to_redis_value_key(value: RedisValue) -> Result<RedisValueKey, Error> {
+    match value {
+        RedisValue::BulkString(s) => Ok(RedisValueKey::String(s)),
+        RedisValue::StringBuffer(b) => Ok(RedisValueKey::BulkString(b)),
+        RedisValue::Integer(i) => Ok(RedisValueKey::Integer(i)),
+        RedisValue::Bool(b) => Ok(RedisValueKey::Bool(b)),
+        RedisValue::Float(f) => Ok(RedisValueKey::from(f)),
+        other => Err(ser::Error::custom(format!(
+            "{other:?} can not be used as a map key"
+        ))),
+    }
+}
+
+fn variant_entry(variant: &'static str, value: RedisValue) -> RedisValue {
+    RedisValue::MapEntries(vec![(RedisValueKey::String(variant.to_owned()), value)])
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = RedisValue;
+    type Error = Error;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeVec;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    fn serialize_bool(self, v: bool) -> Result<RedisValue, Error> {
+        Ok(RedisValue::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RedisValue, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RedisValue, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RedisValue, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RedisValue, Error> {
+        Ok(RedisValue::Integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RedisValue, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RedisValue, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RedisValue, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RedisValue, Error> {
+        // Values that do not fit a Redis integer are sent as a big number.
+        Ok(i64::try_from(v).map_or_else(
+            |_| RedisValue::BigNumber(v.to_string()),
+            RedisValue::Integer,
+        ))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RedisValue, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RedisValue, Error> {
+        Ok(RedisValue::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<RedisValue, Error> {
+        Ok(RedisValue::BulkString(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RedisValue, Error> {
+        Ok(RedisValue::BulkString(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RedisValue, Error> {
+        Ok(RedisValue::StringBuffer(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<RedisValue, Error> {
+        Ok(RedisValue::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<RedisValue, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<RedisValue, Error> {
+        Ok(RedisValue::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<RedisValue, Error> {
+        Ok(RedisValue::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<RedisValue, Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<RedisValue, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<RedisValue, Error> {
+        Ok(variant_entry(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec, Error> {
+        Ok(SerializeVec {
+            variant: None,
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec, Error> {
+        Ok(SerializeVec {
+            variant: Some(variant),
+            values: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap, Error> {
+        Ok(SerializeMap {
+            variant: None,
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap, Error> {
+        Ok(SerializeMap {
+            variant: Some(variant),
+            entries: Vec::with_capacity(len),
+            next_key: None,
+        })
+    }
+}
+
+struct SerializeVec {
+    variant: Option<&'static str>,
+    values: Vec<RedisValue>,
+}
+
+impl SerializeVec {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.values.push(to_redis_value(value)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<RedisValue, Error> {
+        let array = RedisValue::Array(self.values);
+        Ok(match self.variant {
+            Some(variant) => variant_entry(variant, array),
+            None => array,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = RedisValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RedisValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = RedisValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RedisValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = RedisValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RedisValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVec {
+    type Ok = RedisValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RedisValue, Error> {
+        self.finish()
+    }
+}
+
+struct SerializeMap {
+    variant: Option<&'static str>,
+    entries: Vec<(RedisValueKey, RedisValue)>,
+    next_key: Option<RedisValueKey>,
+}
+
+impl SerializeMap {
+    fn finish(self) -> Result<RedisValue, Error> {
+        let map = RedisValue::MapEntries(self.entries);
+        Ok(match self.variant {
+            Some(variant) => variant_entry(variant, map),
+            None => map,
+        })
+    }
+
+    fn push_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.entries.push((
+            RedisValueKey::String(key.to_owned()),
+            to_redis_value(value)?,
+        ));
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = RedisValue;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.next_key = Some(to_redis_value_key(to_redis_value(key)?)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| ser::Error::custom("map value serialized before its key"))?;
+        self.entries.push((key, to_redis_value(value)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<RedisValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = RedisValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.push_field(key, value)
+    }
+
+    fn end(self) -> Result<RedisValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = RedisValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.push_field(key, value)
+    }
+
+    fn end(self) -> Result<RedisValue, Error> {
+        self.finish()
+    }
+}
+
+fn key_to_value(key: &RedisValueKey) -> RedisValue {
+    match key {
+        RedisValueKey::Integer(i) => RedisValue::Integer(*i),
+        RedisValueKey::String(s) => RedisValue::BulkString(s.clone()),
+        RedisValueKey::BulkRedisString(s) => RedisValue::StringBuffer(s.as_slice().to_vec()),
+        RedisValueKey::BulkString(b) => RedisValue::StringBuffer(b.clone()),
+        RedisValueKey::Bool(b) => RedisValue::Bool(*b),
+        RedisValueKey::Double(d) => RedisValue::Float(d.0),
+    }
+}
+
+fn value_as_bytes(value: &RedisValue) -> Option<&[u8]> {
+    match value {
+        RedisValue::SimpleStringStatic(s) => Some(s.as_bytes()),
+        RedisValue::SimpleString(s) | RedisValue::BulkString(s) | RedisValue::BigNumber(s) => {
+            Some(s.as_bytes())
+        }
+        RedisValue::BulkRedisString(s) => Some(s.as_slice()),
+        RedisValue::StringBuffer(b) | RedisValue::VerbatimString((_, b)) => Some(b),
+        _ => None,
+    }
+}
+
+fn value_as_str(value: &RedisValue) -> Option<&str> {
+    value_as_bytes(value).and_then(|b| std::str::from_utf8(b).ok())
+}
+
+fn path_segment(key: &RedisValue) -> String {
+    match value_as_bytes(key) {
+        Some(b) => String::from_utf8_lossy(b).into_owned(),
+        None => format!("{key:?}"),
+    }
+}
+
+/// Deserializes a borrowed [RedisValue]. Only owned data is handed to the
+/// visitors, so the `'de` lifetime is not tied to the value.
+struct Deserializer<'a>(&'a RedisValue);
+
+impl<'a> Deserializer<'a> {
+    fn map_access(&self) -> Option<MapAccess<'a>> {
+        let entries: Vec<(RedisValue, &RedisValue)> = match self.0 {
+            RedisValue::Map(map) => map.iter().map(|(k, v)| (key_to_value(k), v)).collect(),
+            RedisValue::OrderedMap(map) => map.iter().map(|(k, v)| (key_to_value(k), v)).collect(),
+            RedisValue::MapEntries(entries) => {
+                entries.iter().map(|(k, v)| (key_to_value(k), v)).collect()
+            }
+            // RESP2 replies send maps as a flat array of `key, value` pairs.
+            RedisValue::Array(array) if array.len() % 2 == 0 => array
+                .chunks(2)
+                .map(|pair| (pair[0].clone(), &pair[1]))
+                .collect(),
+            _ => return None,
+        };
+        Some(MapAccess {
+            entries: entries.into_iter(),
+            value: None,
+        })
+    }
+
+    fn seq_access(&self) -> Option<SeqAccess<'a>> {
+        let items: Vec<Cow<RedisValue>> = match self.0 {
+            RedisValue::Array(array) => array.iter().map(Cow::Borrowed).collect(),
+            RedisValue::Set(set) => set.iter().map(|k| Cow::Owned(key_to_value(k))).collect(),
+            RedisValue::OrderedSet(set) => {
+                set.iter().map(|k| Cow::Owned(key_to_value(k))).collect()
+            }
+            _ => return None,
+        };
+        Some(SeqAccess {
+            items: items.into_iter().enumerate(),
+        })
+    }
+}
+
+macro_rules! deserialize_signed {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match value_as_str(self.0).and_then(|s| s.parse::<i64>().ok()) {
+                Some(i) => visitor.visit_i64(i),
+                None => self.deserialize_any(visitor),
+            }
+        }
+    )*};
+}
+
+macro_rules! deserialize_unsigned {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match value_as_str(self.0).and_then(|s| s.parse::<u64>().ok()) {
+                Some(u) => visitor.visit_u64(u),
+                None => self.deserialize_any(visitor),
+            }
+        }
+    )*};
+}
+
+macro_rules! deserialize_float {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match value_as_str(self.0).and_then(|s| s.parse::<f64>().ok()) {
+                Some(f) => visitor.visit_f64(f),
+                None => self.deserialize_any(visitor),
+            }
+        }
+    )*};
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if let Some(bytes) = value_as_bytes(self.0) {
+            return match std::str::from_utf8(bytes) {
+                Ok(s) => visitor.visit_str(s),
+                Err(_) => visitor.visit_bytes(bytes),
+            };
+        }
+        match self.0 {
+            RedisValue::Integer(i) => visitor.visit_i64(*i),
+            RedisValue::Bool(b) => visitor.visit_bool(*b),
+            RedisValue::Float(f) => visitor.visit_f64(*f),
+            RedisValue::Null | RedisValue::NoReply => visitor.visit_unit(),
+            RedisValue::StaticError(e) => Err(de::Error::custom(format!("error reply: {e}"))),
+            RedisValue::Error(e) => Err(de::Error::custom(format!("error reply: {e}"))),
+            other => {
+                if let Some(access) = self.seq_access() {
+                    visitor.visit_seq(access)
+                } else if let Some(access) = self.map_access() {
+                    visitor.visit_map(access)
+                } else {
+                    Err(de::Error::custom(format!("unsupported value {other:?}")))
+                }
+            }
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match (self.0, value_as_str(self.0)) {
+            (RedisValue::Integer(0), _) | (_, Some("0" | "false")) => visitor.visit_bool(false),
+            (RedisValue::Integer(1), _) | (_, Some("1" | "true")) => visitor.visit_bool(true),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    deserialize_signed!(deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64);
+    deserialize_unsigned!(deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64);
+    deserialize_float!(deserialize_f32 deserialize_f64);
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            RedisValue::Integer(i) => visitor.visit_string(i.to_string()),
+            RedisValue::Float(f) => visitor.visit_string(f.to_string()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match value_as_bytes(self.0) {
+            Some(bytes) => visitor.visit_bytes(bytes),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            RedisValue::Null | RedisValue::NoReply => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.map_access() {
+            Some(access) => visitor.visit_map(access),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        if value_as_bytes(self.0).is_some() {
+            return visitor.visit_enum(EnumAccess {
+                variant: self.0.clone(),
+                value: None,
+            });
+        }
+        // A variant with data is a single entry map, see `variant_entry`.
+        let mut entries = match self.map_access() {
+            Some(access) => access.entries,
+            None => return self.deserialize_any(visitor),
+        };
+        match (entries.next(), entries.next()) {
+            (Some((variant, value)), None) => visitor.visit_enum(EnumAccess {
+                variant,
+                value: Some(value),
+            }),
+            _ => Err(de::Error::invalid_value(
+                de::Unexpected::Map,
+                &"a map with a single entry",
+            )),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+struct SeqAccess<'a> {
+    items: std::iter::Enumerate<std::vec::IntoIter<Cow<'a, RedisValue>>>,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for SeqAccess<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.items.next() {
+            Some((index, item)) => seed
+                .deserialize(Deserializer(&item))
+                .map(Some)
+                .map_err(|e| e.at(format!("[{index}]"))),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct MapAccess<'a> {
+    entries: std::vec::IntoIter<(RedisValue, &'a RedisValue)>,
+    value: Option<(String, &'a RedisValue)>,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapAccess<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                let segment = path_segment(&key);
+                let key = seed
+                    .deserialize(Deserializer(&key))
+                    .map_err(|e| e.at(segment.clone()))?;
+                self.value = Some((segment, value));
+                Ok(Some(key))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (segment, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("map value requested before its key"))?;
+        seed.deserialize(Deserializer(value))
+            .map_err(|e| e.at(segment))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct EnumAccess<'a> {
+    variant: RedisValue,
+    value: Option<&'a RedisValue>,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumAccess<'a> {
+    type Error = Error;
+    type Variant = VariantAccess<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantAccess<'a>), Error> {
+        let variant = seed.deserialize(Deserializer(&self.variant))?;
+        Ok((
+            variant,
+            VariantAccess {
+                segment: path_segment(&self.variant),
+                value: self.value,
+            },
+        ))
+    }
+}
+
+struct VariantAccess<'a> {
+    segment: String,
+    value: Option<&'a RedisValue>,
+}
+
+impl<'a> VariantAccess<'a> {
+    fn value(&self) -> Result<Deserializer<'a>, Error> {
+        self.value.map(Deserializer).ok_or_else(|| {
+            de::Error::invalid_type(de::Unexpected::UnitVariant, &"a variant with data")
+        })
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for VariantAccess<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.value {
+            None | Some(RedisValue::Null) => Ok(()),
+            Some(_) => Err(de::Error::invalid_type(
+                de::Unexpected::NewtypeVariant,
+                &"a unit variant",
+            )),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.value()?)
+            .map_err(|e| e.at(self.segment))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.value()?, visitor).map_err(|e| e.at(self.segment))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.value()?, visitor).map_err(|e| e.at(self.segment))
+    }
+}
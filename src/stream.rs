@@ -3,8 +3,133 @@ use crate::raw;
 use crate::RedisError;
 use crate::RedisString;
 use crate::Status;
+use std::fmt;
 use std::os::raw::c_long;
 use std::ptr;
+use std::str::FromStr;
+
+/// The ID of a stream entry, made of a milliseconds time and a sequence number
+/// and written `<ms>-<seq>`. IDs are ordered like the entries of a stream.
+///
+/// [StreamId::MIN] and [StreamId::MAX] are the `-` and `+` special IDs used by
+/// `XRANGE` for the smallest and greatest possible IDs. IDs can be parsed from
+/// their textual form, including `-`, `+` and `<ms>` alone, which is parsed with
+/// a sequence number of 0 as the start of an `XRANGE`. Use
+/// `StreamId::new(ms, u64::MAX)` for the last ID of a time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    /// The smallest possible ID, `-` in `XRANGE`.
+    pub const MIN: Self = Self { ms: 0, seq: 0 };
+    /// The greatest possible ID, `+` in `XRANGE`.
+    pub const MAX: Self = Self {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    #[must_use]
+    pub const fn new(ms: u64, seq: u64) -> Self {
+        Self { ms, seq }
+    }
+
+    /// The first ID of the given milliseconds time, `<ms>-0`.
+    #[must_use]
+    pub const fn from_ms(ms: u64) -> Self {
+        Self { ms, seq: 0 }
+    }
+}
+
+impl From<raw::RedisModuleStreamID> for StreamId {
+    fn from(id: raw::RedisModuleStreamID) -> Self {
+        Self {
+            ms: id.ms,
+            seq: id.seq,
+        }
+    }
+}
+
+impl From<StreamId> for raw::RedisModuleStreamID {
+    fn from(id: StreamId) -> Self {
+        Self {
+            ms: id.ms,
+            seq: id.seq,
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+impl FromStr for StreamId {
+    type Err = RedisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || RedisError::Str("ERR Invalid stream ID specified as stream command argument");
+        match s {
+            "-" => return Ok(Self::MIN),
+            "+" => return Ok(Self::MAX),
+            _ => {}
+        }
+        let (ms, seq) = match s.split_once('-') {
+            Some((ms, seq)) => (ms, Some(seq)),
+            None => (s, None),
+        };
+        let ms = ms.parse().map_err(|_| invalid())?;
+        let seq = seq.map_or(Ok(0), str::parse).map_err(|_| invalid())?;
+        Ok(Self { ms, seq })
+    }
+}
+
+/// The ID of an entry added with [crate::key::RedisKeyWritable::stream_add].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamAddId {
+    /// Let Redis assign the ID, like `*` in `XADD`.
+    Auto,
+    /// The given ID, which must be greater than the last ID of the stream.
+    Explicit(StreamId),
+}
+
+impl From<StreamId> for StreamAddId {
+    fn from(id: StreamId) -> Self {
+        Self::Explicit(id)
+    }
+}
+
+/// The options of [crate::key::RedisKeyWritable::stream_add_with_options],
+/// named after the `XADD` options they behave like.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StreamAddOptions {
+    /// Do not create the stream when the key does not exist, `NOMKSTREAM`.
+    pub no_mkstream: bool,
+    /// Trim the stream to this number of entries after adding, `MAXLEN`.
+    pub max_len: Option<usize>,
+    /// Trim approximately, `MAXLEN ~`, which is more efficient since only whole
+    /// nodes of the stream are removed.
+    pub approx: bool,
+}
+
+impl StreamAddOptions {
+    #[must_use]
+    pub fn no_mkstream(mut self) -> Self {
+        self.no_mkstream = true;
+        self
+    }
+
+    #[must_use]
+    pub fn max_len(mut self, max_len: usize, approx: bool) -> Self {
+        self.max_len = Some(max_len);
+        self.approx = approx;
+        self
+    }
+}
 
 #[derive(Debug)]
 pub struct StreamRecord {
@@ -98,3 +223,66 @@ impl<'key> Drop for StreamIterator<'key> {
         unsafe { raw::RedisModule_StreamIteratorDelete.unwrap()(self.key.key_inner) };
     }
 }
+
+/// An iterator over the entries of a stream between two IDs, created by
+/// [RedisKey::stream_iter]. Yields the ID and the fields of each entry, in
+/// increasing order of IDs. The Redis iterator is released when dropped.
+#[derive(Debug)]
+pub struct StreamIter<'key> {
+    // `None` when the key does not exist.
+    inner: Option<StreamIterator<'key>>,
+}
+
+impl<'key> StreamIter<'key> {
+    pub(crate) fn new(
+        key: &'key RedisKey,
+        start: StreamId,
+        end: StreamId,
+    ) -> Result<Self, RedisError> {
+        let inner = match key.key_type() {
+            raw::KeyType::Empty => None,
+            raw::KeyType::Stream => Some(StreamIterator::new(
+                key,
+                Some(start.into()),
+                Some(end.into()),
+                false,
+                false,
+            )?),
+            _ => return Err(RedisError::WrongType),
+        };
+        Ok(Self { inner })
+    }
+}
+
+impl<'key> Iterator for StreamIter<'key> {
+    type Item = (StreamId, Vec<(RedisString, RedisString)>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.inner.as_mut()?.next()?;
+        Some((record.id.into(), record.fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_id_from_str() {
+        assert_eq!("-".parse::<StreamId>().unwrap(), StreamId::MIN);
+        assert_eq!("+".parse::<StreamId>().unwrap(), StreamId::MAX);
+        assert_eq!("5".parse::<StreamId>().unwrap(), StreamId::from_ms(5));
+        assert_eq!("5-3".parse::<StreamId>().unwrap(), StreamId::new(5, 3));
+        for invalid in ["", "a", "5-", "-3", "5-3-1", "5-a"] {
+            assert!(invalid.parse::<StreamId>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn stream_id_display_and_order() {
+        assert_eq!(StreamId::new(5, 3).to_string(), "5-3");
+        assert!(StreamId::MIN < StreamId::new(1, 0));
+        assert!(StreamId::new(1, 2) < StreamId::new(2, 1));
+        assert!(StreamId::new(2, 1) < StreamId::MAX);
+    }
+}
@@ -0,0 +1,142 @@
+//! Stand-ins for the `RedisModuleString` APIs, used by
+//! [crate::RedisString::from_bytes_owned] when the code does not run inside
+//! Redis, for example in unit tests. A string is a reference counted byte
+//! buffer, which supports the operations [crate::RedisString] needs to be
+//! created, appended to, cloned, read, compared, parsed and dropped.
+
+use std::cmp::Ordering;
+use std::os::raw::{c_char, c_int, c_longlong};
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Once;
+
+use crate::raw;
+
+struct TestString {
+    refcount: AtomicUsize,
+    bytes: Vec<u8>,
+}
+
+fn test_string<'a>(s: *const raw::RedisModuleString) -> &'a TestString {
+    unsafe { &*s.cast::<TestString>() }
+}
+
+fn new_test_string(bytes: Vec<u8>) -> *mut raw::RedisModuleString {
+    let s = TestString {
+        refcount: AtomicUsize::new(1),
+        bytes,
+    };
+    Box::into_raw(Box::new(s)).cast()
+}
+
+extern "C" fn create_string(
+    _ctx: *mut raw::RedisModuleCtx,
+    ptr: *const c_char,
+    len: usize,
+) -> *mut raw::RedisModuleString {
+    let bytes = if len == 0 {
+        Vec::new()
+    } else {
+        unsafe { slice::from_raw_parts(ptr.cast::<u8>(), len) }.to_vec()
+    };
+    new_test_string(bytes)
+}
+
+extern "C" fn create_string_from_string(
+    _ctx: *mut raw::RedisModuleCtx,
+    s: *const raw::RedisModuleString,
+) -> *mut raw::RedisModuleString {
+    new_test_string(test_string(s).bytes.clone())
+}
+
+extern "C" fn string_append_buffer(
+    _ctx: *mut raw::RedisModuleCtx,
+    s: *mut raw::RedisModuleString,
+    buf: *const c_char,
+    len: usize,
+) -> c_int {
+    if len > 0 {
+        let s = unsafe { &mut *s.cast::<TestString>() };
+        s.bytes
+            .extend_from_slice(unsafe { slice::from_raw_parts(buf.cast::<u8>(), len) });
+    }
+    raw::REDISMODULE_OK as c_int
+}
+
+extern "C" fn retain_string(_ctx: *mut raw::RedisModuleCtx, s: *mut raw::RedisModuleString) {
+    test_string(s)
+        .refcount
+        .fetch_add(1, AtomicOrdering::Relaxed);
+}
+
+extern "C" fn free_string(_ctx: *mut raw::RedisModuleCtx, s: *mut raw::RedisModuleString) {
+    if test_string(s).refcount.fetch_sub(1, AtomicOrdering::AcqRel) == 1 {
+        drop(unsafe { Box::from_raw(s.cast::<TestString>()) });
+    }
+}
+
+extern "C" fn string_ptr_len(s: *const raw::RedisModuleString, len: *mut usize) -> *const c_char {
+    let bytes = &test_string(s).bytes;
+    if !len.is_null() {
+        unsafe { *len = bytes.len() };
+    }
+    bytes.as_ptr().cast()
+}
+
+extern "C" fn string_compare(
+    a: *const raw::RedisModuleString,
+    b: *const raw::RedisModuleString,
+) -> c_int {
+    match test_string(a).bytes.cmp(&test_string(b).bytes) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+fn parse<T: std::str::FromStr>(s: *const raw::RedisModuleString, out: *mut T) -> c_int {
+    let parsed = std::str::from_utf8(&test_string(s).bytes)
+        .ok()
+        .and_then(|s| s.parse().ok());
+    match parsed {
+        Some(v) => {
+            unsafe { *out = v };
+            raw::REDISMODULE_OK as c_int
+        }
+        None => raw::REDISMODULE_ERR as c_int,
+    }
+}
+
+extern "C" fn string_to_long_long(s: *const raw::RedisModuleString, out: *mut c_longlong) -> c_int {
+    parse(s, out)
+}
+
+extern "C" fn string_to_double(s: *const raw::RedisModuleString, out: *mut f64) -> c_int {
+    let mut val: f64 = 0.0;
+    if parse(s, &mut val) == raw::REDISMODULE_ERR as c_int || val.is_nan() {
+        return raw::REDISMODULE_ERR as c_int;
+    }
+    unsafe { *out = val };
+    raw::REDISMODULE_OK as c_int
+}
+
+/// Install the stand-ins, unless the module was loaded by Redis and the real
+/// APIs are available.
+pub(crate) fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| unsafe {
+        let loaded_by_redis = raw::RedisModule_CreateString;
+        if loaded_by_redis.is_some() {
+            return;
+        }
+        raw::RedisModule_CreateString = Some(create_string);
+        raw::RedisModule_CreateStringFromString = Some(create_string_from_string);
+        raw::RedisModule_StringAppendBuffer = Some(string_append_buffer);
+        raw::RedisModule_RetainString = Some(retain_string);
+        raw::RedisModule_FreeString = Some(free_string);
+        raw::RedisModule_StringPtrLen = Some(string_ptr_len);
+        raw::RedisModule_StringCompare = Some(string_compare);
+        raw::RedisModule_StringToLongLong = Some(string_to_long_long);
+        raw::RedisModule_StringToDouble = Some(string_to_double);
+    });
+}
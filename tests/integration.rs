@@ -1,3 +1,5 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
 use std::thread;
 use std::time::Duration;
 
@@ -55,6 +57,54 @@ fn test_keys_pos() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_get_command_keys() -> Result<()> {
+    let port: u16 = 6530;
+    let _guards = vec![start_redis_server_with_module("keys_pos", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    for (command, expected) in [
+        (vec!["get", "a"], vec![1]),
+        (vec!["mset", "a", "1", "b", "2"], vec![1, 3]),
+        (vec!["zadd", "z", "1", "m"], vec![1]),
+        (vec!["sort", "s", "by", "w_*", "store", "dst"], vec![1, 5]),
+        (
+            vec!["georadius", "g", "0", "0", "1", "km", "store", "dst"],
+            vec![1, 7],
+        ),
+        (vec!["keys_pos", "a", "1", "b", "2"], vec![1, 3]),
+        (vec!["ping"], vec![]),
+    ] {
+        let res: Vec<i64> = redis::cmd("keys_pos.of")
+            .arg(&command)
+            .query(&mut con)
+            .with_context(|| "failed to run keys_pos.of")?;
+        assert_eq!(res, expected, "{command:?}");
+    }
+
+    // The source of SORT ... STORE is only read, the destination is written.
+    let res: Vec<i64> = redis::cmd("keys_pos.read_only_of")
+        .arg(&["sort", "s", "store", "dst"])
+        .query(&mut con)
+        .with_context(|| "failed to run keys_pos.read_only_of")?;
+    assert_eq!(res, vec![1]);
+
+    for (command, error) in [
+        (vec!["no_such_command", "a"], "unknown command"),
+        (vec!["get", "a", "b"], "wrong number of arguments"),
+    ] {
+        let err = redis::cmd("keys_pos.of")
+            .arg(&command)
+            .query::<Vec<i64>>(&mut con)
+            .unwrap_err();
+        assert!(err.to_string().contains(error), "{command:?}: {err}");
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_helper_version() -> Result<()> {
     let port: u16 = 6481;
@@ -74,6 +124,13 @@ fn test_helper_version() -> Result<()> {
         .with_context(|| "failed to run test_helper._version_rm_call")?;
     assert_eq!(res, res2);
 
+    for _ in 0..2 {
+        let res3: Vec<i64> = redis::cmd("test_helper._version_cached")
+            .query(&mut con)
+            .with_context(|| "failed to run test_helper._version_cached")?;
+        assert_eq!(res, res3);
+    }
+
     Ok(())
 }
 
@@ -190,6 +247,144 @@ fn test_test_helper_err() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_error_stats_code() -> Result<()> {
+    let port: u16 = 6508;
+    let _guards = vec![start_redis_server_with_module("test_helper", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: RedisResult<()> = redis::cmd("test_helper.err_code")
+        .arg(&["MYMOD", "something failed"])
+        .query(&mut con);
+    let err = res.unwrap_err();
+    assert_eq!(err.code(), Some("MYMOD"));
+    assert_eq!(err.detail(), Some("something failed"));
+
+    let res: String = redis::cmd("info").arg(&["errorstats"]).query(&mut con)?;
+    assert!(res.contains("errorstat_MYMOD:count=1"));
+
+    Ok(())
+}
+
+#[test]
+fn test_info_gauges() -> Result<()> {
+    let port: u16 = 6521;
+    let _guards = vec![start_redis_server_with_module("info", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: String = redis::cmd("info").arg(&["info_sessions"]).query(&mut con)?;
+    assert!(res.contains("active_sessions:0"));
+    assert!(res.contains("max_sessions:100"));
+
+    for _ in 0..2 {
+        let _: i64 = redis::cmd("info.session_open").query(&mut con)?;
+    }
+    let res: String = redis::cmd("info").arg(&["info_sessions"]).query(&mut con)?;
+    assert!(res.contains("active_sessions:2"));
+
+    Ok(())
+}
+
+#[test]
+fn test_create_string_from_numbers() -> Result<()> {
+    let port: u16 = 6512;
+    let _guards = vec![start_redis_server_with_module("test_helper", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: Vec<String> = redis::cmd("test_helper.numbers").query(&mut con)?;
+    assert_eq!(res, vec!["-42", "1.5", "inf"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_latency_timer() -> Result<()> {
+    let port: u16 = 6532;
+    let _guards = vec![start_redis_server_with_module("test_helper", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: Vec<i64> = redis::cmd("test_helper.latency")
+        .arg(20)
+        .query(&mut con)
+        .with_context(|| "failed to run test_helper.latency")?;
+    let (timer, micros) = (res[0], res[1]);
+    assert!(timer >= 20_000, "{timer}");
+    assert!(micros >= timer, "{micros} < {timer}");
+    assert!(micros < 1_000_000, "{micros}");
+
+    Ok(())
+}
+
+#[test]
+fn test_module_identity() -> Result<()> {
+    let port: u16 = 6540;
+    let _guards = vec![start_redis_server_with_module("test_helper", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: (String, i64) = redis::cmd("test_helper.module_identity")
+        .query(&mut con)
+        .with_context(|| "failed to run test_helper.module_identity")?;
+    assert_eq!(res, ("test_helper".to_owned(), 1));
+
+    Ok(())
+}
+
+#[test]
+fn test_bulk_reply_in_chunks() -> Result<()> {
+    let port: u16 = 6514;
+    let _guards = vec![start_redis_server_with_module("test_helper", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: Vec<u8> = redis::cmd("test_helper.bulk_chunks")
+        .arg(&[64, 1024 * 1024])
+        .query(&mut con)?;
+    assert_eq!(res.len(), 64 * 1024 * 1024);
+    assert!(res
+        .chunks(1024 * 1024)
+        .all(|c| c[..26] == *b"abcdefghijklmnopqrstuvwxyz"));
+
+    let res: String = redis::cmd("test_helper.bulk_chunks")
+        .arg(&[0, 10])
+        .query(&mut con)?;
+    assert_eq!(res, "");
+
+    // Fewer bytes than declared, then a chunk past the declared length.
+    for args in [[3, 10, 40], [3, 10, 25]] {
+        let err = redis::cmd("test_helper.bulk_chunks")
+            .arg(&args)
+            .query::<Vec<u8>>(&mut con)
+            .unwrap_err();
+        assert_eq!(err.detail(), Some("bulk reply aborted"), "{args:?}");
+    }
+
+    // An error returned by the command is the only reply.
+    let err = redis::cmd("test_helper.bulk_chunks")
+        .arg(&["3", "10", "40", "FAIL"])
+        .query::<Vec<u8>>(&mut con)
+        .unwrap_err();
+    assert_eq!(err.detail(), Some("failed after the bulk reply began"));
+
+    // The connection is still usable.
+    let res: Vec<u8> = redis::cmd("test_helper.bulk_chunks")
+        .arg(&[2, 3])
+        .query(&mut con)?;
+    assert_eq!(res, b"abcabc");
+
+    Ok(())
+}
+
 #[test]
 fn test_string() -> Result<()> {
     let port: u16 = 6485;
@@ -210,6 +405,325 @@ fn test_string() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_string_range() -> Result<()> {
+    let port: u16 = 6503;
+    let _guards = vec![start_redis_server_with_module("string", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    redis::cmd("set")
+        .arg(&["key", "This is a string"])
+        .query(&mut con)
+        .with_context(|| "failed to run set")?;
+
+    for (start, end, expected) in [
+        ("0", "3", "This"),
+        ("-3", "-1", "ing"),
+        ("0", "-1", "This is a string"),
+        ("10", "100", "string"),
+        ("-100", "3", "This"),
+        ("-1", "-3", ""),
+        ("5", "3", ""),
+    ] {
+        let res: String = redis::cmd("string.getrange")
+            .arg(&["key", start, end])
+            .query(&mut con)
+            .with_context(|| "failed to run string.getrange")?;
+        let expected_res: String = redis::cmd("getrange")
+            .arg(&["key", start, end])
+            .query(&mut con)?;
+        assert_eq!(&res, expected);
+        assert_eq!(res, expected_res);
+    }
+
+    let res: String = redis::cmd("string.getrange")
+        .arg(&["missing", "0", "-1"])
+        .query(&mut con)?;
+    assert_eq!(&res, "");
+
+    let res: i64 = redis::cmd("string.setrange")
+        .arg(&["key", "10", "Redis!"])
+        .query(&mut con)
+        .with_context(|| "failed to run string.setrange")?;
+    assert_eq!(res, 16);
+    let res: String = redis::cmd("get").arg(&["key"]).query(&mut con)?;
+    assert_eq!(&res, "This is a Redis!");
+
+    let res: i64 = redis::cmd("string.setrange")
+        .arg(&["other", "3", "abc"])
+        .query(&mut con)
+        .with_context(|| "failed to run string.setrange")?;
+    assert_eq!(res, 6);
+    let res: Vec<u8> = redis::cmd("get").arg(&["other"]).query(&mut con)?;
+    assert_eq!(&res, b"\0\0\0abc");
+
+    let res: i64 = redis::cmd("string.setrange")
+        .arg(&["missing", "3", ""])
+        .query(&mut con)?;
+    assert_eq!(res, 0);
+    let res: bool = redis::cmd("exists").arg(&["missing"]).query(&mut con)?;
+    assert!(!res);
+
+    // Empty data past the end leaves the value as is, like SETRANGE.
+    let res: i64 = redis::cmd("string.setrange")
+        .arg(&["other", "100", ""])
+        .query(&mut con)?;
+    assert_eq!(res, 6);
+    let res: Vec<u8> = redis::cmd("get").arg(&["other"]).query(&mut con)?;
+    assert_eq!(&res, b"\0\0\0abc");
+
+    for offset in ["536870912", "18446744073709551615"] {
+        let err = redis::cmd("string.setrange")
+            .arg(&["other", offset, "x"])
+            .query::<i64>(&mut con)
+            .unwrap_err();
+        assert_eq!(
+            err.detail(),
+            Some("string exceeds maximum allowed size"),
+            "offset {offset}"
+        );
+    }
+    let res: Vec<u8> = redis::cmd("get").arg(&["other"]).query(&mut con)?;
+    assert_eq!(&res, b"\0\0\0abc");
+
+    redis::cmd("hset")
+        .arg(&["hash", "field", "value"])
+        .query(&mut con)
+        .with_context(|| "failed to run hset")?;
+    for cmd in [
+        redis::cmd("string.getrange").arg(&["hash", "0", "-1"]),
+        redis::cmd("string.setrange").arg(&["hash", "0", "x"]),
+        redis::cmd("string.get").arg(&["hash"]),
+    ] {
+        let err = cmd.query::<String>(&mut con).unwrap_err();
+        assert_eq!(err.code(), Some("WRONGTYPE"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_string_bits() -> Result<()> {
+    let port: u16 = 6526;
+    let _guards = vec![start_redis_server_with_module("string", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    for (offset, value, old) in [("7", "1", 0), ("0", "1", 0), ("7", "0", 1), ("7", "1", 0)] {
+        let res: i64 = redis::cmd("string.setbit")
+            .arg(&["bits", offset, value])
+            .query(&mut con)
+            .with_context(|| "failed to run string.setbit")?;
+        assert_eq!(res, old);
+    }
+    let res: Vec<u8> = redis::cmd("get").arg(&["bits"]).query(&mut con)?;
+    assert_eq!(res, vec![0x81]);
+
+    // Large offsets grow the string with zero bytes, like SETBIT.
+    let large_offset = "8000007";
+    let res: i64 = redis::cmd("string.setbit")
+        .arg(&["bits", large_offset, "1"])
+        .query(&mut con)?;
+    assert_eq!(res, 0);
+    let res: i64 = redis::cmd("strlen").arg(&["bits"]).query(&mut con)?;
+    assert_eq!(res, 1_000_001);
+    redis::cmd("setbit")
+        .arg(&["expected", "0", "1"])
+        .query(&mut con)?;
+    redis::cmd("setbit")
+        .arg(&["expected", "7", "1"])
+        .query(&mut con)?;
+    redis::cmd("setbit")
+        .arg(&["expected", large_offset, "1"])
+        .query(&mut con)?;
+    let bits: Vec<u8> = redis::cmd("get").arg(&["bits"]).query(&mut con)?;
+    let expected: Vec<u8> = redis::cmd("get").arg(&["expected"]).query(&mut con)?;
+    assert_eq!(bits, expected);
+
+    for offset in ["0", "1", "7", "8", large_offset, "8000008", "4294967295"] {
+        let res: i64 = redis::cmd("string.getbit")
+            .arg(&["bits", offset])
+            .query(&mut con)
+            .with_context(|| "failed to run string.getbit")?;
+        let expected: i64 = redis::cmd("getbit")
+            .arg(&["bits", offset])
+            .query(&mut con)?;
+        assert_eq!(res, expected);
+    }
+    let res: i64 = redis::cmd("string.getbit")
+        .arg(&["missing", "100"])
+        .query(&mut con)?;
+    assert_eq!(res, 0);
+
+    for range in [
+        vec![],
+        vec!["0", "0"],
+        vec!["1", "-2"],
+        vec!["-1", "-1"],
+        vec!["5", "1"],
+    ] {
+        let res: i64 = redis::cmd("string.bitcount")
+            .arg("bits")
+            .arg(&range)
+            .query(&mut con)
+            .with_context(|| "failed to run string.bitcount")?;
+        let expected: i64 = redis::cmd("bitcount")
+            .arg("bits")
+            .arg(&range)
+            .query(&mut con)?;
+        assert_eq!(res, expected);
+    }
+
+    let err = redis::cmd("string.setbit")
+        .arg(&["bits", "4294967296", "1"])
+        .query::<i64>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("bit offset"));
+
+    Ok(())
+}
+
+#[test]
+fn test_open_key_cached() -> Result<()> {
+    let port: u16 = 6533;
+    let _guards = vec![start_redis_server_with_module("string", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    redis::cmd("set").arg(&["cached", "old"]).query(&mut con)?;
+    let (before, reused, after): (String, i64, String) = redis::cmd("string.cached")
+        .arg(&["cached", "new"])
+        .query(&mut con)
+        .with_context(|| "failed to run string.cached")?;
+    assert_eq!(before, "old");
+    assert_eq!(reused, 1);
+    assert_eq!(after, "new");
+
+    // A write through a handle opened before the cached one.
+    let (before, written, reused, after): (String, String, i64, String) =
+        redis::cmd("string.cached_write")
+            .arg(&["cached", "newer"])
+            .query(&mut con)
+            .with_context(|| "failed to run string.cached_write")?;
+    assert_eq!(before, "new");
+    assert_eq!(written, "newer");
+    assert_eq!(reused, 1);
+    assert_eq!(after, "newer");
+
+    Ok(())
+}
+
+#[test]
+fn test_dict() -> Result<()> {
+    let port: u16 = 6527;
+    let _guards = vec![start_redis_server_with_module("dict", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    for (key, value) in [
+        ("b", "2"),
+        ("d", "4"),
+        ("a", "1"),
+        ("c", "3"),
+        ("e\0x", "5"),
+    ] {
+        let res: i64 = redis::cmd("dict.set")
+            .arg(&[key, value])
+            .query(&mut con)
+            .with_context(|| "failed to run dict.set")?;
+        assert_eq!(res, 1);
+    }
+    let res: i64 = redis::cmd("dict.set").arg(&["c", "33"]).query(&mut con)?;
+    assert_eq!(res, 0);
+
+    let res: Option<String> = redis::cmd("dict.get").arg(&["c"]).query(&mut con)?;
+    assert_eq!(res.as_deref(), Some("33"));
+    let res: Option<String> = redis::cmd("dict.get").arg(&["e\0x"]).query(&mut con)?;
+    assert_eq!(res.as_deref(), Some("5"));
+    let res: Option<String> = redis::cmd("dict.get").arg(&["e"]).query(&mut con)?;
+    assert_eq!(res, None);
+
+    let res: Vec<String> = redis::cmd("dict.range")
+        .arg(&["b", "d"])
+        .query(&mut con)
+        .with_context(|| "failed to run dict.range")?;
+    assert_eq!(res, vec!["b", "2", "c", "33", "d", "4"]);
+    let res: Vec<String> = redis::cmd("dict.range").arg(&["bb", "z"]).query(&mut con)?;
+    assert_eq!(res, vec!["c", "33", "d", "4", "e\0x", "5"]);
+    let res: Vec<String> = redis::cmd("dict.range").arg(&["x", "z"]).query(&mut con)?;
+    assert!(res.is_empty());
+
+    let res: i64 = redis::cmd("dict.del").arg(&["c"]).query(&mut con)?;
+    assert_eq!(res, 1);
+    let res: i64 = redis::cmd("dict.del").arg(&["c"]).query(&mut con)?;
+    assert_eq!(res, 0);
+    let res: i64 = redis::cmd("dict.len").query(&mut con)?;
+    assert_eq!(res, 4);
+
+    // The dict now holds a, b, d and "e\0x".
+    for (op, key, direction, expected) in [
+        ("^", "", "next", vec!["a", "b", "d"]),
+        ("$", "", "prev", vec!["e\0x", "d", "b"]),
+        (">", "b", "next", vec!["d", "e\0x"]),
+        (">=", "b", "next", vec!["b", "d", "e\0x"]),
+        (">=", "c", "prev", vec!["d", "b", "a"]),
+        ("<", "d", "prev", vec!["b", "a"]),
+        ("<=", "d", "prev", vec!["d", "b", "a"]),
+        ("<=", "c", "next", vec!["b", "d", "e\0x"]),
+        ("==", "d", "next", vec!["d", "e\0x"]),
+        ("==", "c", "next", vec![]),
+        (">", "z", "next", vec![]),
+    ] {
+        let res: Vec<String> = redis::cmd("dict.from")
+            .arg(&[op, key, "3", direction])
+            .query(&mut con)
+            .with_context(|| "failed to run dict.from")?;
+        assert_eq!(res, expected, "{op} {key} {direction}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_block_client_on_task() -> Result<()> {
+    let port: u16 = 6529;
+    let _guards = vec![start_redis_server_with_module("block_async", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: i64 = redis::cmd("block_async.sleep_add")
+        .arg(&["100", "40", "2"])
+        .query(&mut con)
+        .with_context(|| "failed to run block_async.sleep_add")?;
+    assert_eq!(res, 42);
+
+    let err = redis::cmd("block_async.sleep_add")
+        .arg(0)
+        .arg(i64::MAX)
+        .arg(1)
+        .query::<i64>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("integer overflow"));
+
+    // A task that panics still unblocks its client, with an error.
+    let err = redis::cmd("block_async.panic")
+        .query::<()>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("the task did not complete"));
+
+    // The server kept serving other clients while the tasks ran.
+    let res: String = redis::cmd("ping").query(&mut con)?;
+    assert_eq!(&res, "PONG");
+
+    Ok(())
+}
+
 #[test]
 fn test_scan() -> Result<()> {
     let port: u16 = 6486;
@@ -236,6 +750,54 @@ fn test_scan() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_scan_while_modifying() -> Result<()> {
+    let port: u16 = 6513;
+    let _guards = vec![start_redis_server_with_module("scan_keys", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let stable: Vec<String> = (0..1000).map(|i| format!("stable:{i}")).collect();
+    for key in &stable {
+        redis::cmd("set").arg(&[key, "1"]).query(&mut con)?;
+    }
+
+    // Add and delete keys between the first scan steps, growing the keyspace
+    // enough to resize and rehash it during the scan.
+    let mut seen = std::collections::HashSet::new();
+    let mut step = 0;
+    loop {
+        let res: Vec<String> = redis::cmd("scan_keys.step").arg(1).query(&mut con)?;
+        seen.extend(res[1..].iter().cloned());
+        if res[0] == "1" {
+            break;
+        }
+
+        if step < 40 {
+            for i in 0..50 {
+                redis::cmd("set")
+                    .arg(&[format!("tmp:{step}:{i}"), "1".to_owned()])
+                    .query(&mut con)?;
+            }
+        }
+        if step > 0 && step <= 40 {
+            for i in 0..25 {
+                redis::cmd("del")
+                    .arg(format!("tmp:{}:{i}", step - 1))
+                    .query(&mut con)?;
+            }
+        }
+        step += 1;
+    }
+
+    for key in &stable {
+        assert!(seen.contains(key), "key {key} was not returned by the scan");
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_stream_reader() -> Result<()> {
     let port: u16 = 6487;
@@ -276,6 +838,283 @@ fn test_stream_reader() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_stream_iter() -> Result<()> {
+    let port: u16 = 6535;
+    let _guards = vec![start_redis_server_with_module("stream", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    for (id, fields) in [
+        ("1-1", vec!["a", "1"]),
+        ("1-2", vec!["a", "2", "b", "3"]),
+        ("2-0", vec!["c", "4"]),
+    ] {
+        let _: String = redis::cmd("XADD")
+            .arg("s")
+            .arg(id)
+            .arg(&fields)
+            .query(&mut con)
+            .with_context(|| "failed to add data to the stream")?;
+    }
+
+    for range in [
+        ["-", "+"],
+        ["1-2", "+"],
+        ["-", "1-1"],
+        ["2", "2-0"],
+        ["3", "+"],
+    ] {
+        let res: Value = redis::cmd("STREAM_RANGE")
+            .arg("s")
+            .arg(&range)
+            .query(&mut con)
+            .with_context(|| "failed to run STREAM_RANGE")?;
+        let expected: Value = redis::cmd("XRANGE").arg("s").arg(&range).query(&mut con)?;
+        assert_eq!(res, expected, "range {range:?}");
+    }
+
+    let res: Value = redis::cmd("STREAM_RANGE")
+        .arg(&["missing", "-", "+"])
+        .query(&mut con)?;
+    assert_eq!(res, Value::Bulk(vec![]));
+
+    redis::cmd("SET").arg(&["str", "x"]).query(&mut con)?;
+    let err = redis::cmd("STREAM_RANGE")
+        .arg(&["str", "-", "+"])
+        .query::<Value>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"));
+
+    let err = redis::cmd("STREAM_RANGE")
+        .arg(&["s", "x", "+"])
+        .query::<Value>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("Invalid stream ID"));
+
+    Ok(())
+}
+
+#[test]
+fn test_stream_add() -> Result<()> {
+    let port: u16 = 6536;
+    let _guards = vec![start_redis_server_with_module("stream", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: String = redis::cmd("STREAM_ADD")
+        .arg(&["s", "5-1", "a", "1"])
+        .query(&mut con)
+        .with_context(|| "failed to run STREAM_ADD")?;
+    assert_eq!(res, "5-1");
+    let res: String = redis::cmd("STREAM_ADD")
+        .arg(&["s", "*", "b", "2", "c", "3"])
+        .query(&mut con)?;
+    // The returned ID is the one of the last entry.
+    let res: Value = redis::cmd("XRANGE")
+        .arg(&["s", &res, &res])
+        .query(&mut con)?;
+    let expected: Value = redis::cmd("XREVRANGE")
+        .arg(&["s", "+", "-", "COUNT", "1"])
+        .query(&mut con)?;
+    assert_eq!(res, expected);
+    assert_ne!(res, Value::Bulk(vec![]));
+
+    let err = redis::cmd("STREAM_ADD")
+        .arg(&["s", "5-1", "a", "1"])
+        .query::<String>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("equal or smaller"));
+
+    let res: Option<String> = redis::cmd("STREAM_ADD")
+        .arg(&["missing", "NOMKSTREAM", "*", "a", "1"])
+        .query(&mut con)?;
+    assert_eq!(res, None);
+    let res: i64 = redis::cmd("EXISTS").arg("missing").query(&mut con)?;
+    assert_eq!(res, 0);
+
+    for i in 0..5 {
+        let _: String = redis::cmd("STREAM_ADD")
+            .arg(&["capped", "MAXLEN", "3", "*", "i"])
+            .arg(i)
+            .query(&mut con)?;
+    }
+    let res: i64 = redis::cmd("XLEN").arg("capped").query(&mut con)?;
+    assert_eq!(res, 3);
+
+    redis::cmd("SET").arg(&["str", "x"]).query(&mut con)?;
+    let err = redis::cmd("STREAM_ADD")
+        .arg(&["str", "*", "a", "1"])
+        .query::<String>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"));
+
+    Ok(())
+}
+
+#[test]
+fn test_stream_trim_and_delete() -> Result<()> {
+    let port: u16 = 6537;
+    let _guards = vec![start_redis_server_with_module("stream", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    for i in 1..=10 {
+        let _: String = redis::cmd("XADD")
+            .arg("s")
+            .arg(format!("{i}-0"))
+            .arg(&["i", "x"])
+            .query(&mut con)?;
+    }
+
+    let res: i64 = redis::cmd("STREAM_TRIM")
+        .arg(&["s", "MAXLEN", "8"])
+        .query(&mut con)
+        .with_context(|| "failed to run STREAM_TRIM")?;
+    assert_eq!(res, 2);
+    let res: i64 = redis::cmd("STREAM_TRIM")
+        .arg(&["s", "MINID", "5"])
+        .query(&mut con)?;
+    assert_eq!(res, 2);
+    // Nothing left to trim is not an error.
+    let res: i64 = redis::cmd("STREAM_TRIM")
+        .arg(&["s", "MINID", "5"])
+        .query(&mut con)?;
+    assert_eq!(res, 0);
+    // An approximate trim never removes part of a node, which holds up to 100
+    // entries by default.
+    let res: i64 = redis::cmd("STREAM_TRIM")
+        .arg(&["s", "MAXLEN", "~", "1"])
+        .query(&mut con)?;
+    assert_eq!(res, 0);
+    let res: i64 = redis::cmd("XLEN").arg("s").query(&mut con)?;
+    assert_eq!(res, 6);
+
+    let res: i64 = redis::cmd("STREAM_DEL")
+        .arg(&["s", "5-0", "6-0", "1-0"])
+        .query(&mut con)
+        .with_context(|| "failed to run STREAM_DEL")?;
+    assert_eq!(res, 2);
+    let res: i64 = redis::cmd("XLEN").arg("s").query(&mut con)?;
+    assert_eq!(res, 4);
+
+    let res: i64 = redis::cmd("STREAM_DEL")
+        .arg(&["missing", "1-0"])
+        .query(&mut con)?;
+    assert_eq!(res, 0);
+    for strategy in ["MAXLEN", "MINID"] {
+        let res: i64 = redis::cmd("STREAM_TRIM")
+            .arg(&["missing", strategy, "0"])
+            .query(&mut con)?;
+        assert_eq!(res, 0);
+    }
+    let res: i64 = redis::cmd("EXISTS").arg("missing").query(&mut con)?;
+    assert_eq!(res, 0);
+
+    redis::cmd("SET").arg(&["str", "x"]).query(&mut con)?;
+    for cmd in [
+        redis::cmd("STREAM_DEL").arg(&["str", "1-0"]).clone(),
+        redis::cmd("STREAM_TRIM")
+            .arg(&["str", "MAXLEN", "0"])
+            .clone(),
+    ] {
+        let err = cmd.query::<i64>(&mut con).unwrap_err();
+        assert!(err.to_string().contains("WRONGTYPE"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_string_set_with_expire() -> Result<()> {
+    let port: u16 = 6539;
+    let _guards = vec![start_redis_server_with_module("expire", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: String = redis::cmd("expire.set")
+        .arg(&["k", "v1", "100000"])
+        .query(&mut con)
+        .with_context(|| "failed to run expire.set")?;
+    assert_eq!(res, "OK");
+    let value: String = redis::cmd("GET").arg("k").query(&mut con)?;
+    assert_eq!(value, "v1");
+    let ttl: i64 = redis::cmd("PTTL").arg("k").query(&mut con)?;
+    assert!(ttl > 90_000 && ttl <= 100_000, "unexpected ttl {ttl}");
+
+    // The time to live is kept with KEEPTTL and cleared otherwise, like SET.
+    redis::cmd("expire.set")
+        .arg(&["k", "v2", "KEEPTTL"])
+        .query(&mut con)?;
+    let value: String = redis::cmd("GET").arg("k").query(&mut con)?;
+    assert_eq!(value, "v2");
+    let ttl: i64 = redis::cmd("PTTL").arg("k").query(&mut con)?;
+    assert!(ttl > 90_000 && ttl <= 100_000, "unexpected ttl {ttl}");
+    redis::cmd("expire.set")
+        .arg(&["k", "v3", "PERSIST"])
+        .query(&mut con)?;
+    let ttl: i64 = redis::cmd("PTTL").arg("k").query(&mut con)?;
+    assert_eq!(ttl, -1);
+
+    // Any type is overwritten.
+    redis::cmd("RPUSH").arg(&["list", "a"]).query(&mut con)?;
+    redis::cmd("expire.set")
+        .arg(&["list", "v", "PERSIST"])
+        .query(&mut con)?;
+    let value: String = redis::cmd("GET").arg("list").query(&mut con)?;
+    assert_eq!(value, "v");
+
+    let err = redis::cmd("expire.set")
+        .arg(&["k", "v", "0"])
+        .query::<String>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid expire time"));
+
+    Ok(())
+}
+
+#[test]
+fn test_key_type_name() -> Result<()> {
+    let port: u16 = 6542;
+    let _guards = vec![start_redis_server_with_module("data_type", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    redis::cmd("SET").arg(&["string", "x"]).query(&mut con)?;
+    redis::cmd("RPUSH").arg(&["list", "x"]).query(&mut con)?;
+    redis::cmd("HSET")
+        .arg(&["hash", "f", "x"])
+        .query(&mut con)?;
+    redis::cmd("SADD").arg(&["set", "x"]).query(&mut con)?;
+    redis::cmd("ZADD")
+        .arg(&["zset", "1", "x"])
+        .query(&mut con)?;
+    redis::cmd("XADD")
+        .arg(&["stream", "*", "f", "x"])
+        .query(&mut con)?;
+    redis::cmd("alloc.set")
+        .arg(&["mytype", "3"])
+        .query(&mut con)?;
+
+    for key in [
+        "string", "list", "hash", "set", "zset", "stream", "mytype", "missing",
+    ] {
+        let res: String = redis::cmd("alloc.type")
+            .arg(key)
+            .query(&mut con)
+            .with_context(|| "failed to run alloc.type")?;
+        let expected: String = redis::cmd("TYPE").arg(key).query(&mut con)?;
+        assert_eq!(res, expected);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_call() -> Result<()> {
     let port: u16 = 6488;
@@ -293,6 +1132,31 @@ fn test_call() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_call_nested_error() -> Result<()> {
+    let port: u16 = 6518;
+    let _guards = vec![start_redis_server_with_module("call", port)
+        .with_context(|| "failed to start redis server")?];
+    // wait for the server to accept connections
+    get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    let script = "return {1, redis.error_reply('ERR boom'), 'ok'}";
+    stream.write_all(
+        format!(
+            "*4\r\n$10\r\ncall.proxy\r\n$4\r\neval\r\n${}\r\n{script}\r\n$1\r\n0\r\n",
+            script.len()
+        )
+        .as_bytes(),
+    )?;
+    let expected = b"*3\r\n:1\r\n-ERR boom\r\n+ok\r\n";
+    let mut res = vec![0; expected.len()];
+    stream.read_exact(&mut res)?;
+    assert_eq!(res, expected);
+
+    Ok(())
+}
+
 #[test]
 fn test_ctx_flags() -> Result<()> {
     let port: u16 = 6489;
@@ -303,7 +1167,188 @@ fn test_ctx_flags() -> Result<()> {
 
     let res: String = redis::cmd("my_role").query(&mut con)?;
 
-    assert_eq!(&res, "master");
+    assert_eq!(&res, "master");
+
+    Ok(())
+}
+
+#[test]
+fn test_block_in_transaction() -> Result<()> {
+    let port: u16 = 6506;
+    let _guards = vec![start_redis_server_with_module("block", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: String = redis::cmd("block").query(&mut con)?;
+    assert_eq!(&res, "42");
+
+    let start = std::time::Instant::now();
+    let res: (Option<String>,) = redis::pipe().atomic().cmd("block").query(&mut con)?;
+    assert_eq!(res, (None,));
+    assert!(start.elapsed() < Duration::from_millis(1000));
+
+    Ok(())
+}
+
+#[test]
+fn test_blocked_client_count() -> Result<()> {
+    let port: u16 = 6525;
+    let _guards = vec![start_redis_server_with_module("block", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: i64 = redis::cmd("block.count").query(&mut con)?;
+    assert_eq!(res, 0);
+
+    let mut blocked_con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+    let blocked = std::thread::spawn(move || redis::cmd("block").query::<String>(&mut blocked_con));
+    std::thread::sleep(Duration::from_millis(300));
+    let res: i64 = redis::cmd("block.count").query(&mut con)?;
+    assert_eq!(res, 1);
+
+    assert_eq!(blocked.join().unwrap()?, "42");
+    let res: i64 = redis::cmd("block.count").query(&mut con)?;
+    assert_eq!(res, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_get_client_cert() -> Result<()> {
+    let port: u16 = 6507;
+    let _guards = vec![start_redis_server_with_module("acl", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    // The test server does not use TLS, so there is no certificate.
+    let client_id: u64 = redis::cmd("client").arg(&["id"]).query(&mut con)?;
+    let res: Option<Vec<u8>> = redis::cmd("get_client_cert")
+        .arg(client_id)
+        .query(&mut con)?;
+    assert_eq!(res, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_command_acl_categories() -> Result<()> {
+    let port: u16 = 6520;
+    let _guards = vec![start_redis_server_with_module("acl_categories", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: Vec<String> = redis::cmd("ACL").arg(&["CAT", "fast"]).query(&mut con)?;
+    assert!(res.iter().any(|c| c == "acl_categories.get"));
+    let res: Vec<String> = redis::cmd("ACL").arg(&["CAT", "write"]).query(&mut con)?;
+    assert!(!res.iter().any(|c| c == "acl_categories.get"));
+
+    let _: String = redis::cmd("ACL")
+        .arg(&["SETUSER", "alice", "on", ">pass", "+@all", "-@read", "~*"])
+        .query(&mut con)?;
+    let _: String = redis::cmd("AUTH").arg(&["alice", "pass"]).query(&mut con)?;
+    let res: Result<String, RedisError> = redis::cmd("acl_categories.get").query(&mut con);
+    assert!(res.unwrap_err().to_string().contains("no permissions"));
+
+    Ok(())
+}
+
+#[test]
+fn test_client_name() -> Result<()> {
+    let port: u16 = 6515;
+    let _guards = vec![start_redis_server_with_module("acl", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let client_id: u64 = redis::cmd("client").arg(&["id"]).query(&mut con)?;
+    let res: Option<Vec<u8>> = redis::cmd("get_client_name")
+        .arg(client_id)
+        .query(&mut con)?;
+    assert_eq!(res, None);
+
+    let name: &[u8] = b"session:42";
+    let _: String = redis::cmd("set_client_name")
+        .arg(client_id)
+        .arg(name)
+        .query(&mut con)?;
+    let res: Option<Vec<u8>> = redis::cmd("get_client_name")
+        .arg(client_id)
+        .query(&mut con)?;
+    assert_eq!(res.as_deref(), Some(name));
+
+    let res: Vec<u8> = redis::cmd("client").arg(&["getname"]).query(&mut con)?;
+    assert_eq!(res, name);
+
+    let res: Result<String, RedisError> = redis::cmd("set_client_name")
+        .arg(client_id)
+        .arg("with space")
+        .query(&mut con);
+    assert!(res.is_err());
+
+    let res: Result<String, RedisError> = redis::cmd("set_client_name")
+        .arg(client_id + 1000)
+        .arg("other")
+        .query(&mut con);
+    assert!(res.is_err());
+
+    let res: Option<Vec<u8>> = redis::cmd("get_client_name")
+        .arg(client_id + 1000)
+        .query(&mut con)?;
+    assert_eq!(res, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_auth_callback() -> Result<()> {
+    let port: u16 = 6510;
+    let _guards = vec![start_redis_server_with_module("auth", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let _: String = redis::cmd("ACL")
+        .arg(&["SETUSER", "alice", "on", ">alice_password", "+@all", "~*"])
+        .query(&mut con)?;
+
+    let _: String = redis::cmd("AUTH")
+        .arg(&["alice", "module_secret"])
+        .query(&mut con)?;
+    let res: String = redis::cmd("ACL").arg(&["WHOAMI"]).query(&mut con)?;
+    assert_eq!(&res, "alice");
+
+    let res: RedisResult<String> = redis::cmd("AUTH")
+        .arg(&["alice", "module_deny"])
+        .query(&mut con);
+    assert!(res
+        .unwrap_err()
+        .to_string()
+        .contains("DENIED by the auth module"));
+
+    // Not handled by the module, falls back to password authentication.
+    let _: String = redis::cmd("AUTH")
+        .arg(&["alice", "alice_password"])
+        .query(&mut con)?;
+    let res: RedisResult<String> = redis::cmd("AUTH")
+        .arg(&["alice", "wrong_password"])
+        .query(&mut con);
+    assert_eq!(res.unwrap_err().code(), Some("WRONGPASS"));
+
+    // Verified on a background thread while the client is blocked.
+    let _: String = redis::cmd("AUTH")
+        .arg(&["alice", "module_async_secret"])
+        .query(&mut con)?;
+    let res: String = redis::cmd("ACL").arg(&["WHOAMI"]).query(&mut con)?;
+    assert_eq!(&res, "alice");
+    let res: RedisResult<String> = redis::cmd("AUTH")
+        .arg(&["alice", "module_async_deny"])
+        .query(&mut con);
+    assert_eq!(res.unwrap_err().code(), Some("WRONGPASS"));
 
     Ok(())
 }
@@ -388,6 +1433,76 @@ fn test_key_space_notifications() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_module_keyspace_event() -> Result<()> {
+    let port: u16 = 6509;
+    let _guards = vec![start_redis_server_with_module("events", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    // Module events are enabled by the `d` class.
+    let _: String = redis::cmd("CONFIG")
+        .arg(&["SET", "notify-keyspace-events", "Ed"])
+        .query(&mut con)?;
+
+    let mut sub_con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+    let mut pubsub = sub_con.as_pubsub();
+    pubsub.set_read_timeout(Some(Duration::from_secs(5)))?;
+    pubsub.subscribe("__keyevent@0__:myevent")?;
+
+    let res: String = redis::cmd("events.send_module")
+        .arg(&["mykey"])
+        .query(&mut con)?;
+    assert_eq!(&res, "Event sent");
+
+    let msg = pubsub.get_message()?;
+    assert_eq!(msg.get_channel_name(), "__keyevent@0__:myevent");
+    let payload: String = msg.get_payload()?;
+    assert_eq!(payload, "mykey");
+
+    Ok(())
+}
+
+#[test]
+fn test_single_event_for_several_writes() -> Result<()> {
+    let port: u16 = 6524;
+    let _guards = vec![start_redis_server_with_module("events", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let _: String = redis::cmd("CONFIG")
+        .arg(&["SET", "notify-keyspace-events", "KA"])
+        .query(&mut con)?;
+
+    let mut sub_con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+    let mut pubsub = sub_con.as_pubsub();
+    pubsub.set_read_timeout(Some(Duration::from_secs(5)))?;
+    pubsub.subscribe("__keyspace@0__:k")?;
+
+    let res: String = redis::cmd("events.set_with_ttl")
+        .arg(&["k", "v", "100000"])
+        .query(&mut con)?;
+    assert_eq!(&res, "OK");
+    let ttl: i64 = redis::cmd("PTTL").arg(&["k"]).query(&mut con)?;
+    assert!(ttl > 0);
+
+    // The write and the expire are published as one event, so the event sent
+    // next is the following message.
+    let _: String = redis::cmd("events.send_module")
+        .arg(&["k"])
+        .query(&mut con)?;
+    let events = (0..2)
+        .map(|_| pubsub.get_message()?.get_payload())
+        .collect::<Result<Vec<String>, _>>()?;
+    assert_eq!(events, ["events.set_with_ttl", "myevent"]);
+
+    Ok(())
+}
+
 #[test]
 fn test_context_mutex() -> Result<()> {
     let port: u16 = 6493;
@@ -561,6 +1676,501 @@ fn test_response() -> Result<()> {
     res.sort();
     assert_eq!(&res, &["b", "d"]);
 
+    let res: Vec<String> = redis::cmd("map.entries")
+        .arg(&["k", "e", "a", "c"])
+        .query(&mut con)
+        .with_context(|| "failed to run map.entries")?;
+
+    assert_eq!(&res, &["e", "b", "a", "b", "c", "d"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_empty_string_vs_null() -> Result<()> {
+    let port: u16 = 6504;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    // wait for the server to accept connections
+    get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    for (kind, expected) in [
+        ("empty", &b"$0\r\n\r\n"[..]),
+        ("bulk", &b"$0\r\n\r\n"[..]),
+        ("buffer", &b"$0\r\n\r\n"[..]),
+        ("null", &b"$-1\r\n"[..]),
+    ] {
+        stream.write_all(format!("reply.empty_or_null {kind}\r\n").as_bytes())?;
+        let mut res = vec![0; expected.len()];
+        stream.read_exact(&mut res)?;
+        assert_eq!(res, expected, "wrong reply bytes for '{kind}'");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_from_exact_iter() -> Result<()> {
+    let port: u16 = 6543;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: Vec<i64> = redis::cmd("reply.exact_iter")
+        .arg(1000)
+        .query(&mut con)
+        .with_context(|| "failed to run reply.exact_iter")?;
+    assert_eq!(res, (0..1000).collect::<Vec<i64>>());
+
+    let res: Value = redis::cmd("reply.exact_iter")
+        .arg(0)
+        .query(&mut con)
+        .with_context(|| "failed to run reply.exact_iter")?;
+    assert_eq!(res, Value::Bulk(vec![]));
+
+    // An iterator shorter than its length is padded with nulls.
+    let res: Value = redis::cmd("reply.exact_iter")
+        .arg(&[3, 1])
+        .query(&mut con)
+        .with_context(|| "failed to run reply.exact_iter")?;
+    assert_eq!(
+        res,
+        Value::Bulk(vec![Value::Int(0), Value::Nil, Value::Nil])
+    );
+
+    // The connection is still in sync.
+    let res: String = redis::cmd("PING").query(&mut con)?;
+    assert_eq!(res, "PONG");
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_error_with_details() -> Result<()> {
+    fn check(stream: &mut TcpStream, cmd: &str, expected: &[u8]) -> Result<()> {
+        stream.write_all(format!("{cmd}\r\n").as_bytes())?;
+        let mut res = vec![0; expected.len()];
+        stream.read_exact(&mut res)?;
+        assert_eq!(res, expected, "wrong reply bytes for '{cmd}'");
+        Ok(())
+    }
+
+    let port: u16 = 6544;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    // RESP2 clients only get the error.
+    let err = redis::cmd("reply.error_details")
+        .arg(100)
+        .query::<String>(&mut con)
+        .unwrap_err();
+    assert_eq!(err.code(), Some("INDEX_FULL"));
+    assert_eq!(err.detail(), Some("the index is full"));
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    check(
+        &mut stream,
+        "reply.error_details 100",
+        b"-INDEX_FULL the index is full\r\n",
+    )?;
+
+    // Switch to RESP3, skipping the HELLO reply up to the PONG that follows it.
+    stream.write_all(b"HELLO 3\r\nPING\r\n")?;
+    let mut skipped = Vec::new();
+    while !skipped.ends_with(b"+PONG\r\n") {
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte)?;
+        skipped.push(byte[0]);
+    }
+    check(
+        &mut stream,
+        "reply.error_details 100",
+        b"|1\r\n$5\r\nlimit\r\n$3\r\n100\r\n-INDEX_FULL the index is full\r\n",
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_nested_too_deeply() -> Result<()> {
+    let port: u16 = 6545;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: Vec<Vec<Vec<i64>>> = redis::cmd("reply.nested").arg(3).query(&mut con)?;
+    assert_eq!(res, vec![vec![vec![1]]]);
+
+    // Replying would overflow the stack of the server.
+    let err = redis::cmd("reply.nested")
+        .arg(100_000)
+        .query::<redis::Value>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("reply nested too deeply"));
+    let res: String = redis::cmd("PING").query(&mut con)?;
+    assert_eq!(res, "PONG");
+
+    let err = redis::cmd("reply.nested")
+        .arg(&[3, 2])
+        .query::<redis::Value>(&mut con)
+        .unwrap_err();
+    assert!(err.to_string().contains("reply nested too deeply"));
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_i64_slice() -> Result<()> {
+    let port: u16 = 6516;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: Vec<i64> = redis::cmd("reply.range")
+        .arg(50_000)
+        .query(&mut con)
+        .with_context(|| "failed to run reply.range")?;
+    assert_eq!(res, (0..50_000).collect::<Vec<i64>>());
+
+    let res: Value = redis::cmd("reply.range")
+        .arg(0)
+        .query(&mut con)
+        .with_context(|| "failed to run reply.range")?;
+    assert_eq!(res, Value::Bulk(vec![]));
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_with_map_keeps_duplicates() -> Result<()> {
+    let port: u16 = 6519;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    // RESP2 clients get the map as a flat array of keys and values.
+    let res: Vec<String> = redis::cmd("reply.pairs")
+        .arg(&["a", "1", "b", "2", "a", "3"])
+        .query(&mut con)
+        .with_context(|| "failed to run reply.pairs")?;
+    assert_eq!(&res, &["a", "1", "b", "2", "a", "3"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_flat_pairs() -> Result<()> {
+    fn check(stream: &mut TcpStream, cmd: &str, expected: &[u8]) -> Result<()> {
+        stream.write_all(format!("{cmd}\r\n").as_bytes())?;
+        let mut res = vec![0; expected.len()];
+        stream.read_exact(&mut res)?;
+        assert_eq!(res, expected, "wrong reply bytes for '{cmd}'");
+        Ok(())
+    }
+
+    let port: u16 = 6534;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    // wait for the server to accept connections
+    get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let expected = b"*6\r\n$1\r\nb\r\n$1\r\n1\r\n$1\r\na\r\n$1\r\n2\r\n$1\r\nb\r\n$1\r\n3\r\n";
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    check(&mut stream, "reply.flat_pairs b 1 a 2 b 3", expected)?;
+    check(&mut stream, "reply.flat_pairs", b"*0\r\n")?;
+
+    // Switch to RESP3, skipping the HELLO reply up to the PONG that follows it.
+    stream.write_all(b"HELLO 3\r\nPING\r\n")?;
+    let mut skipped = Vec::new();
+    while !skipped.ends_with(b"+PONG\r\n") {
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte)?;
+        skipped.push(byte[0]);
+    }
+    // RESP3 clients get the same flat array, not a map.
+    check(&mut stream, "reply.flat_pairs b 1 a 2 b 3", expected)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_raw_protocol() -> Result<()> {
+    fn check(stream: &mut TcpStream, resp: &[u8], expected: &[u8]) -> Result<()> {
+        stream.write_all(&redis::cmd("reply.raw").arg(resp).get_packed_command())?;
+        let mut res = vec![0; expected.len()];
+        stream.read_exact(&mut res)?;
+        assert_eq!(
+            res,
+            expected,
+            "wrong reply bytes for '{}'",
+            String::from_utf8_lossy(resp)
+        );
+        Ok(())
+    }
+
+    let port: u16 = 6538;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    // wait for the server to accept connections
+    get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    for resp in [
+        &b"+OK\r\n"[..],
+        b"$4\r\na\r\nb\r\n",
+        b":-3\r\n",
+        b"*3\r\n$1\r\na\r\n*0\r\n$-1\r\n",
+        b"-ERR custom failure\r\n",
+    ] {
+        check(&mut stream, resp, resp)?;
+    }
+    // RESP3 types are downgraded for RESP2 clients.
+    check(
+        &mut stream,
+        b"%1\r\n$1\r\nk\r\n#t\r\n",
+        b"*2\r\n$1\r\nk\r\n:1\r\n",
+    )?;
+    // Malformed bytes are not written to the client.
+    check(
+        &mut stream,
+        b"*2\r\n:1\r\n",
+        b"-ERR incomplete RESP value\r\n",
+    )?;
+
+    // Switch to RESP3, skipping the HELLO reply up to the PONG that follows it.
+    stream.write_all(b"HELLO 3\r\nPING\r\n")?;
+    let mut skipped = Vec::new();
+    while !skipped.ends_with(b"+PONG\r\n") {
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte)?;
+        skipped.push(byte[0]);
+    }
+    for resp in [
+        &b"%1\r\n$1\r\nk\r\n#t\r\n"[..],
+        b",1.5\r\n",
+        b"(12345678901234567890\r\n",
+        b"=7\r\ntxt:abc\r\n",
+        b"_\r\n",
+    ] {
+        check(&mut stream, resp, resp)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_with() -> Result<()> {
+    let port: u16 = 6541;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: i64 = redis::cmd("reply.with")
+        .arg("42")
+        .query(&mut con)
+        .with_context(|| "failed to run reply.with")?;
+    assert_eq!(res, 42);
+
+    // An error with a code keeps it, others get the ERR code.
+    let err = redis::cmd("reply.with")
+        .arg("")
+        .query::<i64>(&mut con)
+        .unwrap_err();
+    assert_eq!(err.code(), Some("EMPTY"));
+    let err = redis::cmd("reply.with")
+        .arg("4\r\n2")
+        .query::<i64>(&mut con)
+        .unwrap_err();
+    assert_eq!(err.code(), Some("ERR"));
+    assert!(err.to_string().contains("'4  2' is not a number"), "{err}");
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_double_fmt() -> Result<()> {
+    fn check(stream: &mut TcpStream, cmd: &str, expected: &[u8]) -> Result<()> {
+        stream.write_all(format!("{cmd}\r\n").as_bytes())?;
+        let mut res = vec![0; expected.len()];
+        stream.read_exact(&mut res)?;
+        assert_eq!(res, expected, "wrong reply bytes for '{cmd}'");
+        Ok(())
+    }
+
+    let port: u16 = 6522;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    // wait for the server to accept connections
+    get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    check(&mut stream, "reply.double 1.5 2", b"$4\r\n1.50\r\n")?;
+    check(&mut stream, "reply.double 0.125 2", b"$4\r\n0.12\r\n")?;
+    check(&mut stream, "reply.double 0.375 2", b"$4\r\n0.38\r\n")?;
+    check(&mut stream, "reply.double 2.5 0", b"$1\r\n2\r\n")?;
+
+    // Switch to RESP3, skipping the HELLO reply up to the PONG that follows it.
+    stream.write_all(b"HELLO 3\r\nPING\r\n")?;
+    let mut skipped = Vec::new();
+    while !skipped.ends_with(b"+PONG\r\n") {
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte)?;
+        skipped.push(byte[0]);
+    }
+    check(&mut stream, "reply.double 1.5 2", b",1.5\r\n")?;
+    check(&mut stream, "reply.double 1.2534 2", b",1.25\r\n")?;
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_bool_as_int() -> Result<()> {
+    fn check(stream: &mut TcpStream, cmd: &str, expected: &[u8]) -> Result<()> {
+        stream.write_all(format!("{cmd}\r\n").as_bytes())?;
+        let mut res = vec![0; expected.len()];
+        stream.read_exact(&mut res)?;
+        assert_eq!(res, expected, "wrong reply bytes for '{cmd}'");
+        Ok(())
+    }
+
+    let port: u16 = 6531;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    // wait for the server to accept connections
+    get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    check(&mut stream, "reply.bool int 1", b":1\r\n")?;
+    check(&mut stream, "reply.bool native 1", b":1\r\n")?;
+    check(&mut stream, "reply.bool native 0", b":0\r\n")?;
+
+    // Switch to RESP3, skipping the HELLO reply up to the PONG that follows it.
+    stream.write_all(b"HELLO 3\r\nPING\r\n")?;
+    let mut skipped = Vec::new();
+    while !skipped.ends_with(b"+PONG\r\n") {
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte)?;
+        skipped.push(byte[0]);
+    }
+    check(&mut stream, "reply.bool int 1", b":1\r\n")?;
+    check(&mut stream, "reply.bool int 0", b":0\r\n")?;
+    check(&mut stream, "reply.bool native 1", b"#t\r\n")?;
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_null_array() -> Result<()> {
+    let port: u16 = 6523;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    // wait for the server to accept connections
+    get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    stream.write_all(b"reply.null_array\r\nBLPOP missing 0.01\r\n")?;
+    let mut res = [0; 10];
+    stream.read_exact(&mut res)?;
+    // Same null array as the BLPOP timeout, not a null bulk string (`$-1`).
+    assert_eq!(&res, b"*-1\r\n*-1\r\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_reply_deferred() -> Result<()> {
+    fn check(stream: &mut TcpStream, cmd: &str, expected: &[u8]) -> Result<()> {
+        stream.write_all(format!("{cmd}\r\n").as_bytes())?;
+        let mut res = vec![0; expected.len()];
+        stream.read_exact(&mut res)?;
+        assert_eq!(res, expected, "wrong reply bytes for '{cmd}'");
+        Ok(())
+    }
+
+    let port: u16 = 6528;
+    let _guards = vec![start_redis_server_with_module("response", port)
+        .with_context(|| "failed to start redis server")?];
+    // wait for the server to accept connections
+    get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    check(&mut stream, "reply.deferred array 2", b"*2\r\n:0\r\n:1\r\n")?;
+    check(&mut stream, "reply.deferred set 2", b"*2\r\n:0\r\n:1\r\n")?;
+    // RESP2 clients get the map as a flat array, twice as long.
+    check(
+        &mut stream,
+        "reply.deferred map 2",
+        b"*4\r\n:0\r\n:0\r\n:1\r\n:1\r\n",
+    )?;
+    check(&mut stream, "reply.deferred map 0", b"*0\r\n")?;
+
+    // Switch to RESP3, skipping the HELLO reply up to the PONG that follows it.
+    stream.write_all(b"HELLO 3\r\nPING\r\n")?;
+    let mut skipped = Vec::new();
+    while !skipped.ends_with(b"+PONG\r\n") {
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte)?;
+        skipped.push(byte[0]);
+    }
+    check(&mut stream, "reply.deferred array 2", b"*2\r\n:0\r\n:1\r\n")?;
+    check(&mut stream, "reply.deferred set 2", b"~2\r\n:0\r\n:1\r\n")?;
+    check(
+        &mut stream,
+        "reply.deferred map 3",
+        b"%3\r\n:0\r\n:0\r\n:1\r\n:1\r\n:2\r\n:4\r\n",
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn test_serde_reply() -> Result<()> {
+    let port: u16 = 6505;
+    let _guards = vec![start_redis_server_with_module("serde_reply", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let bulk = |s: &str| Value::Data(s.as_bytes().to_vec());
+
+    // RESP2 clients get maps as flat arrays, in field order.
+    let res: Value = redis::cmd("serde.user")
+        .arg(&["guest"])
+        .query(&mut con)
+        .with_context(|| "failed to run serde.user")?;
+    assert_eq!(
+        res,
+        Value::Bulk(vec![
+            bulk("name"),
+            bulk("guest"),
+            bulk("age"),
+            Value::Int(42),
+            bulk("tags"),
+            Value::Bulk(vec![bulk("a"), bulk("b")]),
+            bulk("nickname"),
+            Value::Nil,
+            bulk("role"),
+            Value::Bulk(vec![
+                bulk("Guest"),
+                Value::Bulk(vec![bulk("expires"), Value::Int(60)]),
+            ]),
+        ])
+    );
+
+    let res: Vec<Value> = redis::cmd("serde.user")
+        .arg(&["admin"])
+        .query(&mut con)
+        .with_context(|| "failed to run serde.user")?;
+    assert_eq!(res[9], bulk("Admin"));
+
     Ok(())
 }
 
@@ -743,3 +2353,97 @@ fn test_expire() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_key_exists() -> Result<()> {
+    let port: u16 = 6517;
+    let _guards = vec![start_redis_server_with_module("expire", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    let res: Vec<i64> = redis::cmd("expire.exists").arg(&["key"]).query(&mut con)?;
+    assert_eq!(res, vec![0, 0]);
+
+    redis::cmd("set")
+        .arg(&["key", "value"])
+        .query(&mut con)
+        .with_context(|| "failed to run set")?;
+    let res: Vec<i64> = redis::cmd("expire.exists").arg(&["key"]).query(&mut con)?;
+    assert_eq!(res, vec![1, 1]);
+
+    // Keep the key in the keyspace after it expires.
+    redis::cmd("debug")
+        .arg(&["set-active-expire", "0"])
+        .query(&mut con)
+        .with_context(|| "failed to run debug set-active-expire")?;
+    redis::cmd("pexpire")
+        .arg(&["key", "1"])
+        .query(&mut con)
+        .with_context(|| "failed to run pexpire")?;
+    thread::sleep(Duration::from_millis(10));
+
+    let res: Vec<i64> = redis::cmd("expire.exists").arg(&["key"]).query(&mut con)?;
+    assert_eq!(res, vec![0, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_key_idle_time_and_freq() -> Result<()> {
+    let port: u16 = 6511;
+    let _guards = vec![start_redis_server_with_module("expire", port)
+        .with_context(|| "failed to start redis server")?];
+    let mut con =
+        get_redis_connection(port).with_context(|| "failed to connect to redis server")?;
+
+    redis::cmd("set")
+        .arg(&["key", "value"])
+        .query(&mut con)
+        .with_context(|| "failed to run set")?;
+
+    // Keys are only evicted with a memory limit.
+    let res: String = redis::cmd("expire.eviction")
+        .arg(&["key"])
+        .query(&mut con)?;
+    assert_eq!(res, "noeviction");
+    redis::cmd("config")
+        .arg(&["set", "maxmemory", "1gb"])
+        .query(&mut con)
+        .with_context(|| "failed to run config set")?;
+
+    for (policy, tracked, not_tracked, metric) in [
+        ("allkeys-lru", "expire.idle", "expire.freq", "lru"),
+        ("allkeys-lfu", "expire.freq", "expire.idle", "lfu"),
+    ] {
+        redis::cmd("config")
+            .arg(&["set", "maxmemory-policy", policy])
+            .query(&mut con)
+            .with_context(|| "failed to run config set")?;
+
+        let res: i64 = redis::cmd(tracked).arg(&["key"]).query(&mut con)?;
+        assert!(res >= 0);
+
+        let res: RedisResult<i64> = redis::cmd(not_tracked).arg(&["key"]).query(&mut con);
+        assert!(res.unwrap_err().to_string().contains("maxmemory-policy"));
+
+        let res: String = redis::cmd("expire.eviction")
+            .arg(&["key"])
+            .query(&mut con)?;
+        assert_eq!(res, metric);
+    }
+
+    redis::cmd("config")
+        .arg(&["set", "maxmemory-policy", "noeviction"])
+        .query(&mut con)
+        .with_context(|| "failed to run config set")?;
+    let res: String = redis::cmd("expire.eviction")
+        .arg(&["key"])
+        .query(&mut con)?;
+    assert_eq!(res, "noeviction");
+
+    let res: RedisResult<i64> = redis::cmd("expire.freq").arg(&["missing"]).query(&mut con);
+    assert!(res.is_err());
+
+    Ok(())
+}